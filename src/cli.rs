@@ -0,0 +1,341 @@
+use gio::prelude::*;
+use glib::translate::ToGlibPtr;
+use glib::{OptionArg, OptionFlags};
+use libadwaita::Application;
+use std::cell::RefCell;
+use std::ffi::CString;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::Duration;
+
+use crate::AppState;
+use crate::config::{ListeningSettings, OpenAction};
+use crate::library::MusicLibrary;
+use crate::ui::song_list::format_duration;
+
+// Opções que só fazem sentido com o Librefy já aberto
+const REMOTE_OPTIONS: [&str; 8] = [
+    "play-pause", "next", "previous", "stop", "volume", "seek", "status", "scan",
+];
+
+pub fn add_options(app: &Application) {
+    let flag = |name: &str, description: &str| {
+        app.add_main_option(name, glib::Char::from(0), OptionFlags::NONE, OptionArg::None, description, None);
+    };
+    flag("play-pause", "Toggle playback in the running instance");
+    flag("next", "Skip to the next track");
+    flag("previous", "Go back to the previous track");
+    flag("stop", "Stop playback");
+    flag("enqueue", "Add the given files to the end of the queue instead of playing them");
+    flag("status", "Show what the running instance is playing");
+    app.add_main_option(
+        "volume",
+        glib::Char::from(0),
+        OptionFlags::NONE,
+        OptionArg::Int,
+        "Set the volume (0-100)",
+        Some("PERCENT"),
+    );
+    app.add_main_option(
+        "seek",
+        glib::Char::from(0),
+        OptionFlags::NONE,
+        OptionArg::String,
+        "Seek to a position, or by an offset with + or -",
+        Some("[+|-]SECONDS|MM:SS"),
+    );
+    app.add_main_option(
+        "scan",
+        glib::Char::from(0),
+        OptionFlags::NONE,
+        OptionArg::String,
+        "Scan a folder into the library (without a window if Librefy is not running)",
+        Some("DIR"),
+    );
+}
+
+// Roda no processo que foi chamado, antes de falar com a instância principal
+pub fn handle_local_options(app: &Application, options: &glib::VariantDict) -> i32 {
+    if !REMOTE_OPTIONS.iter().any(|name| options.contains(name)) {
+        return -1;
+    }
+
+    // A pasta é relativa ao diretório de quem chamou
+    let scan_dir = options.lookup::<String>("scan").ok().flatten().map(|dir| absolute(Path::new(&dir)));
+    if let Some(ref dir) = scan_dir {
+        options.insert_value("scan", &dir.to_string_lossy().to_variant());
+    }
+
+    if let Err(e) = app.register(gio::Cancellable::NONE) {
+        eprintln!("Failed to register application: {}", e);
+        return 1;
+    }
+    if app.is_remote() {
+        return -1;
+    }
+
+    match scan_dir {
+        Some(dir) => match scan_headless(&dir) {
+            Ok(()) => 0,
+            Err(e) => {
+                eprintln!("Failed to scan {}: {}", dir.display(), e);
+                1
+            }
+        },
+        None => {
+            eprintln!("Librefy is not running");
+            1
+        }
+    }
+}
+
+fn absolute(path: &Path) -> PathBuf {
+    std::env::current_dir().map(|cwd| cwd.join(path)).unwrap_or_else(|_| path.to_path_buf())
+}
+
+fn scan_headless(dir: &Path) -> anyhow::Result<()> {
+    let dir = dir.to_str().ok_or_else(|| anyhow::anyhow!("Invalid folder name"))?;
+    let mut library = MusicLibrary::load();
+    library.sync_rating_tags = ListeningSettings::load().sync_rating_tags;
+    let previous_count = library.songs.len();
+    library.scan_directory(dir)?;
+    library.save()?;
+    println!(
+        "Added {} songs ({} in library)",
+        library.songs.len() - previous_count,
+        library.songs.len()
+    );
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum SeekTarget {
+    To(Duration),
+    Forward(Duration),
+    Backward(Duration),
+}
+
+// "90", "1:30", "+10" ou "-10"
+fn parse_seek(text: &str) -> Option<SeekTarget> {
+    let text = text.trim();
+    let (kind, rest): (fn(Duration) -> SeekTarget, &str) = match text.chars().next()? {
+        '+' => (SeekTarget::Forward, &text[1..]),
+        '-' => (SeekTarget::Backward, &text[1..]),
+        _ => (SeekTarget::To, text),
+    };
+    // Sinal só no começo; NaN, infinito e valores grandes demais são recusados
+    let seconds = |text: &str| {
+        let text = text.strip_prefix(['+', '-']).is_none().then_some(text)?;
+        Duration::try_from_secs_f64(text.parse::<f64>().ok()?).ok()
+    };
+    if rest.starts_with(['+', '-']) {
+        return None;
+    }
+    let time = match rest.split_once(':') {
+        Some((minutes, rest)) => {
+            let minutes = minutes.parse::<u64>().ok()?.checked_mul(60)?;
+            Duration::from_secs(minutes).checked_add(seconds(rest)?)?
+        }
+        None => seconds(rest)?,
+    };
+    Some(kind(time))
+}
+
+// Uma chamada de `librefy ...`, já interpretada
+#[derive(Debug, Default)]
+pub struct Request {
+    files: Vec<PathBuf>,
+    enqueue: bool,
+    play_pause: bool,
+    next: bool,
+    previous: bool,
+    stop: bool,
+    volume: Option<i32>,
+    seek: Option<String>,
+    status: bool,
+    scan: Option<String>,
+}
+
+impl Request {
+    pub fn parse(command_line: &gio::ApplicationCommandLine) -> Self {
+        let options = command_line.options_dict();
+        let flag = |name: &str| options.contains(name);
+        // O primeiro argumento é o próprio executável; caminhos relativos usam o cwd de quem chamou
+        let files = command_line
+            .arguments()
+            .iter()
+            .skip(1)
+            .filter_map(|arg| command_line.create_file_for_arg(arg).path())
+            .collect();
+        Self {
+            files,
+            enqueue: flag("enqueue"),
+            play_pause: flag("play-pause"),
+            next: flag("next"),
+            previous: flag("previous"),
+            stop: flag("stop"),
+            volume: options.lookup("volume").ok().flatten(),
+            seek: options.lookup("seek").ok().flatten(),
+            status: flag("status"),
+            scan: options.lookup("scan").ok().flatten(),
+        }
+    }
+
+    pub fn opens_files(&self) -> bool {
+        !self.files.is_empty()
+    }
+
+    // Sem nada para fazer: só abre (ou traz para frente) a janela
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+            && !(self.play_pause || self.next || self.previous || self.stop || self.status)
+            && self.volume.is_none()
+            && self.seek.is_none()
+            && self.scan.is_none()
+    }
+}
+
+// A binding do GIO 0.18 ainda não expõe g_application_command_line_print
+fn print(command_line: &gio::ApplicationCommandLine, text: &str, error: bool) {
+    let Ok(text) = CString::new(format!("{}\n", text)) else {
+        return;
+    };
+    let format = b"%s\0".as_ptr().cast();
+    unsafe {
+        if error {
+            gio::ffi::g_application_command_line_printerr(command_line.to_glib_none().0, format, text.as_ptr());
+        } else {
+            gio::ffi::g_application_command_line_print(command_line.to_glib_none().0, format, text.as_ptr());
+        }
+    }
+}
+
+// Executa na instância principal; a saída aparece no terminal de quem chamou
+pub fn run(state: &Rc<RefCell<AppState>>, request: &Request, command_line: &gio::ApplicationCommandLine) -> i32 {
+    let mut status = 0;
+    let mut fail = |message: &str| {
+        print(command_line, message, true);
+        status = 1;
+    };
+
+    if let Some(ref dir) = request.scan {
+        match crate::scan_music_folder(state, dir) {
+            Ok(added) => print(command_line, &format!("Added {} songs", added), false),
+            Err(e) => fail(&format!("Failed to scan {}: {}", dir, e)),
+        }
+    }
+
+    if !request.files.is_empty() {
+        let action = if request.enqueue { OpenAction::Enqueue } else { state.borrow().opening.action };
+        // As tags são lidas em segundo plano; quem chamou espera até o fim para ver os erros
+        let command_line = command_line.clone();
+        crate::open_files(state, request.files.clone(), action, move |errors| {
+            for error in &errors {
+                print(&command_line, error, true);
+            }
+            if !errors.is_empty() {
+                command_line.set_exit_status(1);
+            }
+        });
+    }
+
+    if request.play_pause {
+        crate::toggle_playback(state);
+    }
+    if request.previous && !crate::play_previous_track(state) {
+        fail("No previous track");
+    }
+    if request.next && !crate::play_next_track(state) {
+        fail("Queue is empty");
+    }
+    if request.stop {
+        crate::stop_playback(state);
+    }
+
+    if let Some(volume) = request.volume {
+        match state.borrow().player {
+            Some(ref player) => player.set_volume(f64::from(volume.clamp(0, 100)) / 100.0),
+            None => fail("Player is not available"),
+        }
+    }
+
+    if let Some(ref text) = request.seek {
+        match parse_seek(text) {
+            Some(target) => {
+                if let Err(e) = seek(state, target) {
+                    fail(&e);
+                }
+            }
+            None => fail(&format!("Invalid position: {}", text)),
+        }
+    }
+
+    if request.status {
+        print(command_line, &status_text(&state.borrow()), false);
+    }
+    status
+}
+
+fn seek(state: &Rc<RefCell<AppState>>, target: SeekTarget) -> Result<(), String> {
+    let mut app_state = state.borrow_mut();
+    let length = app_state.current_song.as_ref().and_then(|song| song.duration).map(Duration::from_secs);
+    let Some(ref mut player) = app_state.player else {
+        return Err("Player is not available".to_string());
+    };
+    let position = player.position().ok_or("Nothing is playing")?;
+    let target = match target {
+        SeekTarget::To(time) => time,
+        SeekTarget::Forward(offset) => position.checked_add(offset).ok_or("Seek target is out of range")?,
+        SeekTarget::Backward(offset) => position.saturating_sub(offset),
+    };
+    let target = length.map_or(target, |length| target.min(length));
+    player.seek(target).map_err(|e| e.to_string())
+}
+
+fn status_text(app_state: &AppState) -> String {
+    let player = app_state.player.as_ref();
+    let position = player.and_then(|p| p.position());
+    let status = if player.is_some_and(|p| p.is_playing()) {
+        "Playing"
+    } else if position.is_some() {
+        "Paused"
+    } else {
+        "Stopped"
+    };
+
+    let mut lines = vec![format!("Status: {}", status)];
+    if let Some(ref song) = app_state.current_song {
+        lines.push(format!("Track: {}", song.get_display_name()));
+        lines.push(format!("Album: {}", song.album));
+        let elapsed = format_duration(position.unwrap_or_default().as_secs());
+        let total = song.duration.map_or_else(|| "?".to_string(), format_duration);
+        lines.push(format!("Position: {} / {}", elapsed, total));
+    }
+    if let Some(player) = player {
+        lines.push(format!("Volume: {}%", (player.volume() * 100.0).round()));
+    }
+    lines.push(format!("Queue: {} upcoming", app_state.queue.upcoming().len()));
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_seek_targets() {
+        assert_eq!(parse_seek("90"), Some(SeekTarget::To(Duration::from_secs(90))));
+        assert_eq!(parse_seek(" 1:30.5 "), Some(SeekTarget::To(Duration::from_millis(90_500))));
+        assert_eq!(parse_seek("+10"), Some(SeekTarget::Forward(Duration::from_secs(10))));
+        assert_eq!(parse_seek("-0:05"), Some(SeekTarget::Backward(Duration::from_secs(5))));
+    }
+
+    #[test]
+    fn rejects_invalid_seeks() {
+        for text in ["", "+", "abc", "++5", "+-5", "1:-5", "1:+5", "-1:30:00", "NaN", "inf", "1e30", "1:inf"] {
+            assert_eq!(parse_seek(text), None, "{:?}", text);
+        }
+        assert_eq!(parse_seek(&format!("{}:00", u64::MAX)), None);
+        assert_eq!(parse_seek(&format!("{}:{}", u64::MAX / 60, u64::MAX)), None);
+    }
+}
@@ -0,0 +1,411 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use anyhow::Result;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+// $XDG_CONFIG_HOME/librefy
+pub fn config_dir() -> PathBuf {
+    glib::user_config_dir().join("librefy")
+}
+
+// $XDG_DATA_HOME/librefy, para dados que não são preferências (biblioteca, histórico)
+pub fn data_dir() -> PathBuf {
+    glib::user_data_dir().join("librefy")
+}
+
+pub fn load_json<T: DeserializeOwned>(name: &str) -> Option<T> {
+    let path = config_dir().join(name);
+    let contents = fs::read_to_string(&path).ok()?;
+    match serde_json::from_str(&contents) {
+        Ok(value) => Some(value),
+        Err(e) => {
+            eprintln!("Ignoring invalid config file {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+pub fn save_json<T: Serialize>(name: &str, value: &T) -> Result<()> {
+    let dir = config_dir();
+    fs::create_dir_all(&dir)?;
+    fs::write(dir.join(name), serde_json::to_string_pretty(value)?)?;
+    Ok(())
+}
+
+// Pasta de músicas do XDG (~/Music se não estiver configurada)
+pub fn default_music_dir() -> PathBuf {
+    glib::user_special_dir(glib::UserDirectory::Music).unwrap_or_else(|| glib::home_dir().join("Music"))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AudioOutput {
+    #[default]
+    Auto,
+    PulseAudio,
+    PipeWire,
+    Alsa,
+}
+
+impl AudioOutput {
+    pub const ALL: [AudioOutput; 4] = [
+        AudioOutput::Auto,
+        AudioOutput::PulseAudio,
+        AudioOutput::PipeWire,
+        AudioOutput::Alsa,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            AudioOutput::Auto => "Automatic",
+            AudioOutput::PulseAudio => "PulseAudio",
+            AudioOutput::PipeWire => "PipeWire",
+            AudioOutput::Alsa => "ALSA",
+        }
+    }
+
+    // Elemento de saída do GStreamer
+    pub fn element(&self) -> &'static str {
+        match self {
+            AudioOutput::Auto => "autoaudiosink",
+            AudioOutput::PulseAudio => "pulsesink",
+            AudioOutput::PipeWire => "pipewiresink",
+            AudioOutput::Alsa => "alsasink",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ColorScheme {
+    #[default]
+    System,
+    Light,
+    Dark,
+}
+
+impl ColorScheme {
+    pub const ALL: [ColorScheme; 3] = [ColorScheme::System, ColorScheme::Light, ColorScheme::Dark];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ColorScheme::System => "Follow System",
+            ColorScheme::Light => "Light",
+            ColorScheme::Dark => "Dark",
+        }
+    }
+}
+
+// Preferências gerais; as de cada recurso ficam nos próprios arquivos
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AppSettings {
+    pub library_folders: Vec<PathBuf>,
+    pub scan_on_startup: bool,
+    pub audio_output: AudioOutput,
+    // Volta a tocar de onde parou ao abrir, se estava tocando ao fechar
+    pub resume_playback: bool,
+    pub color_scheme: ColorScheme,
+    // Nome de um arquivo em $XDG_DATA_HOME/librefy/themes; None = tema embutido
+    pub theme: Option<String>,
+    pub accent_color: Option<String>,
+    pub accent_from_cover: bool,
+    pub remember_window_size: bool,
+    pub window_width: i32,
+    pub window_height: i32,
+    pub window_maximized: bool,
+    pub mini_width: i32,
+    pub mini_height: i32,
+    // Só no X11; no Wayland o compositor escolhe onde abrir
+    pub mini_position: Option<(i32, i32)>,
+}
+
+impl Default for AppSettings {
+    fn default() -> Self {
+        Self {
+            library_folders: vec![default_music_dir()],
+            scan_on_startup: false,
+            audio_output: AudioOutput::Auto,
+            resume_playback: false,
+            color_scheme: ColorScheme::System,
+            theme: None,
+            accent_color: None,
+            accent_from_cover: false,
+            remember_window_size: true,
+            window_width: 1200,
+            window_height: 800,
+            window_maximized: false,
+            mini_width: 420,
+            mini_height: 140,
+            mini_position: None,
+        }
+    }
+}
+
+impl AppSettings {
+    pub const FILE_NAME: &'static str = "settings.json";
+
+    pub fn window_size(&self) -> (i32, i32) {
+        (self.window_width, self.window_height)
+    }
+
+    pub fn load() -> Self {
+        load_json(Self::FILE_NAME).unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        if let Err(e) = save_json(Self::FILE_NAME, self) {
+            eprintln!("Failed to save settings: {}", e);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ColumnState {
+    pub id: String,
+    pub visible: bool,
+    pub width: i32, // -1 = largura automática
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct TrackTableLayout {
+    pub columns: Vec<ColumnState>,
+    pub sort_column: Option<String>,
+    pub sort_descending: bool,
+}
+
+impl TrackTableLayout {
+    pub const FILE_NAME: &'static str = "track_table.json";
+
+    pub fn load() -> Self {
+        load_json(Self::FILE_NAME).unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        if let Err(e) = save_json(Self::FILE_NAME, self) {
+            eprintln!("Failed to save track table layout: {}", e);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ListeningSettings {
+    // Conta uma reprodução ao passar da porcentagem ou dos segundos, o que vier primeiro
+    pub count_after_percent: u8,
+    pub count_after_seconds: u64,
+    // Lê e grava nota/contagem nas tags POPM e FMPS_*
+    pub sync_rating_tags: bool,
+}
+
+impl Default for ListeningSettings {
+    fn default() -> Self {
+        Self {
+            count_after_percent: 50,
+            count_after_seconds: 240,
+            sync_rating_tags: false,
+        }
+    }
+}
+
+impl ListeningSettings {
+    pub const FILE_NAME: &'static str = "listening.json";
+
+    pub fn load() -> Self {
+        load_json(Self::FILE_NAME).unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        if let Err(e) = save_json(Self::FILE_NAME, self) {
+            eprintln!("Failed to save listening settings: {}", e);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OpenAction {
+    #[default]
+    Play,
+    Enqueue,
+}
+
+// Arquivos abertos pelo gerenciador de arquivos, linha de comando ou arrastados para a janela
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OpenSettings {
+    pub action: OpenAction,
+    pub add_to_library: bool,
+}
+
+impl Default for OpenSettings {
+    fn default() -> Self {
+        Self {
+            action: OpenAction::Play,
+            add_to_library: true,
+        }
+    }
+}
+
+impl OpenSettings {
+    pub const FILE_NAME: &'static str = "opening.json";
+
+    pub fn load() -> Self {
+        load_json(Self::FILE_NAME).unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        if let Err(e) = save_json(Self::FILE_NAME, self) {
+            eprintln!("Failed to save open settings: {}", e);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NotificationSettings {
+    // Avisa quando começa uma nova música (não com a janela em foco)
+    pub track_change: bool,
+}
+
+impl Default for NotificationSettings {
+    fn default() -> Self {
+        Self { track_change: true }
+    }
+}
+
+impl NotificationSettings {
+    pub const FILE_NAME: &'static str = "notifications.json";
+
+    pub fn load() -> Self {
+        load_json(Self::FILE_NAME).unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        if let Err(e) = save_json(Self::FILE_NAME, self) {
+            eprintln!("Failed to save notification settings: {}", e);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ScrobbleService {
+    #[default]
+    ListenBrainz,
+    LastFm,
+}
+
+impl ScrobbleService {
+    pub fn default_url(&self) -> &'static str {
+        match self {
+            ScrobbleService::ListenBrainz => "https://api.listenbrainz.org",
+            ScrobbleService::LastFm => "https://ws.audioscrobbler.com/2.0/",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ScrobbleSettings {
+    pub enabled: bool,
+    pub service: ScrobbleService,
+    // Vazio = servidor oficial do serviço; pode apontar para um mock ou instância própria
+    pub api_url: String,
+    // ListenBrainz
+    pub token: String,
+    // Last.fm e compatíveis (Libre.fm etc.)
+    pub api_key: String,
+    pub api_secret: String,
+    pub session_key: String,
+}
+
+impl ScrobbleSettings {
+    pub const FILE_NAME: &'static str = "scrobbling.json";
+
+    pub fn load() -> Self {
+        load_json(Self::FILE_NAME).unwrap_or_default()
+    }
+
+    pub fn save(&self) {
+        if let Err(e) = save_json(Self::FILE_NAME, self) {
+            eprintln!("Failed to save scrobbling settings: {}", e);
+        }
+    }
+
+    pub fn url(&self) -> &str {
+        if self.api_url.trim().is_empty() {
+            self.service.default_url()
+        } else {
+            self.api_url.trim()
+        }
+    }
+}
+
+pub const EQ_BANDS: usize = 10;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EqualizerPreset {
+    pub name: String,
+    pub preamp: f64,           // dB
+    pub bands: [f64; EQ_BANDS], // dB, -24 a +12
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EqualizerSettings {
+    pub enabled: bool,
+    pub preamp: f64,
+    pub bands: [f64; EQ_BANDS],
+    // Nome do preset ativo; None depois de mexer nas bandas à mão
+    pub preset: Option<String>,
+    pub user_presets: Vec<EqualizerPreset>,
+    pub auto_by_album: bool,
+    pub auto_by_genre: bool,
+    // Álbum / gênero (minúsculo) -> nome do preset
+    pub album_presets: HashMap<String, String>,
+    pub genre_presets: HashMap<String, String>,
+    // Ajuste manual (pré-amplificação, bandas, preset) enquanto vale um preset automático
+    #[serde(skip)]
+    pub manual: Option<(f64, [f64; EQ_BANDS], Option<String>)>,
+}
+
+impl Default for EqualizerSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            preamp: 0.0,
+            bands: [0.0; EQ_BANDS],
+            preset: Some("Flat".to_string()),
+            user_presets: Vec::new(),
+            auto_by_album: false,
+            auto_by_genre: false,
+            album_presets: HashMap::new(),
+            genre_presets: HashMap::new(),
+            manual: None,
+        }
+    }
+}
+
+impl EqualizerSettings {
+    pub const FILE_NAME: &'static str = "equalizer.json";
+
+    pub fn load() -> Self {
+        load_json(Self::FILE_NAME).unwrap_or_default()
+    }
+
+    // Grava o ajuste manual, não o preset automático da música atual
+    pub fn save(&self) {
+        let mut settings = self.clone();
+        if let Some((preamp, bands, preset)) = settings.manual.take() {
+            settings.preamp = preamp;
+            settings.bands = bands;
+            settings.preset = preset;
+        }
+        if let Err(e) = save_json(Self::FILE_NAME, &settings) {
+            eprintln!("Failed to save equalizer settings: {}", e);
+        }
+    }
+}
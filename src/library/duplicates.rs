@@ -0,0 +1,730 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use anyhow::{anyhow, Result};
+use gio::prelude::*;
+
+use super::search::normalize;
+use super::{MusicLibrary, Song};
+
+// Durações que diferem até isso ainda contam como a mesma gravação
+const DURATION_TOLERANCE_SECS: u64 = 2;
+// Fingerprints do Chromaprint: 1.0 = idênticos, ~0.5 = sem relação
+const FINGERPRINT_THRESHOLD: f64 = 0.85;
+const FINGERPRINT_MAX_OFFSET: usize = 3;
+const FINGERPRINT_MIN_FRAMES: usize = 30;
+const FINGERPRINT_SECONDS: &str = "120";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DuplicateMethod {
+    Tags,
+    AudioHash,
+    Fingerprint,
+}
+
+impl DuplicateMethod {
+    pub fn label(&self) -> &'static str {
+        match self {
+            DuplicateMethod::Tags => "Same tags and duration",
+            DuplicateMethod::AudioHash => "Identical audio",
+            DuplicateMethod::Fingerprint => "Sounds the same",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DuplicateGroup {
+    pub songs: Vec<Song>, // a melhor cópia primeiro
+    pub reasons: Vec<DuplicateMethod>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct DuplicateReport {
+    pub groups: Vec<DuplicateGroup>,
+    pub errors: Vec<(PathBuf, String)>,
+}
+
+// Sem perdas > formatos com perdas modernos > MP3/AAC, depois bitrate e tamanho
+pub fn quality_key(song: &Song) -> (u8, u32, u64) {
+    let ext = song
+        .file_path
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    let format_rank = match ext.as_str() {
+        "flac" | "wav" => 3,
+        // M4A pode ser ALAC (sem perdas) ou AAC; só o codec diz qual
+        "m4a" | "mp4" => match mp4_file_codec(&song.file_path) {
+            Some(codec) if &codec == b"alac" => 3,
+            _ => 2,
+        },
+        "ogg" => 2,
+        "mp3" | "aac" => 1,
+        _ => 0,
+    };
+    (format_rank, song.bitrate.unwrap_or(0), song.file_size)
+}
+
+struct UnionFind {
+    parent: Vec<usize>,
+}
+
+impl UnionFind {
+    fn new(size: usize) -> Self {
+        Self {
+            parent: (0..size).collect(),
+        }
+    }
+
+    fn find(&mut self, mut i: usize) -> usize {
+        while self.parent[i] != i {
+            self.parent[i] = self.parent[self.parent[i]];
+            i = self.parent[i];
+        }
+        i
+    }
+
+    fn union(&mut self, a: usize, b: usize) {
+        let (a, b) = (self.find(a), self.find(b));
+        if a != b {
+            self.parent[b] = a;
+        }
+    }
+}
+
+fn tag_key(song: &Song) -> String {
+    let clean = |text: &str| normalize(text).split_whitespace().collect::<Vec<_>>().join(" ");
+    format!("{}\u{0}{}", clean(&song.artist), clean(&song.title))
+}
+
+// Agrupa por artista + título normalizados e depois por durações próximas
+fn tag_clusters(songs: &[Song]) -> Vec<Vec<usize>> {
+    let mut by_key: HashMap<String, Vec<usize>> = HashMap::new();
+    for (i, song) in songs.iter().enumerate() {
+        by_key.entry(tag_key(song)).or_default().push(i);
+    }
+
+    let mut clusters = Vec::new();
+    for mut indices in by_key.into_values().filter(|v| v.len() > 1) {
+        indices.sort_by_key(|&i| songs[i].duration);
+        let mut current = vec![indices[0]];
+        for pair in indices.windows(2) {
+            let close = match (songs[pair[0]].duration, songs[pair[1]].duration) {
+                (Some(a), Some(b)) => b - a <= DURATION_TOLERANCE_SECS,
+                (None, None) => true,
+                _ => false,
+            };
+            if !close {
+                clusters.push(std::mem::take(&mut current));
+            }
+            current.push(pair[1]);
+        }
+        clusters.push(current);
+    }
+    clusters.retain(|c| c.len() > 1);
+    clusters
+}
+
+fn syncsafe(bytes: &[u8]) -> usize {
+    bytes.iter().fold(0, |acc, b| (acc << 7) | (*b as usize & 0x7f))
+}
+
+// Pula tags ID3v2 no início e ID3v1/APEv2 no fim (MP3, AAC e afins)
+fn untagged_range(data: &[u8]) -> (usize, usize) {
+    let mut start = 0;
+    while data.len() >= start + 10 && &data[start..start + 3] == b"ID3" {
+        let footer = if data[start + 5] & 0x10 != 0 { 10 } else { 0 };
+        start += 10 + syncsafe(&data[start + 6..start + 10]) + footer;
+    }
+
+    let mut end = data.len();
+    if end >= start + 128 && &data[end - 128..end - 125] == b"TAG" {
+        end -= 128;
+    }
+    if end >= start + 32 && &data[end - 32..end - 24] == b"APETAGEX" {
+        let footer = &data[end - 32..end];
+        let size = u32::from_le_bytes([footer[12], footer[13], footer[14], footer[15]]) as usize;
+        let has_header = footer[23] & 0x80 != 0;
+        let total = size + if has_header { 32 } else { 0 };
+        end = end.saturating_sub(total).max(start);
+    }
+
+    (start.min(end), end)
+}
+
+fn flac_audio(data: &[u8]) -> Option<&[u8]> {
+    let (start, end) = untagged_range(data);
+    let data = &data[start..end];
+    if !data.starts_with(b"fLaC") {
+        return None;
+    }
+    let mut pos = 4;
+    loop {
+        let header = data.get(pos..pos + 4)?;
+        let last = header[0] & 0x80 != 0;
+        let length = u32::from_be_bytes([0, header[1], header[2], header[3]]) as usize;
+        pos += 4 + length;
+        if last {
+            return data.get(pos..);
+        }
+    }
+}
+
+// Só o conteúdo dos átomos "mdat"; as tags ficam em "moov"
+fn mp4_audio(data: &[u8], checksum: &mut glib::Checksum) -> Option<()> {
+    let mut pos = 0;
+    let mut found = false;
+    while pos + 8 <= data.len() {
+        let size = u32::from_be_bytes(data[pos..pos + 4].try_into().ok()?) as usize;
+        let kind = &data[pos + 4..pos + 8];
+        let (header, size) = match size {
+            0 => (8, data.len() - pos),
+            1 => (16, u64::from_be_bytes(data.get(pos + 8..pos + 16)?.try_into().ok()?) as usize),
+            size => (8, size),
+        };
+        if size < header {
+            return None;
+        }
+        // Tamanhos de 64 bits corrompidos passariam do fim do endereçamento
+        let end = pos.checked_add(size)?;
+        if kind == b"mdat" {
+            checksum.update(data.get(pos + header..end)?);
+            found = true;
+        }
+        pos = end;
+    }
+    found.then_some(())
+}
+
+// Átomos de um nível do MP4: (tipo, conteúdo)
+fn mp4_atoms(data: &[u8]) -> impl Iterator<Item = (&[u8], &[u8])> {
+    let mut pos = 0;
+    std::iter::from_fn(move || {
+        let header = data.get(pos..pos + 8)?;
+        let (offset, size) = match u32::from_be_bytes(header[..4].try_into().ok()?) {
+            0 => (8, data.len() - pos),
+            1 => (16, u64::from_be_bytes(data.get(pos + 8..pos + 16)?.try_into().ok()?) as usize),
+            size => (8, size as usize),
+        };
+        if size < offset {
+            return None;
+        }
+        let body = data.get(pos + offset..pos.checked_add(size)?)?;
+        pos += size;
+        Some((&header[4..8], body))
+    })
+}
+
+fn mp4_child<'a>(data: &'a [u8], kind: &[u8]) -> Option<&'a [u8]> {
+    mp4_atoms(data).find(|(k, _)| *k == kind).map(|(_, body)| body)
+}
+
+// Codec da primeira faixa de som: moov > trak > mdia > minf > stbl > stsd
+fn mp4_codec(moov: &[u8]) -> Option<[u8; 4]> {
+    mp4_atoms(moov).filter(|(kind, _)| *kind == b"trak").find_map(|(_, trak)| {
+        let mdia = mp4_child(trak, b"mdia")?;
+        if mp4_child(mdia, b"hdlr")?.get(8..12)? != b"soun" {
+            return None;
+        }
+        let stbl = mp4_child(mp4_child(mdia, b"minf")?, b"stbl")?;
+        // versão/flags, número de entradas, tamanho da primeira entrada
+        mp4_child(stbl, b"stsd")?.get(12..16)?.try_into().ok()
+    })
+}
+
+// Lê só o "moov"; o "mdat" pode ter centenas de megabytes
+fn mp4_file_codec(path: &Path) -> Option<[u8; 4]> {
+    let mut file = fs::File::open(path).ok()?;
+    let mut header = [0u8; 8];
+    loop {
+        file.read_exact(&mut header).ok()?;
+        let (offset, size) = match u32::from_be_bytes(header[..4].try_into().ok()?) {
+            0 => return None,
+            1 => {
+                let mut large = [0u8; 8];
+                file.read_exact(&mut large).ok()?;
+                (16, u64::from_be_bytes(large))
+            }
+            size => (8, size as u64),
+        };
+        let body = size.checked_sub(offset)?;
+        if &header[4..8] == b"moov" {
+            let mut moov = Vec::new();
+            file.take(body).read_to_end(&mut moov).ok()?;
+            return mp4_codec(&moov);
+        }
+        file.seek(SeekFrom::Current(i64::try_from(body).ok()?)).ok()?;
+    }
+}
+
+fn wav_audio(data: &[u8]) -> Option<&[u8]> {
+    if !data.starts_with(b"RIFF") || data.get(8..12)? != b"WAVE" {
+        return None;
+    }
+    let mut pos = 12;
+    while pos + 8 <= data.len() {
+        let size = u32::from_le_bytes(data[pos + 4..pos + 8].try_into().ok()?) as usize;
+        if &data[pos..pos + 4] == b"data" {
+            return data.get(pos + 8..(pos + 8).saturating_add(size).min(data.len()));
+        }
+        pos = pos.checked_add(8 + size + size % 2)?;
+    }
+    None
+}
+
+// Páginas de áudio do Ogg; os cabeçalhos (com os comentários) têm granule 0
+fn ogg_audio(data: &[u8], checksum: &mut glib::Checksum) -> Option<()> {
+    let mut pos = 0;
+    let mut started = false;
+    while pos + 27 <= data.len() {
+        if &data[pos..pos + 4] != b"OggS" {
+            return None;
+        }
+        let granule = i64::from_le_bytes(data[pos + 6..pos + 14].try_into().ok()?);
+        let segments = data[pos + 26] as usize;
+        let table = data.get(pos + 27..pos + 27 + segments)?;
+        let payload_size: usize = table.iter().map(|s| *s as usize).sum();
+        let payload_start = pos + 27 + segments;
+        started |= granule > 0;
+        if started {
+            checksum.update(data.get(payload_start..payload_start + payload_size)?);
+        }
+        pos = payload_start + payload_size;
+    }
+    started.then_some(())
+}
+
+// Hash do áudio em si, ignorando os blocos de tags de cada formato
+pub fn audio_hash(path: &Path) -> io::Result<String> {
+    let data = fs::read(path)?;
+    let ext = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+
+    let mut checksum = glib::Checksum::new(glib::ChecksumType::Sha256)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::Other, "SHA-256 unavailable"))?;
+    let handled = match ext.as_str() {
+        "flac" => flac_audio(&data).map(|audio| checksum.update(audio)),
+        "m4a" | "mp4" => mp4_audio(&data, &mut checksum),
+        "ogg" | "oga" | "opus" => ogg_audio(&data, &mut checksum),
+        "wav" => wav_audio(&data).map(|audio| checksum.update(audio)),
+        _ => None,
+    };
+    if handled.is_none() {
+        // Formato desconhecido ou estrutura inválida: recomeça só sem as tags ID3/APE
+        checksum.reset();
+        let (start, end) = untagged_range(&data);
+        checksum.update(&data[start..end]);
+    }
+
+    checksum
+        .string()
+        .ok_or_else(|| io::Error::new(io::ErrorKind::Other, "checksum failed"))
+}
+
+// Fingerprint cru do Chromaprint, calculado localmente pelo fpcalc
+pub fn fingerprint(path: &Path) -> Result<Vec<u32>> {
+    let output = Command::new("fpcalc")
+        .args(["-raw", "-length", FINGERPRINT_SECONDS])
+        .arg(path)
+        .output()
+        .map_err(|e| match e.kind() {
+            io::ErrorKind::NotFound => anyhow!("fpcalc not found; install Chromaprint to compare by sound"),
+            _ => e.into(),
+        })?;
+    if !output.status.success() {
+        return Err(anyhow!("fpcalc failed: {}", String::from_utf8_lossy(&output.stderr).trim()));
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let values = stdout
+        .lines()
+        .find_map(|line| line.strip_prefix("FINGERPRINT="))
+        .ok_or_else(|| anyhow!("fpcalc returned no fingerprint"))?;
+    // Versões antigas imprimem os valores com sinal
+    Ok(values
+        .split(',')
+        .filter_map(|v| v.trim().parse::<i64>().ok())
+        .map(|v| v as u32)
+        .collect())
+}
+
+// Fração de bits iguais no melhor alinhamento entre os dois fingerprints
+pub fn fingerprint_similarity(a: &[u32], b: &[u32]) -> f64 {
+    let mut best: f64 = 0.0;
+    for offset in 0..=FINGERPRINT_MAX_OFFSET {
+        for (x, y) in [(a.get(offset..), Some(b)), (Some(a), b.get(offset..))] {
+            let (Some(x), Some(y)) = (x, y) else {
+                continue;
+            };
+            let frames = x.len().min(y.len());
+            if frames < FINGERPRINT_MIN_FRAMES {
+                continue;
+            }
+            let errors: u32 = x.iter().zip(y).map(|(p, q)| (p ^ q).count_ones()).sum();
+            best = best.max(1.0 - errors as f64 / (32 * frames) as f64);
+        }
+    }
+    best
+}
+
+// Roda fora da thread da interface: lê todos os arquivos quando há hash ou fingerprint
+pub fn find_duplicates(songs: &[Song], methods: &[DuplicateMethod]) -> DuplicateReport {
+    let mut report = DuplicateReport::default();
+    let mut union_find = UnionFind::new(songs.len());
+    let mut linked: Vec<(DuplicateMethod, Vec<usize>)> = Vec::new();
+
+    if methods.contains(&DuplicateMethod::Tags) {
+        for cluster in tag_clusters(songs) {
+            for &i in &cluster[1..] {
+                union_find.union(cluster[0], i);
+            }
+            linked.push((DuplicateMethod::Tags, cluster));
+        }
+    }
+
+    if methods.contains(&DuplicateMethod::AudioHash) {
+        let mut by_hash: HashMap<String, Vec<usize>> = HashMap::new();
+        for (i, song) in songs.iter().enumerate() {
+            match audio_hash(&song.file_path) {
+                Ok(hash) => by_hash.entry(hash).or_default().push(i),
+                Err(e) => report.errors.push((song.file_path.clone(), e.to_string())),
+            }
+        }
+        for cluster in by_hash.into_values().filter(|c| c.len() > 1) {
+            for &i in &cluster[1..] {
+                union_find.union(cluster[0], i);
+            }
+            linked.push((DuplicateMethod::AudioHash, cluster));
+        }
+    }
+
+    if methods.contains(&DuplicateMethod::Fingerprint) {
+        let mut prints: Vec<(usize, Vec<u32>)> = Vec::new();
+        for (i, song) in songs.iter().enumerate() {
+            match fingerprint(&song.file_path) {
+                Ok(print) => prints.push((i, print)),
+                Err(e) => {
+                    let missing_tool = e.to_string().starts_with("fpcalc not found");
+                    report.errors.push((song.file_path.clone(), e.to_string()));
+                    if missing_tool {
+                        break;
+                    }
+                }
+            }
+        }
+
+        // Só compara músicas de duração parecida
+        prints.sort_by_key(|(i, _)| songs[*i].duration.unwrap_or(0));
+        for a in 0..prints.len() {
+            for b in a + 1..prints.len() {
+                let (i, j) = (prints[a].0, prints[b].0);
+                let gap = songs[j].duration.unwrap_or(0) - songs[i].duration.unwrap_or(0);
+                if gap > DURATION_TOLERANCE_SECS * 3 {
+                    break;
+                }
+                if fingerprint_similarity(&prints[a].1, &prints[b].1) >= FINGERPRINT_THRESHOLD {
+                    union_find.union(i, j);
+                    linked.push((DuplicateMethod::Fingerprint, vec![i, j]));
+                }
+            }
+        }
+    }
+
+    let mut groups: HashMap<usize, Vec<usize>> = HashMap::new();
+    for i in 0..songs.len() {
+        let root = union_find.find(i);
+        groups.entry(root).or_default().push(i);
+    }
+
+    for (root, members) in groups.into_iter().filter(|(_, m)| m.len() > 1) {
+        let mut reasons = Vec::new();
+        for (method, cluster) in &linked {
+            if !reasons.contains(method) && union_find.find(cluster[0]) == root {
+                reasons.push(*method);
+            }
+        }
+
+        let mut group: Vec<Song> = members.into_iter().map(|i| songs[i].clone()).collect();
+        group.sort_by_cached_key(|song| std::cmp::Reverse(quality_key(song)));
+        report.groups.push(DuplicateGroup {
+            songs: group,
+            reasons,
+        });
+    }
+
+    report.groups.sort_by(|a, b| {
+        let key = |g: &DuplicateGroup| (g.songs[0].artist.to_lowercase(), g.songs[0].title.to_lowercase());
+        key(a).cmp(&key(b))
+    });
+    report
+}
+
+// O que fazer com os arquivos das cópias removidas da biblioteca
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DiskAction {
+    Keep,
+    Trash,
+    Delete,
+}
+
+impl MusicLibrary {
+    // Tira da biblioteca; arquivos que não puderam ir para a lixeira ou ser apagados ficam nela
+    pub fn remove_duplicates(&mut self, paths: &[PathBuf], action: DiskAction) -> Vec<(PathBuf, anyhow::Error)> {
+        let mut errors = Vec::new();
+        let mut removed = Vec::new();
+
+        for path in paths {
+            let result = match action {
+                DiskAction::Keep => Ok(()),
+                DiskAction::Trash => gio::File::for_path(path).trash(gio::Cancellable::NONE).map_err(|e| anyhow!(e)),
+                DiskAction::Delete => fs::remove_file(path).map_err(|e| anyhow!(e)),
+            };
+            if let Err(e) = result {
+                errors.push((path.clone(), e));
+                continue;
+            }
+            removed.push(path.clone());
+        }
+
+        self.remove_songs(&removed);
+        errors
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn song(artist: &str, title: &str, duration: Option<u64>) -> Song {
+        let mut song = Song::new(
+            PathBuf::from(format!("/music/{} - {}.mp3", artist, title)),
+            title.to_string(),
+            artist.to_string(),
+            "Album".to_string(),
+            None,
+        );
+        song.duration = duration;
+        song
+    }
+
+    fn atom(kind: &[u8], body: &[u8]) -> Vec<u8> {
+        let mut atom = ((body.len() + 8) as u32).to_be_bytes().to_vec();
+        atom.extend_from_slice(kind);
+        atom.extend_from_slice(body);
+        atom
+    }
+
+    fn ogg_page(granule: i64, payload: &[u8]) -> Vec<u8> {
+        let mut page = b"OggS".to_vec();
+        page.extend_from_slice(&[0, 0]);
+        page.extend_from_slice(&granule.to_le_bytes());
+        page.extend_from_slice(&[0; 12]);
+        page.push(1);
+        page.push(payload.len() as u8);
+        page.extend_from_slice(payload);
+        page
+    }
+
+    fn sha256(parse: impl FnOnce(&mut glib::Checksum) -> Option<()>) -> Option<String> {
+        let mut checksum = glib::Checksum::new(glib::ChecksumType::Sha256).unwrap();
+        parse(&mut checksum)?;
+        checksum.string()
+    }
+
+    fn trak(handler: &[u8], codec: &[u8]) -> Vec<u8> {
+        let mut hdlr = vec![0; 8];
+        hdlr.extend_from_slice(handler);
+        hdlr.extend_from_slice(&[0; 12]);
+        let mut stsd = vec![0, 0, 0, 0, 0, 0, 0, 1];
+        stsd.extend(atom(codec, &[0; 28]));
+        let stbl = atom(b"stbl", &atom(b"stsd", &stsd));
+        let mut mdia = atom(b"hdlr", &hdlr);
+        mdia.extend(atom(b"minf", &stbl));
+        atom(b"trak", &atom(b"mdia", &mdia))
+    }
+
+    #[test]
+    fn untagged_range_skips_id3_and_ape_tags() {
+        let mut data = b"ID3\x04\x00\x00\x00\x00\x00\x03abc".to_vec();
+        data.extend_from_slice(b"AUDIO");
+        data.extend_from_slice(b"TAG");
+        data.extend_from_slice(&[b' '; 125]);
+        let (start, end) = untagged_range(&data);
+        assert_eq!(&data[start..end], b"AUDIO");
+
+        // APEv2 sem cabeçalho: o tamanho conta itens + rodapé
+        let mut data = b"AUDIO".to_vec();
+        data.extend_from_slice(&[7; 10]);
+        let mut footer = b"APETAGEX".to_vec();
+        footer.extend_from_slice(&2000u32.to_le_bytes());
+        footer.extend_from_slice(&42u32.to_le_bytes());
+        footer.extend_from_slice(&[0; 16]);
+        data.extend(footer);
+        let (start, end) = untagged_range(&data);
+        assert_eq!(&data[start..end], b"AUDIO");
+
+        // Tamanho maior que o arquivo não pode passar do fim
+        let data = b"ID3\x04\x00\x00\x7f\x7f\x7f\x7f".to_vec();
+        assert_eq!(untagged_range(&data), (data.len(), data.len()));
+    }
+
+    #[test]
+    fn flac_audio_starts_after_the_last_metadata_block() {
+        let mut data = b"fLaC".to_vec();
+        data.extend_from_slice(&[0x00, 0, 0, 2, 1, 2]);
+        data.extend_from_slice(&[0x84, 0, 0, 3, b'a', b'b', b'c']);
+        data.extend_from_slice(b"FRAMES");
+        assert_eq!(flac_audio(&data), Some(&b"FRAMES"[..]));
+
+        let mut tagged = b"ID3\x04\x00\x00\x00\x00\x00\x01x".to_vec();
+        tagged.extend_from_slice(&data);
+        assert_eq!(flac_audio(&tagged), Some(&b"FRAMES"[..]));
+
+        assert_eq!(flac_audio(&data[..10]), None);
+        assert_eq!(flac_audio(b"OggS"), None);
+    }
+
+    #[test]
+    fn wav_audio_finds_the_data_chunk_after_padded_chunks() {
+        let mut data = b"RIFF\x00\x00\x00\x00WAVE".to_vec();
+        data.extend_from_slice(b"LIST\x03\x00\x00\x00abc\x00");
+        data.extend_from_slice(b"data\x04\x00\x00\x00PCM!");
+        assert_eq!(wav_audio(&data), Some(&b"PCM!"[..]));
+
+        // Tamanho do chunk maior que o arquivo (gravação interrompida)
+        let cut = &data[..data.len() - 2];
+        assert_eq!(wav_audio(cut), Some(&b"PC"[..]));
+
+        assert_eq!(wav_audio(b"RIFF\x00\x00\x00\x00AVI LIST"), None);
+    }
+
+    #[test]
+    fn mp4_audio_hashes_only_mdat() {
+        let file = |tags: &[u8]| {
+            let mut data = atom(b"ftyp", b"M4A ");
+            data.extend(atom(b"moov", &atom(b"udta", tags)));
+            data.extend(atom(b"mdat", b"samples"));
+            data
+        };
+        let a = sha256(|c| mp4_audio(&file(b"old title"), c));
+        let b = sha256(|c| mp4_audio(&file(b"a much longer new title"), c));
+        assert!(a.is_some());
+        assert_eq!(a, b);
+
+        let mut other = atom(b"ftyp", b"M4A ");
+        other.extend(atom(b"mdat", b"different"));
+        assert_ne!(a, sha256(|c| mp4_audio(&other, c)));
+
+        assert_eq!(sha256(|c| mp4_audio(&atom(b"ftyp", b"M4A "), c)), None);
+        let mut broken = atom(b"ftyp", b"M4A ");
+        broken.extend_from_slice(&[0, 0, 0, 4]);
+        broken.extend_from_slice(b"mdat");
+        assert_eq!(sha256(|c| mp4_audio(&broken, c)), None);
+    }
+
+    #[test]
+    fn mp4_codec_reads_the_sound_track() {
+        let mut moov = trak(b"vide", b"avc1");
+        moov.extend(trak(b"soun", b"alac"));
+        assert_eq!(mp4_codec(&moov), Some(*b"alac"));
+        assert_eq!(mp4_codec(&trak(b"soun", b"mp4a")), Some(*b"mp4a"));
+        assert_eq!(mp4_codec(&trak(b"vide", b"avc1")), None);
+        assert_eq!(mp4_codec(b"\x00\x00\x00\x20trak"), None);
+    }
+
+    #[test]
+    fn alac_ranks_above_lossy_files() {
+        let dir = std::env::temp_dir().join(format!("duplicates-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let write = |name: &str, codec: &[u8]| {
+            let path = dir.join(name);
+            let mut data = atom(b"ftyp", b"M4A ");
+            data.extend(atom(b"mdat", &[0; 64]));
+            data.extend(atom(b"moov", &trak(b"soun", codec)));
+            fs::write(&path, data).unwrap();
+            let mut song = song("Artist", name, Some(200));
+            song.file_path = path;
+            song.bitrate = Some(700);
+            song
+        };
+        let alac = write("alac.m4a", b"alac");
+        let aac = write("aac.m4a", b"mp4a");
+        let mut mp3 = song("Artist", "mp3", Some(200));
+        mp3.bitrate = Some(320);
+        let mut flac = song("Artist", "flac", Some(200));
+        flac.file_path.set_extension("flac");
+        flac.bitrate = Some(900);
+
+        assert_eq!(mp4_file_codec(&alac.file_path), Some(*b"alac"));
+        assert!(quality_key(&alac) > quality_key(&mp3));
+        assert!(quality_key(&alac) > quality_key(&aac));
+        assert!(quality_key(&aac) > quality_key(&mp3));
+        assert!(quality_key(&flac) > quality_key(&alac));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn ogg_audio_skips_header_pages() {
+        let file = |comments: &[u8]| {
+            let mut data = ogg_page(0, b"\x01vorbis");
+            data.extend(ogg_page(0, comments));
+            data.extend(ogg_page(1024, b"packet one"));
+            data.extend(ogg_page(2048, b"packet two"));
+            data
+        };
+        let a = sha256(|c| ogg_audio(&file(b"\x03vorbis title=Old"), c));
+        let b = sha256(|c| ogg_audio(&file(b"\x03vorbis title=New and longer"), c));
+        assert!(a.is_some());
+        assert_eq!(a, b);
+
+        assert_eq!(sha256(|c| ogg_audio(&ogg_page(0, b"\x01vorbis"), c)), None);
+        let mut truncated = file(b"\x03vorbis");
+        truncated.truncate(truncated.len() - 3);
+        assert_eq!(sha256(|c| ogg_audio(&truncated, c)), None);
+        assert_eq!(sha256(|c| ogg_audio(b"RIFF\x00\x00\x00\x00WAVE and more bytes here....", c)), None);
+    }
+
+    #[test]
+    fn fingerprint_similarity_aligns_small_offsets() {
+        let print: Vec<u32> = (0..60u32).map(|i| i.wrapping_mul(2_654_435_761)).collect();
+        assert_eq!(fingerprint_similarity(&print, &print), 1.0);
+        assert_eq!(fingerprint_similarity(&print[2..], &print), 1.0);
+        assert_eq!(fingerprint_similarity(&print, &print[3..]), 1.0);
+
+        assert_eq!(fingerprint_similarity(&[0; 60], &[u32::MAX; 60]), 0.0);
+        let inverted: Vec<u32> = print.iter().map(|v| !v).collect();
+        assert!(fingerprint_similarity(&print, &inverted) < FINGERPRINT_THRESHOLD);
+
+        let mut noisy = print.clone();
+        for value in noisy.iter_mut().step_by(2) {
+            *value ^= 0xff;
+        }
+        let similarity = fingerprint_similarity(&print, &noisy);
+        assert!((similarity - (1.0 - 8.0 / 64.0)).abs() < 1e-9);
+
+        // Curtos demais para comparar
+        assert_eq!(fingerprint_similarity(&print[..20], &print[..20]), 0.0);
+    }
+
+    #[test]
+    fn tag_clusters_split_by_duration() {
+        let songs = vec![
+            song("Beyoncé", "Halo", Some(261)),
+            song("BEYONCE", "  halo ", Some(263)),
+            song("Beyonce", "Halo", Some(270)),
+            song("Beyonce", "Halo", None),
+            song("beyonce", "HALO", None),
+            song("Other", "Halo", Some(261)),
+        ];
+        let mut clusters = tag_clusters(&songs);
+        for cluster in &mut clusters {
+            cluster.sort();
+        }
+        clusters.sort();
+        assert_eq!(clusters, vec![vec![0, 1], vec![3, 4]]);
+    }
+}
@@ -0,0 +1,68 @@
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use super::Song;
+use crate::config::data_dir;
+
+// Uma linha JSON por reprodução, só acrescentada
+const HISTORY_FILE: &str = "history.jsonl";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PlayEvent {
+    pub timestamp: u64,
+    pub path: PathBuf,
+    // Cópia das tags, para o histórico sobreviver à música sair da biblioteca
+    pub title: String,
+    pub artist: String,
+    pub album: String,
+    pub played_seconds: u64,
+    pub skipped: bool,
+}
+
+impl PlayEvent {
+    pub fn new(song: &Song, timestamp: u64, played_seconds: u64, skipped: bool) -> Self {
+        // Nunca mais que a duração da música
+        let played_seconds = song.duration.map_or(played_seconds, |duration| played_seconds.min(duration));
+        Self {
+            timestamp,
+            path: song.file_path.clone(),
+            title: song.title.clone(),
+            artist: song.artist.clone(),
+            album: song.album.clone(),
+            played_seconds,
+            skipped,
+        }
+    }
+}
+
+fn history_path() -> PathBuf {
+    data_dir().join(HISTORY_FILE)
+}
+
+pub fn append(event: &PlayEvent) -> Result<()> {
+    fs::create_dir_all(data_dir())?;
+    let mut file = OpenOptions::new().create(true).append(true).open(history_path())?;
+    writeln!(file, "{}", serde_json::to_string(event)?)?;
+    Ok(())
+}
+
+// Linhas inválidas (ex.: gravação interrompida) são ignoradas
+pub fn load() -> Vec<PlayEvent> {
+    let Ok(contents) = fs::read_to_string(history_path()) else {
+        return Vec::new();
+    };
+    contents
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| match serde_json::from_str(line) {
+            Ok(event) => Some(event),
+            Err(e) => {
+                eprintln!("Ignoring invalid history entry: {}", e);
+                None
+            }
+        })
+        .collect()
+}
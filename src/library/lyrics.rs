@@ -0,0 +1,291 @@
+use std::borrow::Cow;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use anyhow::Result;
+use lofty::id3::v2::{Frame, FrameFlags, FrameId, Id3v2Tag, SynchronizedTextFrame, TimestampFormat};
+use lofty::prelude::*;
+use lofty::tag::{Tag, TagType};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LyricsSource {
+    Sidecar,
+    Synced,   // SYLT
+    Embedded, // USLT / LYRICS
+}
+
+impl LyricsSource {
+    pub fn label(&self) -> &'static str {
+        match self {
+            LyricsSource::Sidecar => ".lrc file",
+            LyricsSource::Synced => "embedded synced lyrics",
+            LyricsSource::Embedded => "embedded lyrics",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct LyricLine {
+    pub time: Option<Duration>,
+    pub text: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct Lyrics {
+    pub lines: Vec<LyricLine>,
+    pub source: LyricsSource,
+}
+
+impl Lyrics {
+    pub fn is_synced(&self) -> bool {
+        self.lines.iter().any(|line| line.time.is_some())
+    }
+
+    // Última linha cujo tempo já passou
+    pub fn current_line(&self, position: Duration) -> Option<usize> {
+        self.lines
+            .iter()
+            .rposition(|line| line.time.is_some_and(|time| time <= position))
+    }
+
+    pub fn to_lrc(&self) -> String {
+        self.lines
+            .iter()
+            .map(|line| match line.time {
+                Some(time) => format!("{}{}", format_timestamp(time), line.text),
+                None => line.text.clone(),
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+pub fn sidecar_path(audio: &Path) -> PathBuf {
+    audio.with_extension("lrc")
+}
+
+pub fn format_timestamp(time: Duration) -> String {
+    let centis = time.as_millis() / 10;
+    format!("[{:02}:{:02}.{:02}]", centis / 6000, (centis / 100) % 60, centis % 100)
+}
+
+// [mm:ss], [mm:ss.xx] ou [mm:ss.xxx]
+fn parse_timestamp(tag: &str) -> Option<Duration> {
+    let (minutes, seconds) = tag.split_once(':')?;
+    let minutes: u64 = minutes.trim().parse().ok()?;
+    let seconds: f64 = seconds.trim().replace(',', ".").parse().ok()?;
+    if !(0.0..60.0).contains(&seconds) {
+        return None;
+    }
+    let millis = minutes.checked_mul(60_000)?.checked_add((seconds * 1000.0).round() as u64)?;
+    Some(Duration::from_millis(millis))
+}
+
+pub fn parse_lrc(text: &str) -> Vec<LyricLine> {
+    let mut offset_ms: i64 = 0;
+    let mut lines = Vec::new();
+
+    for raw in text.lines() {
+        let mut rest = raw.trim();
+        let mut times = Vec::new();
+        let mut is_metadata = false;
+
+        // Uma linha pode ter vários tempos: [00:12.00][01:30.00]texto
+        while let Some(tag) = rest.strip_prefix('[').and_then(|r| r.split_once(']')) {
+            let (inner, after) = tag;
+            if let Some(time) = parse_timestamp(inner) {
+                times.push(time);
+            } else if let Some((key, value)) = inner.split_once(':') {
+                if key.trim().eq_ignore_ascii_case("offset") {
+                    offset_ms = value.trim().parse().unwrap_or(0);
+                }
+                is_metadata = true;
+            } else {
+                break;
+            }
+            rest = after;
+        }
+
+        if times.is_empty() {
+            if !is_metadata {
+                lines.push(LyricLine { time: None, text: rest.to_string() });
+            }
+            continue;
+        }
+        for time in times {
+            lines.push(LyricLine { time: Some(time), text: rest.trim().to_string() });
+        }
+    }
+
+    // offset positivo adianta a letra
+    if offset_ms != 0 {
+        for line in lines.iter_mut() {
+            if let Some(time) = line.time {
+                let shifted = i64::try_from(time.as_millis()).unwrap_or(i64::MAX).saturating_sub(offset_ms);
+                line.time = Some(Duration::from_millis(shifted.max(0) as u64));
+            }
+        }
+    }
+
+    // Linhas sem tempo ficam depois da linha com tempo que as precede
+    if lines.iter().any(|line| line.time.is_some()) {
+        let mut previous = Duration::ZERO;
+        let mut keyed: Vec<(Duration, LyricLine)> = lines
+            .into_iter()
+            .map(|line| {
+                previous = line.time.unwrap_or(previous);
+                (previous, line)
+            })
+            .collect();
+        keyed.sort_by_key(|(time, _)| *time);
+        lines = keyed.into_iter().map(|(_, line)| line).collect();
+    }
+
+    // Tira linhas em branco do começo e do fim
+    while lines.first().is_some_and(|line| line.time.is_none() && line.text.trim().is_empty()) {
+        lines.remove(0);
+    }
+    while lines.last().is_some_and(|line| line.time.is_none() && line.text.trim().is_empty()) {
+        lines.pop();
+    }
+    lines
+}
+
+fn read_sylt(tag: &Tag) -> Option<Vec<LyricLine>> {
+    if tag.tag_type() != TagType::Id3v2 {
+        return None;
+    }
+    // O SYLT fica guardado como frame binário junto com a tag genérica
+    let id3 = Id3v2Tag::from(tag.clone());
+    let Some(Frame::Binary(frame)) = id3.get(&FrameId::Valid(Cow::Borrowed("SYLT"))) else {
+        return None;
+    };
+    let sylt = SynchronizedTextFrame::parse(&frame.data, FrameFlags::default()).ok()?;
+    if sylt.timestamp_format != TimestampFormat::MS {
+        return None;
+    }
+
+    let lines: Vec<LyricLine> = sylt
+        .content
+        .into_iter()
+        .map(|(ms, text)| LyricLine {
+            time: Some(Duration::from_millis(u64::from(ms))),
+            text: text.trim_matches(['\r', '\n']).to_string(),
+        })
+        .collect();
+    Some(lines).filter(|lines| !lines.is_empty())
+}
+
+// Ordem: .lrc ao lado do arquivo, SYLT, depois USLT/LYRICS
+pub fn load(audio: &Path) -> Option<Lyrics> {
+    if let Ok(text) = fs::read_to_string(sidecar_path(audio)) {
+        let lines = parse_lrc(&text);
+        if !lines.is_empty() {
+            return Some(Lyrics { lines, source: LyricsSource::Sidecar });
+        }
+    }
+
+    let tagged_file = match lofty::read_from_path(audio) {
+        Ok(file) => file,
+        Err(e) => {
+            eprintln!("Could not read lyrics from {}: {}", audio.display(), e);
+            return None;
+        }
+    };
+    let tag = tagged_file.primary_tag().or_else(|| tagged_file.first_tag())?;
+
+    if let Some(lines) = read_sylt(tag) {
+        return Some(Lyrics { lines, source: LyricsSource::Synced });
+    }
+
+    // Algumas letras embutidas já vêm no formato LRC
+    let text = tag.get_string(&lofty::tag::ItemKey::Lyrics)?;
+    let lines = parse_lrc(text);
+    Some(Lyrics { lines, source: LyricsSource::Embedded }).filter(|lyrics| !lyrics.lines.is_empty())
+}
+
+pub fn save_lrc(audio: &Path, text: &str) -> Result<PathBuf> {
+    let path = sidecar_path(audio);
+    let mut contents = text.trim_end().to_string();
+    contents.push('\n');
+    fs::write(&path, contents)?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(millis: Option<u64>, text: &str) -> LyricLine {
+        LyricLine { time: millis.map(Duration::from_millis), text: text.to_string() }
+    }
+
+    #[test]
+    fn parses_timestamps() {
+        assert_eq!(parse_timestamp("01:02"), Some(Duration::from_millis(62_000)));
+        assert_eq!(parse_timestamp("01:02.5"), Some(Duration::from_millis(62_500)));
+        assert_eq!(parse_timestamp("00:02,250"), Some(Duration::from_millis(2_250)));
+        assert_eq!(parse_timestamp("00:60.00"), None);
+        assert_eq!(parse_timestamp("ar:Artist"), None);
+        assert_eq!(parse_timestamp(&format!("{}:00", u64::MAX)), None);
+    }
+
+    #[test]
+    fn parses_repeated_times_and_metadata() {
+        let lines = parse_lrc("[ar:Artist]\n[00:10.00][00:30.00]Chorus\n[00:20.00] Verse \n");
+        assert_eq!(lines, vec![
+            line(Some(10_000), "Chorus"),
+            line(Some(20_000), "Verse"),
+            line(Some(30_000), "Chorus"),
+        ]);
+    }
+
+    #[test]
+    fn applies_offset_without_overflow() {
+        let lines = parse_lrc("[offset:500]\n[00:00.20]First\n[00:01.00]Second");
+        assert_eq!(lines, vec![line(Some(0), "First"), line(Some(500), "Second")]);
+
+        let lines = parse_lrc(&format!("[offset:{}]\n[00:01.00]Late", i64::MIN));
+        assert_eq!(lines, vec![line(Some(i64::MAX as u64), "Late")]);
+    }
+
+    #[test]
+    fn keeps_untimed_lines_in_place() {
+        let lines = parse_lrc("\n[00:05.00]One\n(instrumental)\n[00:10.00]Two\n\n");
+        assert_eq!(lines, vec![
+            line(Some(5_000), "One"),
+            line(None, "(instrumental)"),
+            line(Some(10_000), "Two"),
+        ]);
+
+        let lines = parse_lrc("\nPlain\n\nText\n\n");
+        assert_eq!(lines, vec![line(None, "Plain"), line(None, ""), line(None, "Text")]);
+    }
+
+    #[test]
+    fn lrc_round_trip() {
+        let lyrics = Lyrics {
+            lines: vec![
+                line(Some(1_230), "First"),
+                line(None, "untimed"),
+                line(Some(65_500), "Second"),
+                line(Some(6_000_000), "Long song"),
+            ],
+            source: LyricsSource::Sidecar,
+        };
+        let text = lyrics.to_lrc();
+        assert_eq!(text, "[00:01.23]First\nuntimed\n[01:05.50]Second\n[100:00.00]Long song");
+        assert_eq!(parse_lrc(&text), lyrics.lines);
+    }
+
+    #[test]
+    fn finds_current_line() {
+        let lyrics = Lyrics {
+            lines: parse_lrc("[00:01.00]A\n[00:03.00]B"),
+            source: LyricsSource::Sidecar,
+        };
+        assert_eq!(lyrics.current_line(Duration::from_millis(500)), None);
+        assert_eq!(lyrics.current_line(Duration::from_secs(2)), Some(0));
+        assert_eq!(lyrics.current_line(Duration::from_secs(3)), Some(1));
+    }
+}
@@ -1,9 +1,50 @@
 use std::path::{Path, PathBuf};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use anyhow::Result;
 use std::fs;
+use std::time::{SystemTime, UNIX_EPOCH};
 use serde::{Deserialize, Serialize};
 
+pub mod duplicates;
+pub mod history;
+pub mod lyrics;
+pub mod organize;
+pub mod playlist;
+pub mod query;
+pub mod search;
+pub mod smart;
+pub mod stats;
+pub mod storage;
+pub mod tags;
+use playlist::Playlist;
+use query::{Query, QueryError};
+use search::SearchText;
+use smart::SmartPlaylist;
+
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+// Artigos ignorados ao ordenar por nome ("The Beatles" fica em "B")
+const SORT_ARTICLES: [&str; 12] = [
+    "the ", "a ", "an ", "os ", "as ", "o ", "um ", "uma ", "los ", "las ", "el ", "la ",
+];
+
+pub fn sort_name(text: &str) -> String {
+    let normalized = search::normalize(text.trim());
+    for article in SORT_ARTICLES {
+        if let Some(rest) = normalized.strip_prefix(article) {
+            if !rest.trim().is_empty() {
+                return rest.trim_start().to_string();
+            }
+        }
+    }
+    normalized
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Song {
     pub file_path: PathBuf,
@@ -11,6 +52,32 @@ pub struct Song {
     pub artist: String,
     pub album: String,
     pub cover_path: Option<PathBuf>, // Nova: caminho para a capa
+    #[serde(default)]
+    pub album_artist: Option<String>,
+    #[serde(default)]
+    pub genre: Option<String>,
+    #[serde(default)]
+    pub year: Option<u32>,
+    #[serde(default)]
+    pub track_number: Option<u32>,
+    #[serde(default)]
+    pub disc_number: Option<u32>,
+    #[serde(default)]
+    pub duration: Option<u64>, // em segundos
+    #[serde(default)]
+    pub bitrate: Option<u32>, // em kbps
+    #[serde(default)]
+    pub file_size: u64,
+    #[serde(default)]
+    pub play_count: u32,
+    #[serde(default)]
+    pub last_played: Option<u64>, // timestamp Unix
+    #[serde(default)]
+    pub skip_count: u32,
+    #[serde(default)]
+    pub rating: u8, // 0 a 5 estrelas
+    #[serde(default)]
+    pub date_added: u64, // timestamp Unix
 }
 
 #[derive(Debug, Clone)]
@@ -18,6 +85,14 @@ pub struct MusicLibrary {
     pub songs: Vec<Song>,
     pub artists: HashMap<String, Vec<Song>>,
     pub albums: HashMap<String, Vec<Song>>,
+    pub playlists: Vec<Playlist>,
+    pub smart_playlists: Vec<SmartPlaylist>,
+    // Grava nota e reproduções nas tags dos arquivos (opcional)
+    pub sync_rating_tags: bool,
+    // Muda a cada alteração das músicas, para as telas saberem quando reavaliar
+    pub revision: u64,
+    // Textos normalizados para a busca, na mesma ordem de `songs`
+    search_index: Vec<SearchText>,
 }
 
 impl MusicLibrary {
@@ -26,6 +101,11 @@ impl MusicLibrary {
             songs: Vec::new(),
             artists: HashMap::new(),
             albums: HashMap::new(),
+            playlists: Vec::new(),
+            smart_playlists: Vec::new(),
+            sync_rating_tags: false,
+            revision: 0,
+            search_index: Vec::new(),
         }
     }
 
@@ -34,6 +114,40 @@ impl MusicLibrary {
         self.organize_library();
     }
 
+    // Tira da biblioteca (não do disco); as playlists ignoram caminhos ausentes
+    pub fn remove_songs(&mut self, paths: &[PathBuf]) -> usize {
+        let before = self.songs.len();
+        let paths: HashSet<&PathBuf> = paths.iter().collect();
+        self.songs.retain(|song| !paths.contains(&song.file_path));
+        self.organize_library();
+        before - self.songs.len()
+    }
+
+    // Grava as tags nos arquivos e relê cada um para atualizar a biblioteca
+    pub fn edit_tags(&mut self, paths: &[PathBuf], edit: &tags::TagEdit) -> Vec<(PathBuf, anyhow::Error)> {
+        let mut errors = Vec::new();
+        for path in paths {
+            let info = match tags::write_tags(path, edit).and_then(|_| tags::read_tags(path)) {
+                Ok(info) => info,
+                Err(e) => {
+                    errors.push((path.clone(), e));
+                    continue;
+                }
+            };
+            if let Some(song) = self.songs.iter_mut().find(|s| &s.file_path == path) {
+                song.apply_tags(info);
+                song.file_size = fs::metadata(path).map(|m| m.len()).unwrap_or(song.file_size);
+                match &edit.cover {
+                    tags::CoverEdit::Keep => {}
+                    tags::CoverEdit::Set(image) => song.cover_path = Some(image.clone()),
+                    tags::CoverEdit::Remove => song.cover_path = None,
+                }
+            }
+        }
+        self.organize_library();
+        errors
+    }
+
     pub fn scan_directory(&mut self, path: &str) -> Result<()> {
         let path = Path::new(path);
         if !path.exists() {
@@ -41,29 +155,29 @@ impl MusicLibrary {
         }
 
         println!("Scanning directory: {}", path.display());
-        self.scan_directory_recursive(path)?;
+        let mut known: HashSet<PathBuf> = self.songs.iter().map(|s| s.file_path.clone()).collect();
+        self.scan_directory_recursive(path, &mut known)?;
         println!("Found {} songs", self.songs.len());
         
         self.organize_library();
         Ok(())
     }
 
-    fn scan_directory_recursive(&mut self, path: &Path) -> Result<()> {
+    fn scan_directory_recursive(&mut self, path: &Path, known: &mut HashSet<PathBuf>) -> Result<()> {
         if let Ok(entries) = fs::read_dir(path) {
-            for entry in entries {
-                if let Ok(entry) = entry {
-                    let path = entry.path();
-                    
-                    if path.is_dir() {
-                        self.scan_directory_recursive(&path)?;
-                    } else if self.is_audio_file(&path) {
-                        match self.create_song_from_file(&path) {
-                            Ok(song) => {
-                                self.songs.push(song);
-                            }
-                            Err(e) => {
-                                eprintln!("Error reading file {}: {}", path.display(), e);
-                            }
+            for entry in entries.flatten() {
+                let path = entry.path();
+                
+                if path.is_dir() {
+                    self.scan_directory_recursive(&path, known)?;
+                } else if self.is_audio_file(&path) && !known.contains(&path) {
+                    match self.create_song_from_file(&path) {
+                        Ok(song) => {
+                            known.insert(path);
+                            self.songs.push(song);
+                        }
+                        Err(e) => {
+                            eprintln!("Error reading file {}: {}", path.display(), e);
                         }
                     }
                 }
@@ -72,6 +186,59 @@ impl MusicLibrary {
         Ok(())
     }
 
+    // Música da biblioteca para o arquivo, ou lida das tags se ainda não estiver nela
+    pub fn song_for_file(&self, path: &Path) -> Result<Song> {
+        if let Some(song) = self.songs.iter().find(|s| s.file_path == path) {
+            return Ok(song.clone());
+        }
+        if !path.is_file() || !self.is_audio_file(path) {
+            return Err(anyhow::anyhow!("Not an audio file: {}", path.display()));
+        }
+        self.create_song_from_file(path)
+    }
+
+    // Arquivos e pastas (com subpastas, em ordem de nome) na ordem dada.
+    // Arquivos citados diretamente que não puderem ser lidos entram nos erros
+    pub fn songs_for_paths(&self, paths: &[PathBuf]) -> (Vec<Song>, Vec<String>) {
+        let mut songs = Vec::new();
+        let mut errors = Vec::new();
+        for path in paths {
+            if path.is_dir() {
+                self.collect_folder(path, &mut songs);
+            } else {
+                match self.song_for_file(path) {
+                    Ok(song) => songs.push(song),
+                    Err(e) => errors.push(e.to_string()),
+                }
+            }
+        }
+        (songs, errors)
+    }
+
+    // Troca as músicas lidas do disco pela cópia da biblioteca, que tem notas e contagens
+    pub fn prefer_known(&self, songs: Vec<Song>) -> Vec<Song> {
+        let known: HashMap<&Path, &Song> = self.songs.iter().map(|s| (s.file_path.as_path(), s)).collect();
+        songs
+            .into_iter()
+            .map(|song| known.get(song.file_path.as_path()).map_or(song, |known| (*known).clone()))
+            .collect()
+    }
+
+    fn collect_folder(&self, path: &Path, songs: &mut Vec<Song>) {
+        let Ok(entries) = fs::read_dir(path) else {
+            return;
+        };
+        let mut paths: Vec<PathBuf> = entries.flatten().map(|entry| entry.path()).collect();
+        paths.sort();
+        for path in paths {
+            if path.is_dir() {
+                self.collect_folder(&path, songs);
+            } else if let Ok(song) = self.song_for_file(&path) {
+                songs.push(song);
+            }
+        }
+    }
+
     fn is_audio_file(&self, path: &Path) -> bool {
         if let Some(extension) = path.extension() {
             let ext = extension.to_string_lossy().to_lowercase();
@@ -89,13 +256,28 @@ impl MusicLibrary {
 
         let (artist, title) = self.parse_file_name(&file_name);
 
-        Ok(Song {
-            file_path: path.to_path_buf(),
+        let mut song = Song::new(
+            path.to_path_buf(),
             title,
             artist,
-            album: "Unknown Album".to_string(),
-            cover_path: None,
-        })
+            "Unknown Album".to_string(),
+            None,
+        );
+        song.file_size = fs::metadata(path)?.len();
+
+        // Usa as tags do arquivo quando existirem; o nome do arquivo fica como fallback
+        match tags::read_tags(path) {
+            Ok(info) => {
+                if self.sync_rating_tags {
+                    song.rating = info.rating.unwrap_or(0);
+                    song.play_count = info.play_count.unwrap_or(0);
+                }
+                song.apply_tags(info);
+            }
+            Err(e) => eprintln!("Could not read tags from {}: {}", path.display(), e),
+        }
+
+        Ok(song)
     }
 
     fn parse_file_name(&self, file_name: &str) -> (String, String) {
@@ -116,36 +298,53 @@ impl MusicLibrary {
     }
 
     fn organize_library(&mut self) {
+        self.revision += 1;
+        self.search_index = self.songs.iter().map(SearchText::new).collect();
         self.artists.clear();
         self.albums.clear();
 
         for song in &self.songs {
             self.artists
                 .entry(song.artist.clone())
-                .or_insert_with(Vec::new)
+                .or_default()
                 .push(song.clone());
 
             self.albums
                 .entry(song.album.clone())
-                .or_insert_with(Vec::new)
+                .or_default()
                 .push(song.clone());
         }
     }
 
+    // Estatísticas não mudam artista, álbum nem a busca: só troca as cópias nos agrupamentos
+    fn refresh_song(&mut self, song: &Song) {
+        self.revision += 1;
+        for group in [self.artists.get_mut(&song.artist), self.albums.get_mut(&song.album)]
+            .into_iter()
+            .flatten()
+        {
+            if let Some(copy) = group.iter_mut().find(|copy| copy.file_path == song.file_path) {
+                *copy = song.clone();
+            }
+        }
+    }
+
     pub fn get_all_songs(&self) -> &[Song] {
         &self.songs
     }
 
-    pub fn search_songs(&self, query: &str) -> Vec<&Song> {
-        let query = query.to_lowercase();
-        self.songs
+    // Resultados ordenados por relevância; empates mantêm a ordem da biblioteca
+    pub fn search_songs(&self, query: &str) -> Result<Vec<&Song>, QueryError> {
+        let query = Query::parse(query)?;
+        let mut results: Vec<(u32, &Song)> = self
+            .songs
             .iter()
-            .filter(|song| {
-                song.title.to_lowercase().contains(&query) ||
-                song.artist.to_lowercase().contains(&query) ||
-                song.album.to_lowercase().contains(&query)
-            })
-            .collect()
+            .zip(&self.search_index)
+            .filter_map(|(song, text)| query.score(song, text, true).map(|score| (score, song)))
+            .collect();
+
+        results.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+        Ok(results.into_iter().map(|(_, song)| song).collect())
     }
 }
 
@@ -157,7 +356,39 @@ impl Song {
             artist,
             album,
             cover_path,
+            album_artist: None,
+            genre: None,
+            year: None,
+            track_number: None,
+            disc_number: None,
+            duration: None,
+            bitrate: None,
+            file_size: 0,
+            play_count: 0,
+            last_played: None,
+            skip_count: 0,
+            rating: 0,
+            date_added: unix_now(),
+        }
+    }
+
+    pub fn apply_tags(&mut self, info: tags::TagInfo) {
+        if let Some(title) = info.title {
+            self.title = title;
+        }
+        if let Some(artist) = info.artist {
+            self.artist = artist;
+        }
+        if let Some(album) = info.album {
+            self.album = album;
         }
+        self.album_artist = info.album_artist;
+        self.genre = info.genre;
+        self.year = info.year;
+        self.track_number = info.track_number;
+        self.disc_number = info.disc_number;
+        self.duration = info.duration;
+        self.bitrate = info.bitrate;
     }
 
     pub fn get_display_name(&self) -> String {
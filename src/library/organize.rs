@@ -0,0 +1,662 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use anyhow::{anyhow, bail, Result};
+
+use super::{MusicLibrary, Song};
+
+pub const DEFAULT_PATTERN: &str = "{albumartist}/{year} - {album}/{disc}-{track:02} {title}";
+
+const IMAGE_EXTENSIONS: [&str; 5] = ["jpg", "jpeg", "png", "webp", "gif"];
+
+// Nomes reservados do Windows, que também quebram em pendrives FAT/exFAT
+const RESERVED_NAMES: [&str; 22] = [
+    "con", "prn", "aux", "nul", "com1", "com2", "com3", "com4", "com5", "com6", "com7", "com8",
+    "com9", "lpt1", "lpt2", "lpt3", "lpt4", "lpt5", "lpt6", "lpt7", "lpt8", "lpt9",
+];
+
+// Limite de bytes por componente, com folga para sufixos como " (2)"
+const MAX_COMPONENT_BYTES: usize = 240;
+// errno do rename entre sistemas de arquivos (ErrorKind::CrossesDevices só existe a partir do Rust 1.85)
+const EXDEV: i32 = 18;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ConflictPolicy {
+    Skip,
+    KeepBoth,
+    Overwrite,
+}
+
+impl ConflictPolicy {
+    pub const ALL: [ConflictPolicy; 3] = [
+        ConflictPolicy::Skip,
+        ConflictPolicy::KeepBoth,
+        ConflictPolicy::Overwrite,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ConflictPolicy::Skip => "Skip conflicting files",
+            ConflictPolicy::KeepBoth => "Keep both (add a number)",
+            ConflictPolicy::Overwrite => "Overwrite existing files",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MoveKind {
+    Song,
+    Cover,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PlannedMove {
+    pub from: PathBuf,
+    pub to: PathBuf,
+    pub kind: MoveKind,
+    pub conflict: bool, // o destino já existia ou foi disputado por outro arquivo
+    pub copy: bool,     // capas compartilhadas por mais de um destino são copiadas
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrganizePlan {
+    pub root: PathBuf,
+    pub moves: Vec<PlannedMove>,
+    pub skipped: Vec<(PathBuf, String)>,
+}
+
+enum Segment {
+    Literal(String),
+    Field { name: String, width: usize },
+}
+
+const FIELDS: [&str; 8] = ["title", "artist", "album", "albumartist", "genre", "year", "track", "disc"];
+
+fn parse_component(component: &str) -> Result<Vec<Segment>> {
+    let mut segments = Vec::new();
+    let mut literal = String::new();
+    let mut chars = component.chars();
+
+    while let Some(c) = chars.next() {
+        if c != '{' {
+            literal.push(c);
+            continue;
+        }
+
+        let mut spec = String::new();
+        loop {
+            match chars.next() {
+                Some('}') => break,
+                Some(c) => spec.push(c),
+                None => bail!("Unclosed '{{' in pattern"),
+            }
+        }
+
+        let (name, width) = match spec.split_once(':') {
+            Some((name, width)) => {
+                let width = width
+                    .parse()
+                    .map_err(|_| anyhow!("Invalid width '{}' for {{{}}}", width, name))?;
+                (name.trim().to_lowercase(), width)
+            }
+            None => (spec.trim().to_lowercase(), 0),
+        };
+        if !FIELDS.contains(&name.as_str()) {
+            bail!("Unknown field {{{}}} in pattern (available: {})", name, FIELDS.join(", "));
+        }
+
+        if !literal.is_empty() {
+            segments.push(Segment::Literal(std::mem::take(&mut literal)));
+        }
+        segments.push(Segment::Field { name, width });
+    }
+
+    if !literal.is_empty() {
+        segments.push(Segment::Literal(literal));
+    }
+    Ok(segments)
+}
+
+fn field_value(song: &Song, name: &str, width: usize) -> String {
+    let number = |n: Option<u32>| n.map(|n| format!("{:0width$}", n, width = width)).unwrap_or_default();
+    match name {
+        "title" => song.title.clone(),
+        "artist" => song.artist.clone(),
+        "album" => song.album.clone(),
+        "albumartist" => song.album_artist.clone().unwrap_or_else(|| song.artist.clone()),
+        "genre" => song.genre.clone().unwrap_or_default(),
+        "year" => number(song.year),
+        "track" => number(song.track_number),
+        "disc" => number(song.disc_number),
+        _ => String::new(),
+    }
+}
+
+// Um componente de caminho válido em FAT/exFAT (e portanto em qualquer lugar)
+pub fn sanitize_component(name: &str) -> String {
+    let mut clean: String = name
+        .chars()
+        .map(|c| match c {
+            '<' | '>' | ':' | '"' | '/' | '\\' | '|' | '?' | '*' => '_',
+            c if c.is_control() => '_',
+            c => c,
+        })
+        .collect();
+
+    // Campos vazios deixam separadores soltos, como em " - Album"
+    clean = clean
+        .trim_matches(|c: char| c.is_whitespace() || c == '-' || c == '_' || c == '.')
+        .to_string();
+
+    if clean.len() > MAX_COMPONENT_BYTES {
+        let mut end = MAX_COMPONENT_BYTES;
+        while !clean.is_char_boundary(end) {
+            end -= 1;
+        }
+        clean.truncate(end);
+        clean = clean.trim_end_matches(|c: char| c.is_whitespace() || c == '.').to_string();
+    }
+
+    if clean.is_empty() {
+        return "Unknown".to_string();
+    }
+
+    let stem = clean.split('.').next().unwrap_or("").to_lowercase();
+    if RESERVED_NAMES.contains(&stem.as_str()) {
+        clean.push('_');
+    }
+    clean
+}
+
+// Caminho relativo à raiz, sem a extensão, com cada componente já sanitizado
+pub fn render_pattern(pattern: &str, song: &Song) -> Result<PathBuf> {
+    let mut path = PathBuf::new();
+    for component in pattern.split('/').filter(|c| !c.trim().is_empty()) {
+        let rendered: String = parse_component(component)?
+            .iter()
+            .map(|segment| match segment {
+                Segment::Literal(text) => text.clone(),
+                Segment::Field { name, width } => field_value(song, name, *width),
+            })
+            .collect();
+        path.push(sanitize_component(&rendered));
+    }
+
+    if path.as_os_str().is_empty() {
+        bail!("Pattern is empty");
+    }
+    Ok(path)
+}
+
+// FAT/exFAT não diferenciam maiúsculas, então "Abba" e "ABBA" são o mesmo destino
+fn path_key(path: &Path) -> String {
+    path.to_string_lossy().to_lowercase()
+}
+
+fn is_same_file(a: &Path, b: &Path) -> bool {
+    match (fs::canonicalize(a), fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+fn numbered_path(path: &Path, n: u32) -> PathBuf {
+    let stem = path.file_stem().map(|s| s.to_string_lossy().to_string()).unwrap_or_default();
+    let name = match path.extension() {
+        Some(ext) => format!("{} ({}).{}", stem, n, ext.to_string_lossy()),
+        None => format!("{} ({})", stem, n),
+    };
+    path.with_file_name(name)
+}
+
+fn image_files(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut images: Vec<PathBuf> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.is_file()
+                && path
+                    .extension()
+                    .map(|ext| IMAGE_EXTENSIONS.contains(&ext.to_string_lossy().to_lowercase().as_str()))
+                    .unwrap_or(false)
+        })
+        .collect();
+    images.sort();
+    images
+}
+
+fn move_file(from: &Path, to: &Path) -> io::Result<()> {
+    match fs::rename(from, to) {
+        // Entre sistemas de arquivos diferentes (ex.: disco local → NAS) o rename não serve;
+        // qualquer outro erro (permissão, destino inválido) volta como está
+        Err(e) if e.raw_os_error() == Some(EXDEV) => {
+            fs::copy(from, to)?;
+            fs::remove_file(from)
+        }
+        result => result,
+    }
+}
+
+// Prévia das músicas, antes das capas
+struct SongPlan {
+    organize: OrganizePlan,
+    taken: HashSet<String>,
+    // Diretório de origem → diretórios de destino das músicas que saem dele
+    album_dirs: HashMap<PathBuf, HashSet<PathBuf>>,
+    // Músicas que continuam onde estão
+    staying: Vec<PathBuf>,
+}
+
+impl SongPlan {
+    // Quem vai para o lugar de outra música só anda depois que ela saiu.
+    // Num ciclo ninguém sai primeiro, então essas músicas ficam onde estão
+    fn order_moves(&mut self) {
+        let mut pending = std::mem::take(&mut self.organize.moves);
+        let mut vacated: HashSet<String> = HashSet::new();
+        let sources: HashSet<String> = pending.iter().map(|m| path_key(&m.from)).collect();
+
+        loop {
+            let (ready, waiting): (Vec<PlannedMove>, Vec<PlannedMove>) = pending.into_iter().partition(|m| {
+                // Mudar só maiúsculas/minúsculas é ir para o próprio lugar
+                let key = path_key(&m.to);
+                key == path_key(&m.from) || !sources.contains(&key) || vacated.contains(&key)
+            });
+            if ready.is_empty() {
+                pending = waiting;
+                break;
+            }
+            vacated.extend(ready.iter().map(|m| path_key(&m.from)));
+            self.organize.moves.extend(ready);
+            pending = waiting;
+        }
+
+        self.staying.extend(pending.into_iter().map(|m| m.from));
+    }
+
+    // As capas vão junto com o álbum; se ele se dividiu ou ficou alguma música, são copiadas
+    fn finish(mut self) -> OrganizePlan {
+        let staying: HashSet<PathBuf> = self
+            .staying
+            .iter()
+            .filter_map(|path| path.parent().map(Path::to_path_buf))
+            .collect();
+        let mut source_dirs: Vec<&PathBuf> = self.album_dirs.keys().collect();
+        source_dirs.sort();
+        for source_dir in source_dirs {
+            let target_dirs = &self.album_dirs[source_dir];
+            let copy = target_dirs.len() > 1 || staying.contains(source_dir);
+            for image in image_files(source_dir) {
+                let Some(name) = image.file_name() else {
+                    continue;
+                };
+                for target_dir in target_dirs {
+                    let target = target_dir.join(name);
+                    if self.taken.contains(&path_key(&target)) || target.exists() {
+                        self.organize
+                            .skipped
+                            .push((image.clone(), format!("{} already exists", target.display())));
+                        continue;
+                    }
+                    self.taken.insert(path_key(&target));
+                    self.organize.moves.push(PlannedMove {
+                        from: image.clone(),
+                        to: target,
+                        kind: MoveKind::Cover,
+                        conflict: false,
+                        copy,
+                    });
+                }
+            }
+        }
+        self.organize
+    }
+}
+
+impl MusicLibrary {
+    // Prévia (dry run): nada é tocado no disco
+    pub fn plan_organize(&self, root: &Path, pattern: &str, policy: ConflictPolicy) -> Result<OrganizePlan> {
+        // Primeira grafia usada para cada pasta de destino
+        let mut dir_spellings: HashMap<String, PathBuf> = HashMap::new();
+        let mut targets = Vec::with_capacity(self.songs.len());
+        for song in &self.songs {
+            let mut target = root.join(render_pattern(pattern, song)?);
+            if let Some(ext) = song.file_path.extension() {
+                target.set_extension(ext.to_string_lossy().to_lowercase());
+            }
+            if let (Some(dir), Some(name)) = (target.parent(), target.file_name()) {
+                let dir = dir_spellings.entry(path_key(dir)).or_insert_with(|| dir.to_path_buf());
+                target = dir.join(name);
+            }
+            targets.push(target);
+        }
+
+        // Começa supondo que toda música fora do lugar sai dele, liberando o caminho para outra.
+        // Quem acaba ficando (pulada ou presa num ciclo) deixa de liberar, e a prévia é refeita
+        let library: HashSet<String> = self.songs.iter().map(|song| path_key(&song.file_path)).collect();
+        let mut leaving: HashSet<String> = self
+            .songs
+            .iter()
+            .zip(&targets)
+            .filter(|(song, target)| song.file_path != **target)
+            .map(|(song, _)| path_key(&song.file_path))
+            .collect();
+
+        loop {
+            let plan = self.plan_songs(root, &targets, &library, &leaving, policy);
+            let stuck: Vec<String> = plan
+                .staying
+                .iter()
+                .map(|path| path_key(path))
+                .filter(|key| leaving.contains(key))
+                .collect();
+            if stuck.is_empty() {
+                return Ok(plan.finish());
+            }
+            for key in stuck {
+                leaving.remove(&key);
+            }
+        }
+    }
+
+    fn plan_songs(
+        &self,
+        root: &Path,
+        targets: &[PathBuf],
+        library: &HashSet<String>,
+        leaving: &HashSet<String>,
+        policy: ConflictPolicy
+    ) -> SongPlan {
+        let mut plan = SongPlan {
+            organize: OrganizePlan {
+                root: root.to_path_buf(),
+                moves: Vec::new(),
+                skipped: Vec::new(),
+            },
+            taken: HashSet::new(),
+            album_dirs: HashMap::new(),
+            staying: Vec::new(),
+        };
+        // Outra música da biblioteca que continua nesse caminho; nunca é sobrescrita
+        let occupied = |key: &String| library.contains(key) && !leaving.contains(key);
+
+        for (song, target) in self.songs.iter().zip(targets) {
+            let mut target = target.clone();
+            let source_dir = song.file_path.parent().map(Path::to_path_buf).unwrap_or_default();
+
+            if target == song.file_path {
+                plan.taken.insert(path_key(&target));
+                plan.staying.push(song.file_path.clone());
+                continue;
+            }
+
+            let key = path_key(&target);
+            let freed = library.contains(&key) && leaving.contains(&key);
+            let on_disk = !freed && target.exists() && !is_same_file(&song.file_path, &target);
+            let claimed = plan.taken.contains(&key) || occupied(&key);
+            let conflict = on_disk || claimed;
+
+            if conflict {
+                match policy {
+                    ConflictPolicy::Skip => {
+                        plan.organize
+                            .skipped
+                            .push((song.file_path.clone(), format!("{} already exists", target.display())));
+                        plan.staying.push(song.file_path.clone());
+                        continue;
+                    }
+                    // Só arquivos de fora da biblioteca são sobrescritos
+                    ConflictPolicy::Overwrite if !claimed => {}
+                    _ => {
+                        let mut n = 2;
+                        let mut numbered = numbered_path(&target, n);
+                        while numbered != song.file_path
+                            && (numbered.exists()
+                                || plan.taken.contains(&path_key(&numbered))
+                                || library.contains(&path_key(&numbered)))
+                        {
+                            n += 1;
+                            numbered = numbered_path(&target, n);
+                        }
+                        target = numbered;
+                    }
+                }
+            }
+
+            // Já estava no lugar certo de uma organização anterior
+            if target == song.file_path {
+                plan.taken.insert(path_key(&target));
+                plan.staying.push(song.file_path.clone());
+                continue;
+            }
+
+            plan.taken.insert(path_key(&target));
+            if let Some(target_dir) = target.parent() {
+                plan.album_dirs.entry(source_dir).or_default().insert(target_dir.to_path_buf());
+            }
+            plan.organize.moves.push(PlannedMove {
+                from: song.file_path.clone(),
+                to: target,
+                kind: MoveKind::Song,
+                conflict,
+                copy: false,
+            });
+        }
+
+        plan.order_moves();
+        plan
+    }
+
+    pub fn apply_organize(&mut self, plan: &OrganizePlan) -> Vec<(PathBuf, anyhow::Error)> {
+        let mut errors = Vec::new();
+        let mut source_dirs: HashSet<PathBuf> = HashSet::new();
+        let mut moved_covers: Vec<(PathBuf, PathBuf)> = Vec::new();
+
+        for planned in &plan.moves {
+            let result = (|| -> io::Result<()> {
+                if let Some(parent) = planned.to.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                if planned.copy {
+                    fs::copy(&planned.from, &planned.to).map(|_| ())
+                } else {
+                    move_file(&planned.from, &planned.to)
+                }
+            })();
+
+            if let Err(e) = result {
+                errors.push((planned.from.clone(), e.into()));
+                continue;
+            }
+
+            if let Some(parent) = planned.from.parent() {
+                source_dirs.insert(parent.to_path_buf());
+            }
+            match planned.kind {
+                MoveKind::Song => self.relocate_song(&planned.from, &planned.to),
+                MoveKind::Cover if !planned.copy => moved_covers.push((planned.from.clone(), planned.to.clone())),
+                MoveKind::Cover => {}
+            }
+        }
+
+        for song in &mut self.songs {
+            if let Some((_, to)) = moved_covers.iter().find(|(from, _)| Some(from) == song.cover_path.as_ref()) {
+                song.cover_path = Some(to.clone());
+            }
+        }
+
+        // Apaga as pastas que ficaram vazias, subindo até a raiz
+        for dir in source_dirs {
+            let mut dir = dir.as_path();
+            while dir.starts_with(&plan.root) && dir != plan.root && fs::remove_dir(dir).is_ok() {
+                match dir.parent() {
+                    Some(parent) => dir = parent,
+                    None => break,
+                }
+            }
+        }
+
+        self.organize_library();
+        errors
+    }
+
+    // Atualiza o caminho na biblioteca e nas playlists para nada apontar para o lugar antigo
+    fn relocate_song(&mut self, from: &Path, to: &Path) {
+        for song in self.songs.iter_mut().filter(|song| song.file_path == from) {
+            song.file_path = to.to_path_buf();
+        }
+        for playlist in &mut self.playlists {
+            for path in playlist.songs.iter_mut().filter(|path| path.as_path() == from) {
+                *path = to.to_path_buf();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Pasta temporária própria de cada teste, apagada no fim
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let path = std::env::temp_dir().join(format!("librefy-organize-{}-{}", name, std::process::id()));
+            let _ = fs::remove_dir_all(&path);
+            fs::create_dir_all(&path).unwrap();
+            Self(path)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn library(root: &Path, songs: &[(&str, &str)]) -> MusicLibrary {
+        let mut library = MusicLibrary::new();
+        for (file, title) in songs {
+            let path = root.join(file);
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(&path, b"").unwrap();
+            library.songs.push(Song::new(path, title.to_string(), "Artist".to_string(), "Album".to_string(), None));
+        }
+        library
+    }
+
+    fn moves(plan: &OrganizePlan, root: &Path) -> Vec<(String, String)> {
+        let relative = |path: &Path| path.strip_prefix(root).unwrap().display().to_string();
+        plan.moves.iter().map(|m| (relative(&m.from), relative(&m.to))).collect()
+    }
+
+    fn pair(from: &str, to: &str) -> (String, String) {
+        (from.to_string(), to.to_string())
+    }
+
+    #[test]
+    fn renders_and_sanitizes_patterns() {
+        let mut song = Song::new("/x/a.mp3".into(), "What?".to_string(), "AC/DC".to_string(), "Back".to_string(), None);
+        song.track_number = Some(3);
+        assert_eq!(
+            render_pattern("{artist}/{track:02} {title}", &song).unwrap(),
+            PathBuf::from("AC_DC/03 What")
+        );
+        assert_eq!(render_pattern("{year} - {album}", &song).unwrap(), PathBuf::from("Back"));
+        assert!(render_pattern("{nope}", &song).is_err());
+        assert!(render_pattern("{title", &song).is_err());
+        assert_eq!(sanitize_component("con"), "con_");
+        assert_eq!(sanitize_component(" - "), "Unknown");
+    }
+
+    #[test]
+    fn never_overwrites_a_library_song_that_stays() {
+        let dir = TempDir::new("stays");
+        let root = dir.0.as_path();
+        let library = library(root, &[("in/one.mp3", "b"), ("b.mp3", "b")]);
+
+        let plan = library.plan_organize(root, "{title}", ConflictPolicy::Overwrite).unwrap();
+        assert_eq!(moves(&plan, root), vec![pair("in/one.mp3", "b (2).mp3")]);
+    }
+
+    #[test]
+    fn overwrites_files_outside_the_library() {
+        let dir = TempDir::new("outside");
+        let root = dir.0.as_path();
+        let library = library(root, &[("in/one.mp3", "b")]);
+        fs::write(root.join("b.mp3"), b"").unwrap();
+
+        let plan = library.plan_organize(root, "{title}", ConflictPolicy::Overwrite).unwrap();
+        assert_eq!(moves(&plan, root), vec![pair("in/one.mp3", "b.mp3")]);
+        assert!(plan.moves[0].conflict);
+
+        let plan = library.plan_organize(root, "{title}", ConflictPolicy::Skip).unwrap();
+        assert!(plan.moves.is_empty());
+        assert_eq!(plan.skipped.len(), 1);
+    }
+
+    #[test]
+    fn uses_targets_freed_by_other_moves() {
+        let dir = TempDir::new("freed");
+        let root = dir.0.as_path();
+        // a.mp3 vai para onde está b.mp3, que antes sai para c.mp3
+        let library = library(root, &[("a.mp3", "b"), ("b.mp3", "c")]);
+
+        for policy in ConflictPolicy::ALL {
+            let plan = library.plan_organize(root, "{title}", policy).unwrap();
+            assert_eq!(moves(&plan, root), vec![pair("b.mp3", "c.mp3"), pair("a.mp3", "b.mp3")]);
+            assert!(plan.skipped.is_empty());
+        }
+    }
+
+    #[test]
+    fn swapped_songs_do_not_overwrite_each_other() {
+        let dir = TempDir::new("swap");
+        let root = dir.0.as_path();
+        let library = library(root, &[("a.mp3", "b"), ("b.mp3", "a")]);
+
+        let plan = library.plan_organize(root, "{title}", ConflictPolicy::Skip).unwrap();
+        assert!(plan.moves.is_empty());
+        assert_eq!(plan.skipped.len(), 2);
+
+        for policy in [ConflictPolicy::KeepBoth, ConflictPolicy::Overwrite] {
+            let plan = library.plan_organize(root, "{title}", policy).unwrap();
+            assert_eq!(
+                moves(&plan, root),
+                vec![pair("a.mp3", "b (2).mp3"), pair("b.mp3", "a (2).mp3")]
+            );
+        }
+    }
+
+    #[test]
+    fn songs_with_the_same_target_keep_both() {
+        let dir = TempDir::new("same");
+        let root = dir.0.as_path();
+        let library = library(root, &[("x/1.mp3", "Song"), ("y/2.mp3", "SONG")]);
+
+        let plan = library.plan_organize(root, "{title}", ConflictPolicy::Overwrite).unwrap();
+        assert_eq!(
+            moves(&plan, root),
+            vec![pair("x/1.mp3", "Song.mp3"), pair("y/2.mp3", "SONG (2).mp3")]
+        );
+        assert!(!plan.moves[0].conflict);
+        assert!(plan.moves[1].conflict);
+    }
+
+    #[test]
+    fn covers_follow_their_album() {
+        let dir = TempDir::new("covers");
+        let root = dir.0.as_path();
+        let library = library(root, &[("old/1.mp3", "one"), ("old/2.mp3", "two")]);
+        fs::write(root.join("old/cover.jpg"), b"").unwrap();
+
+        let plan = library.plan_organize(root, "{album}/{title}", ConflictPolicy::KeepBoth).unwrap();
+        let cover = plan.moves.iter().find(|m| m.kind == MoveKind::Cover).unwrap();
+        assert_eq!(cover.to, root.join("Album/cover.jpg"));
+        assert!(!cover.copy);
+    }
+}
@@ -0,0 +1,612 @@
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use anyhow::Result;
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use serde::{Deserialize, Serialize};
+
+use super::{MusicLibrary, Song};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Playlist {
+    pub name: String,
+    pub songs: Vec<PathBuf>, // Referência às músicas pelo caminho do arquivo
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlaylistFormat {
+    M3u,
+    M3u8,
+    Pls,
+    Xspf,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathStyle {
+    Absolute,
+    Relative,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PlaylistEntry {
+    pub location: String,
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub duration: Option<i64>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ImportReport {
+    pub playlist: Playlist,
+    pub unmatched: Vec<String>,
+}
+
+impl Playlist {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            songs: Vec::new(),
+        }
+    }
+}
+
+impl PlaylistFormat {
+    pub const ALL: [PlaylistFormat; 4] = [
+        PlaylistFormat::M3u,
+        PlaylistFormat::M3u8,
+        PlaylistFormat::Pls,
+        PlaylistFormat::Xspf,
+    ];
+
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let ext = path.extension()?.to_string_lossy().to_lowercase();
+        match ext.as_str() {
+            "m3u" => Some(PlaylistFormat::M3u),
+            "m3u8" => Some(PlaylistFormat::M3u8),
+            "pls" => Some(PlaylistFormat::Pls),
+            "xspf" => Some(PlaylistFormat::Xspf),
+            _ => None,
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            PlaylistFormat::M3u => "m3u",
+            PlaylistFormat::M3u8 => "m3u8",
+            PlaylistFormat::Pls => "pls",
+            PlaylistFormat::Xspf => "xspf",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            PlaylistFormat::M3u => "M3U",
+            PlaylistFormat::M3u8 => "M3U8 (UTF-8)",
+            PlaylistFormat::Pls => "PLS",
+            PlaylistFormat::Xspf => "XSPF",
+        }
+    }
+}
+
+pub fn read_playlist(path: &Path) -> Result<(PlaylistFormat, Vec<PlaylistEntry>)> {
+    let format = PlaylistFormat::from_path(path)
+        .ok_or_else(|| anyhow::anyhow!("Unsupported playlist format: {}", path.display()))?;
+
+    let bytes = fs::read(path)?;
+    // M3U "clássico" costuma vir em Latin-1, então caímos para ele se não for UTF-8
+    let text = match String::from_utf8(bytes) {
+        Ok(text) => text,
+        Err(e) => e.into_bytes().iter().map(|&b| b as char).collect(),
+    };
+    let text = text.trim_start_matches('\u{feff}');
+
+    let entries = match format {
+        PlaylistFormat::M3u | PlaylistFormat::M3u8 => parse_m3u(text),
+        PlaylistFormat::Pls => parse_pls(text),
+        PlaylistFormat::Xspf => parse_xspf(text)?,
+    };
+
+    Ok((format, entries))
+}
+
+fn parse_m3u(text: &str) -> Vec<PlaylistEntry> {
+    let mut entries = Vec::new();
+    let mut pending = PlaylistEntry::default();
+
+    for line in text.lines() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        if let Some(info) = line.strip_prefix("#EXTINF:") {
+            // #EXTINF:<segundos> [atributos],<Artista> - <Título>
+            let (duration, display) = match info.find(',') {
+                Some(pos) => (&info[..pos], info[pos + 1..].trim()),
+                None => (info, ""),
+            };
+            pending.duration = duration
+                .split_whitespace()
+                .next()
+                .and_then(|d| d.parse::<i64>().ok())
+                .filter(|d| *d >= 0);
+
+            let (artist, title) = split_display_name(display);
+            pending.artist = artist;
+            pending.title = title;
+        } else if !line.starts_with('#') {
+            pending.location = line.to_string();
+            entries.push(std::mem::take(&mut pending));
+        }
+    }
+
+    entries
+}
+
+fn parse_pls(text: &str) -> Vec<PlaylistEntry> {
+    let mut entries: BTreeMap<usize, PlaylistEntry> = BTreeMap::new();
+
+    for line in text.lines() {
+        let Some((key, value)) = line.trim().split_once('=') else {
+            continue;
+        };
+        let key = key.trim().to_lowercase();
+        let value = value.trim();
+
+        let (field, index) = if let Some(index) = key.strip_prefix("file") {
+            ("file", index)
+        } else if let Some(index) = key.strip_prefix("title") {
+            ("title", index)
+        } else if let Some(index) = key.strip_prefix("length") {
+            ("length", index)
+        } else {
+            continue;
+        };
+
+        let Ok(index) = index.parse::<usize>() else {
+            continue;
+        };
+        let entry = entries.entry(index).or_default();
+
+        match field {
+            "file" => entry.location = value.to_string(),
+            "title" => {
+                let (artist, title) = split_display_name(value);
+                entry.artist = artist;
+                entry.title = title;
+            }
+            _ => entry.duration = value.parse::<i64>().ok().filter(|d| *d >= 0),
+        }
+    }
+
+    entries
+        .into_values()
+        .filter(|entry| !entry.location.is_empty())
+        .collect()
+}
+
+fn parse_xspf(text: &str) -> Result<Vec<PlaylistEntry>> {
+    let mut reader = Reader::from_str(text);
+    reader.trim_text(true);
+
+    let mut entries = Vec::new();
+    let mut current: Option<PlaylistEntry> = None;
+    let mut field = String::new();
+
+    loop {
+        match reader.read_event()? {
+            Event::Start(e) => {
+                field = String::from_utf8_lossy(e.local_name().as_ref()).to_string();
+                if field == "track" {
+                    current = Some(PlaylistEntry::default());
+                }
+            }
+            Event::Text(t) => {
+                if let Some(entry) = current.as_mut() {
+                    let value = t.unescape()?.trim().to_string();
+                    match field.as_str() {
+                        "location" if entry.location.is_empty() => entry.location = value,
+                        "title" => entry.title = Some(value),
+                        "creator" => entry.artist = Some(value),
+                        "duration" => {
+                            entry.duration = value.parse::<i64>().ok().map(|ms| ms / 1000)
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            Event::End(e) => {
+                if e.local_name().as_ref() == b"track" {
+                    if let Some(entry) = current.take() {
+                        if !entry.location.is_empty() {
+                            entries.push(entry);
+                        }
+                    }
+                }
+                field.clear();
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    Ok(entries)
+}
+
+fn split_display_name(display: &str) -> (Option<String>, Option<String>) {
+    if display.is_empty() {
+        return (None, None);
+    }
+
+    match display.split_once(" - ") {
+        Some((artist, title)) if !artist.trim().is_empty() && !title.trim().is_empty() => {
+            (Some(artist.trim().to_string()), Some(title.trim().to_string()))
+        }
+        _ => (None, Some(display.to_string())),
+    }
+}
+
+fn resolve_location(location: &str, base_dir: &Path) -> PathBuf {
+    if location.starts_with("file:") {
+        if let Ok((path, _)) = glib::filename_from_uri(location) {
+            return path;
+        }
+    }
+
+    // Locais relativos do XSPF vêm com escape de URI
+    let location = if location.contains('%') {
+        glib::Uri::unescape_string(location, None::<&str>)
+            .map(|s| s.to_string())
+            .unwrap_or_else(|| location.to_string())
+    } else {
+        location.to_string()
+    };
+
+    // Playlists feitas no Windows usam barra invertida
+    let path = PathBuf::from(location.replace('\\', "/"));
+    if path.is_absolute() {
+        path
+    } else {
+        normalize_path(&base_dir.join(path))
+    }
+}
+
+fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other.as_os_str()),
+        }
+    }
+    normalized
+}
+
+fn relative_path(from_dir: &Path, to: &Path) -> PathBuf {
+    let from_dir = normalize_path(from_dir);
+    let to = normalize_path(to);
+    let from: Vec<Component> = from_dir.components().collect();
+    let to_components: Vec<Component> = to.components().collect();
+
+    let common = from
+        .iter()
+        .zip(to_components.iter())
+        .take_while(|(a, b)| a == b)
+        .count();
+
+    let mut relative = PathBuf::new();
+    for _ in common..from.len() {
+        relative.push("..");
+    }
+    for component in &to_components[common..] {
+        relative.push(component.as_os_str());
+    }
+    relative
+}
+
+fn xml_escape(text: &str) -> String {
+    quick_xml::escape::escape(text).to_string()
+}
+
+// Acha a música da biblioteca para cada entrada de uma playlist importada.
+// Os caminhos canônicos são calculados uma vez só, na primeira entrada que precisar
+struct EntryResolver<'a> {
+    songs: &'a [Song],
+    canonical: std::cell::OnceCell<HashMap<PathBuf, usize>>,
+}
+
+impl<'a> EntryResolver<'a> {
+    fn new(songs: &'a [Song]) -> Self {
+        Self { songs, canonical: std::cell::OnceCell::new() }
+    }
+
+    fn find(&self, location: &Path, entry: &PlaylistEntry) -> Option<&'a Song> {
+        if let Some(song) = self.songs.iter().find(|s| s.file_path == location) {
+            return Some(song);
+        }
+
+        if let Ok(canonical) = location.canonicalize() {
+            let paths = self.canonical.get_or_init(|| {
+                self.songs
+                    .iter()
+                    .enumerate()
+                    .filter_map(|(index, song)| song.file_path.canonicalize().ok().map(|path| (path, index)))
+                    .collect()
+            });
+            if let Some(&index) = paths.get(&canonical) {
+                return Some(&self.songs[index]);
+            }
+        }
+
+        if let Some(song) = match_by_suffix(self.songs, location) {
+            return Some(song);
+        }
+
+        // Pelo título só quando uma música só combina
+        let title = entry.title.as_ref()?.to_lowercase();
+        let mut candidates = self.songs.iter().filter(|song| {
+            song.title.to_lowercase() == title
+                && entry
+                    .artist
+                    .as_ref()
+                    .map(|artist| song.artist.to_lowercase() == artist.to_lowercase())
+                    .unwrap_or(true)
+        });
+        let song = candidates.next()?;
+        candidates.next().is_none().then_some(song)
+    }
+}
+
+// O arquivo pode ter vindo de outra máquina: compara pelo final do caminho.
+// Exige pelo menos a pasta e o nome do arquivo iguais, e recusa empates
+fn match_by_suffix<'a>(songs: &'a [Song], location: &Path) -> Option<&'a Song> {
+    const MIN_SHARED: usize = 2;
+    let wanted: Vec<Component> = location.components().rev().collect();
+    let mut best: Option<(usize, &Song)> = None;
+    let mut tied = false;
+    for song in songs {
+        let shared = song
+            .file_path
+            .components()
+            .rev()
+            .zip(wanted.iter())
+            .take_while(|(a, b)| a == *b)
+            .count();
+        if shared < MIN_SHARED {
+            continue;
+        }
+        match best {
+            Some((count, _)) if shared < count => {}
+            Some((count, _)) if shared == count => tied = true,
+            _ => {
+                best = Some((shared, song));
+                tied = false;
+            }
+        }
+    }
+    if tied {
+        return None;
+    }
+    best.map(|(_, song)| song)
+}
+
+impl MusicLibrary {
+    pub fn import_playlist(&mut self, path: &Path) -> Result<ImportReport> {
+        let (_, entries) = read_playlist(path)?;
+        let base_dir = path.parent().unwrap_or(Path::new("."));
+
+        let name = path
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("Imported Playlist")
+            .to_string();
+
+        let mut playlist = Playlist::new(&name);
+        let mut unmatched = Vec::new();
+        let resolver = EntryResolver::new(&self.songs);
+
+        for entry in &entries {
+            let location = resolve_location(&entry.location, base_dir);
+            match resolver.find(&location, entry) {
+                Some(song) => playlist.songs.push(song.file_path.clone()),
+                None => unmatched.push(entry.location.clone()),
+            }
+        }
+
+        println!(
+            "Imported playlist {}: {} songs, {} unmatched",
+            playlist.name,
+            playlist.songs.len(),
+            unmatched.len()
+        );
+
+        self.playlists.push(playlist.clone());
+        Ok(ImportReport { playlist, unmatched })
+    }
+
+    pub fn playlist_songs(&self, playlist: &Playlist) -> Vec<&Song> {
+        playlist
+            .songs
+            .iter()
+            .filter_map(|path| self.songs.iter().find(|song| &song.file_path == path))
+            .collect()
+    }
+
+    pub fn export_playlist(
+        &self,
+        playlist: &Playlist,
+        path: &Path,
+        format: PlaylistFormat,
+        style: PathStyle,
+    ) -> Result<()> {
+        let base_dir = path.parent().unwrap_or(Path::new("."));
+        let songs = self.playlist_songs(playlist);
+
+        let location = |song: &Song| -> PathBuf {
+            match style {
+                PathStyle::Absolute => song.get_file_path().to_path_buf(),
+                PathStyle::Relative => relative_path(base_dir, song.get_file_path()),
+            }
+        };
+
+        let mut output = String::new();
+        match format {
+            PlaylistFormat::M3u | PlaylistFormat::M3u8 => {
+                output.push_str("#EXTM3U\n");
+                for song in &songs {
+                    let duration = song.duration.map_or(-1, |d| d as i64);
+                    output.push_str(&format!("#EXTINF:{},{}\n", duration, song.get_display_name()));
+                    output.push_str(&format!("{}\n", location(song).display()));
+                }
+            }
+            PlaylistFormat::Pls => {
+                output.push_str("[playlist]\n");
+                for (i, song) in songs.iter().enumerate() {
+                    let n = i + 1;
+                    output.push_str(&format!("File{}={}\n", n, location(song).display()));
+                    output.push_str(&format!("Title{}={}\n", n, song.get_display_name()));
+                    output.push_str(&format!("Length{}={}\n", n, song.duration.map_or(-1, |d| d as i64)));
+                }
+                output.push_str(&format!("NumberOfEntries={}\nVersion=2\n", songs.len()));
+            }
+            PlaylistFormat::Xspf => {
+                output.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+                output.push_str("<playlist version=\"1\" xmlns=\"http://xspf.org/ns/0/\">\n");
+                output.push_str(&format!("  <title>{}</title>\n", xml_escape(&playlist.name)));
+                output.push_str("  <trackList>\n");
+                for song in &songs {
+                    let uri = match style {
+                        PathStyle::Absolute => glib::filename_to_uri(song.get_file_path(), None)?
+                            .to_string(),
+                        PathStyle::Relative => glib::Uri::escape_string(
+                            &location(song).to_string_lossy(),
+                            Some("/"),
+                            true,
+                        )
+                        .to_string(),
+                    };
+                    output.push_str("    <track>\n");
+                    output.push_str(&format!("      <location>{}</location>\n", xml_escape(&uri)));
+                    output.push_str(&format!("      <title>{}</title>\n", xml_escape(&song.title)));
+                    output.push_str(&format!("      <creator>{}</creator>\n", xml_escape(&song.artist)));
+                    output.push_str(&format!("      <album>{}</album>\n", xml_escape(&song.album)));
+                    output.push_str("    </track>\n");
+                }
+                output.push_str("  </trackList>\n</playlist>\n");
+            }
+        }
+
+        // M3U sem o "8" é tradicionalmente Latin-1; só usamos UTF-8 se não couber
+        if format == PlaylistFormat::M3u && output.chars().all(|c| (c as u32) < 0x100) {
+            fs::write(path, output.chars().map(|c| c as u8).collect::<Vec<u8>>())?;
+        } else {
+            fs::write(path, output)?;
+        }
+        println!("Exported playlist {} to {}", playlist.name, path.display());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn song(path: &str, artist: &str, title: &str) -> Song {
+        Song::new(PathBuf::from(path), title.to_string(), artist.to_string(), String::new(), None)
+    }
+
+    fn entry(title: Option<&str>, artist: Option<&str>) -> PlaylistEntry {
+        PlaylistEntry {
+            title: title.map(str::to_string),
+            artist: artist.map(str::to_string),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn parses_m3u_entries() {
+        let text = "#EXTM3U\n#EXTINF:215,Artist - Title\nmusic/a.mp3\n\n/abs/b.flac\n";
+        let entries = parse_m3u(text);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].location, "music/a.mp3");
+        assert_eq!(entries[0].artist.as_deref(), Some("Artist"));
+        assert_eq!(entries[0].title.as_deref(), Some("Title"));
+        assert_eq!(entries[0].duration, Some(215));
+        assert_eq!(entries[1].title, None);
+    }
+
+    #[test]
+    fn resolves_relative_and_windows_locations() {
+        let base = Path::new("/home/me/lists");
+        assert_eq!(resolve_location("../Music/a.mp3", base), PathBuf::from("/home/me/Music/a.mp3"));
+        assert_eq!(resolve_location("Sub\\My%20Song.mp3", base), PathBuf::from("/home/me/lists/Sub/My Song.mp3"));
+        assert_eq!(resolve_location("file:///music/a%20b.mp3", base), PathBuf::from("/music/a b.mp3"));
+    }
+
+    #[test]
+    fn matches_by_folder_and_file_name() {
+        let songs = vec![
+            song("/home/me/Music/Album/01.mp3", "A", "One"),
+            song("/home/me/Music/Other/01.mp3", "B", "Two"),
+        ];
+        let found = match_by_suffix(&songs, Path::new("C:/Users/me/Music/Album/01.mp3"));
+        assert_eq!(found.unwrap().title, "One");
+        // Só o nome do arquivo não basta
+        assert!(match_by_suffix(&songs, Path::new("/elsewhere/01.mp3")).is_none());
+    }
+
+    #[test]
+    fn refuses_ambiguous_suffix_matches() {
+        let songs = vec![
+            song("/disk1/Music/Album/01.mp3", "A", "One"),
+            song("/disk2/Music/Album/01.mp3", "A", "One copy"),
+        ];
+        assert!(match_by_suffix(&songs, Path::new("/other/Music/Album/01.mp3")).is_none());
+        let found = match_by_suffix(&songs, Path::new("/disk2/Music/Album/01.mp3"));
+        assert_eq!(found.unwrap().title, "One copy");
+    }
+
+    #[test]
+    fn exports_known_durations() {
+        let mut library = MusicLibrary::new();
+        let mut timed = song("/music/a.mp3", "A", "One");
+        timed.duration = Some(215);
+        library.songs = vec![timed, song("/music/b.mp3", "B", "Two")];
+        let mut playlist = Playlist::new("Mix");
+        playlist.songs = library.songs.iter().map(|song| song.file_path.clone()).collect();
+
+        let dir = std::env::temp_dir().join(format!("librefy-playlist-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        for format in [PlaylistFormat::M3u8, PlaylistFormat::Pls] {
+            let path = dir.join(format!("mix.{}", format.extension()));
+            library.export_playlist(&playlist, &path, format, PathStyle::Absolute).unwrap();
+            let (_, entries) = read_playlist(&path).unwrap();
+            let durations: Vec<Option<i64>> = entries.iter().map(|entry| entry.duration).collect();
+            assert_eq!(durations, vec![Some(215), None]);
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn resolver_falls_back_to_title_and_artist() {
+        let songs = vec![
+            song("/music/x.mp3", "Someone", "Song"),
+            song("/music/y.mp3", "Artist", "Song"),
+        ];
+        let resolver = EntryResolver::new(&songs);
+        let missing = Path::new("/nowhere/z.mp3");
+
+        let found = resolver.find(Path::new("/music/y.mp3"), &entry(None, None));
+        assert_eq!(found.unwrap().file_path, PathBuf::from("/music/y.mp3"));
+        let found = resolver.find(missing, &entry(Some("song"), Some("ARTIST")));
+        assert_eq!(found.unwrap().file_path, PathBuf::from("/music/y.mp3"));
+        assert!(resolver.find(missing, &entry(Some("Other"), None)).is_none());
+        // Dois títulos iguais sem artista para desempatar
+        assert!(resolver.find(missing, &entry(Some("Song"), None)).is_none());
+        assert!(resolver.find(missing, &entry(None, Some("Artist"))).is_none());
+    }
+}
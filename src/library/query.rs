@@ -0,0 +1,709 @@
+use std::fmt;
+
+use super::search::{match_score, normalize, SearchText};
+use super::Song;
+
+// Peso de cada campo na relevância: título pesa mais que artista, que pesa mais que álbum
+const TITLE_WEIGHT: u32 = 3;
+const ARTIST_WEIGHT: u32 = 2;
+const ALBUM_WEIGHT: u32 = 1;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QueryError {
+    pub message: String,
+    pub position: usize, // posição (em caracteres) dentro da consulta
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (at position {})", self.message, self.position + 1)
+    }
+}
+
+impl std::error::Error for QueryError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QueryField {
+    Title,
+    Artist,
+    Album,
+    AlbumArtist,
+    Genre,
+    Path,
+    Year,
+    Duration,
+    Bitrate,
+    PlayCount,
+    SkipCount,
+    Rating,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Matcher {
+    Text { text: String, exact: bool },
+    Range(Option<u64>, Option<u64>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Term {
+    pub field: Option<QueryField>,
+    pub matcher: Matcher,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Query {
+    All,
+    Term(Term),
+    Not(Box<Query>),
+    And(Vec<Query>),
+    Or(Vec<Query>),
+}
+
+// Texto (já normalizado) a destacar nos resultados, e o campo em que ele foi buscado
+#[derive(Debug, Clone, PartialEq)]
+pub struct Highlight {
+    pub field: Option<QueryField>,
+    pub text: String,
+}
+
+impl Highlight {
+    // Termos sem campo procuram no título, no artista e no álbum
+    pub fn applies_to(&self, field: QueryField) -> bool {
+        match self.field {
+            Some(own) => own == field,
+            None => matches!(field, QueryField::Title | QueryField::Artist | QueryField::Album),
+        }
+    }
+
+    pub fn texts_for(highlights: &[Highlight], field: QueryField) -> Vec<String> {
+        highlights
+            .iter()
+            .filter(|highlight| highlight.applies_to(field))
+            .map(|highlight| highlight.text.clone())
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum TokenKind {
+    LParen,
+    RParen,
+    Or,
+    And,
+    Not,
+    Term {
+        field: Option<String>,
+        value: String,
+        quoted: bool,
+        negated: bool,
+    },
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    kind: TokenKind,
+    position: usize,
+}
+
+impl QueryField {
+    fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "title" | "t" => Some(QueryField::Title),
+            "artist" | "a" => Some(QueryField::Artist),
+            "album" | "al" => Some(QueryField::Album),
+            "albumartist" | "aa" => Some(QueryField::AlbumArtist),
+            "genre" | "g" => Some(QueryField::Genre),
+            "path" | "file" => Some(QueryField::Path),
+            "year" | "y" => Some(QueryField::Year),
+            "duration" | "length" => Some(QueryField::Duration),
+            "bitrate" => Some(QueryField::Bitrate),
+            "plays" | "playcount" => Some(QueryField::PlayCount),
+            "skips" | "skipcount" => Some(QueryField::SkipCount),
+            "rating" | "stars" => Some(QueryField::Rating),
+            _ => None,
+        }
+    }
+
+    fn is_numeric(&self) -> bool {
+        matches!(
+            self,
+            QueryField::Year
+                | QueryField::Duration
+                | QueryField::Bitrate
+                | QueryField::PlayCount
+                | QueryField::SkipCount
+                | QueryField::Rating
+        )
+    }
+
+    fn text<'a>(&self, text: &'a SearchText) -> Option<&'a str> {
+        match self {
+            QueryField::Title => Some(&text.title),
+            QueryField::Artist => Some(&text.artist),
+            QueryField::Album => Some(&text.album),
+            QueryField::AlbumArtist => text.album_artist.as_deref(),
+            QueryField::Genre => text.genre.as_deref(),
+            QueryField::Path => Some(&text.path),
+            _ => None,
+        }
+    }
+
+    fn number(&self, song: &Song) -> Option<u64> {
+        match self {
+            QueryField::Year => song.year.map(u64::from),
+            QueryField::Duration => song.duration,
+            QueryField::Bitrate => song.bitrate.map(u64::from),
+            QueryField::PlayCount => Some(u64::from(song.play_count)),
+            QueryField::SkipCount => Some(u64::from(song.skip_count)),
+            QueryField::Rating => Some(u64::from(song.rating)),
+            _ => None,
+        }
+    }
+}
+
+fn error(message: impl Into<String>, position: usize) -> QueryError {
+    QueryError {
+        message: message.into(),
+        position,
+    }
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, QueryError> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    let read_phrase = |start: usize| -> Result<(String, usize), QueryError> {
+        // `start` aponta para a aspa de abertura
+        let mut end = start + 1;
+        while end < chars.len() && chars[end] != '"' {
+            end += 1;
+        }
+        if end >= chars.len() {
+            return Err(error("Unterminated quote", start));
+        }
+        Ok((chars[start + 1..end].iter().collect(), end + 1))
+    };
+
+    while i < chars.len() {
+        let c = chars[i];
+        let position = i;
+
+        if c.is_whitespace() {
+            i += 1;
+            continue;
+        }
+
+        let kind = match c {
+            '(' => {
+                i += 1;
+                TokenKind::LParen
+            }
+            ')' => {
+                i += 1;
+                TokenKind::RParen
+            }
+            '|' => {
+                i += 1;
+                TokenKind::Or
+            }
+            '-' if chars.get(i + 1) == Some(&'(') => {
+                i += 1;
+                TokenKind::Not
+            }
+            // Um "-" solto viraria um termo que combina com tudo
+            '-' if chars.get(i + 1).map_or(true, |c| c.is_whitespace() || *c == ')') => {
+                return Err(error("Expected a search term after '-'", position));
+            }
+            _ => {
+                let negated = c == '-';
+                if negated {
+                    i += 1;
+                }
+
+                if chars[i] == '"' {
+                    let (value, next) = read_phrase(i)?;
+                    i = next;
+                    TokenKind::Term {
+                        field: None,
+                        value,
+                        quoted: true,
+                        negated,
+                    }
+                } else {
+                    let start = i;
+                    while i < chars.len()
+                        && !chars[i].is_whitespace()
+                        && !matches!(chars[i], '(' | ')' | '"' | ':')
+                    {
+                        i += 1;
+                    }
+                    let word: String = chars[start..i].iter().collect();
+
+                    if chars.get(i) == Some(&':') {
+                        i += 1;
+                        let quoted = chars.get(i) == Some(&'"');
+                        let value = if quoted {
+                            let (value, next) = read_phrase(i)?;
+                            i = next;
+                            value
+                        } else {
+                            let value_start = i;
+                            while i < chars.len()
+                                && !chars[i].is_whitespace()
+                                && !matches!(chars[i], '(' | ')' | '"')
+                            {
+                                i += 1;
+                            }
+                            chars[value_start..i].iter().collect()
+                        };
+
+                        if value.is_empty() {
+                            return Err(error(format!("Missing value for '{}:'", word), position));
+                        }
+
+                        TokenKind::Term {
+                            field: Some(word),
+                            value,
+                            quoted,
+                            negated,
+                        }
+                    } else if !negated && word == "OR" {
+                        TokenKind::Or
+                    } else if !negated && word == "AND" {
+                        TokenKind::And
+                    } else if !negated && word == "NOT" {
+                        TokenKind::Not
+                    } else {
+                        TokenKind::Term {
+                            field: None,
+                            value: word,
+                            quoted: false,
+                            negated,
+                        }
+                    }
+                }
+            }
+        };
+
+        tokens.push(Token { kind, position });
+    }
+
+    Ok(tokens)
+}
+
+fn parse_number(text: &str, field: QueryField) -> Option<u64> {
+    // Duração aceita tanto segundos quanto "m:ss"
+    if field == QueryField::Duration {
+        if let Some((minutes, seconds)) = text.split_once(':') {
+            return minutes
+                .parse::<u64>()
+                .ok()?
+                .checked_mul(60)?
+                .checked_add(seconds.parse::<u64>().ok()?);
+        }
+    }
+    text.parse::<u64>().ok()
+}
+
+fn parse_range(value: &str, field: QueryField) -> Option<Matcher> {
+    let number = |text: &str| -> Option<Option<u64>> {
+        if text.is_empty() {
+            Some(None)
+        } else {
+            parse_number(text, field).map(Some)
+        }
+    };
+
+    if let Some((low, high)) = value.split_once("..") {
+        let (low, high) = (number(low)?, number(high)?);
+        if low.is_none() && high.is_none() {
+            return None;
+        }
+        return Some(Matcher::Range(low, high));
+    }
+
+    if let Some(rest) = value.strip_prefix(">=") {
+        return Some(Matcher::Range(Some(parse_number(rest, field)?), None));
+    }
+    if let Some(rest) = value.strip_prefix("<=") {
+        return Some(Matcher::Range(None, Some(parse_number(rest, field)?)));
+    }
+    if let Some(rest) = value.strip_prefix('>') {
+        return Some(Matcher::Range(Some(parse_number(rest, field)?.checked_add(1)?), None));
+    }
+    if let Some(rest) = value.strip_prefix('<') {
+        return Some(Matcher::Range(None, Some(parse_number(rest, field)?.checked_sub(1)?)));
+    }
+
+    let exact = parse_number(value, field)?;
+    Some(Matcher::Range(Some(exact), Some(exact)))
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    index: usize,
+    length: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&TokenKind> {
+        self.tokens.get(self.index).map(|t| &t.kind)
+    }
+
+    fn position(&self) -> usize {
+        self.tokens
+            .get(self.index)
+            .map(|t| t.position)
+            .unwrap_or(self.length)
+    }
+
+    fn parse_or(&mut self) -> Result<Query, QueryError> {
+        let mut branches = vec![self.parse_and()?];
+
+        while self.peek() == Some(&TokenKind::Or) {
+            self.index += 1;
+            if matches!(self.peek(), None | Some(TokenKind::RParen) | Some(TokenKind::Or)) {
+                return Err(error("Expected a search term after OR", self.position()));
+            }
+            branches.push(self.parse_and()?);
+        }
+
+        Ok(if branches.len() == 1 {
+            branches.remove(0)
+        } else {
+            Query::Or(branches)
+        })
+    }
+
+    fn parse_and(&mut self) -> Result<Query, QueryError> {
+        let mut terms = Vec::new();
+
+        loop {
+            match self.peek() {
+                None | Some(TokenKind::RParen) | Some(TokenKind::Or) => break,
+                Some(TokenKind::And) => self.index += 1,
+                _ => terms.push(self.parse_unary()?),
+            }
+        }
+
+        match terms.len() {
+            0 => Err(error("Expected a search term", self.position())),
+            1 => Ok(terms.remove(0)),
+            _ => Ok(Query::And(terms)),
+        }
+    }
+
+    fn parse_unary(&mut self) -> Result<Query, QueryError> {
+        let Some(token) = self.tokens.get(self.index).cloned() else {
+            return Err(error("Expected a search term", self.length));
+        };
+        self.index += 1;
+
+        match token.kind {
+            TokenKind::Not => {
+                if self.peek().is_none() {
+                    return Err(error("Expected a search term after NOT", self.length));
+                }
+                Ok(Query::Not(Box::new(self.parse_unary()?)))
+            }
+            TokenKind::LParen => {
+                let inner = self.parse_or()?;
+                if self.peek() != Some(&TokenKind::RParen) {
+                    return Err(error("Missing closing parenthesis", token.position));
+                }
+                self.index += 1;
+                Ok(inner)
+            }
+            TokenKind::Term { field, value, quoted, negated } => {
+                let term = build_term(field.as_deref(), &value, quoted, token.position)?;
+                if negated {
+                    Ok(Query::Not(Box::new(Query::Term(term))))
+                } else {
+                    Ok(Query::Term(term))
+                }
+            }
+            TokenKind::RParen => Err(error("Unexpected ')'", token.position)),
+            TokenKind::Or | TokenKind::And => Err(error("Expected a search term", token.position)),
+        }
+    }
+}
+
+fn build_term(field: Option<&str>, value: &str, quoted: bool, position: usize) -> Result<Term, QueryError> {
+    let field = match field {
+        Some(name) => Some(
+            QueryField::from_name(name)
+                .ok_or_else(|| error(format!("Unknown field '{}'", name), position))?,
+        ),
+        None => None,
+    };
+
+    let matcher = match field {
+        Some(field) if field.is_numeric() => parse_range(value, field).ok_or_else(|| {
+            error(format!("Invalid number or range '{}'", value), position)
+        })?,
+        _ => {
+            let text = normalize(value);
+            if text.is_empty() {
+                return Err(error("Expected a search term", position));
+            }
+            Matcher::Text { text, exact: quoted }
+        }
+    };
+
+    Ok(Term { field, matcher })
+}
+
+impl Query {
+    pub fn parse(input: &str) -> Result<Query, QueryError> {
+        let tokens = tokenize(input)?;
+        if tokens.is_empty() {
+            return Ok(Query::All);
+        }
+
+        let mut parser = Parser {
+            tokens,
+            index: 0,
+            length: input.chars().count(),
+        };
+        let query = parser.parse_or()?;
+
+        if parser.index < parser.tokens.len() {
+            return Err(error("Unexpected ')'", parser.position()));
+        }
+
+        Ok(query)
+    }
+
+    // Termos que devem ser destacados nos resultados; os negados ficam de fora
+    pub fn highlight_terms(&self) -> Vec<Highlight> {
+        let mut terms = Vec::new();
+        self.collect_terms(&mut terms);
+        terms
+    }
+
+    fn collect_terms(&self, terms: &mut Vec<Highlight>) {
+        match self {
+            Query::Term(Term {
+                field,
+                matcher: Matcher::Text { text, .. },
+            }) => terms.push(Highlight {
+                field: *field,
+                text: text.clone(),
+            }),
+            Query::And(queries) | Query::Or(queries) => {
+                for query in queries {
+                    query.collect_terms(terms);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // Retorna a relevância da música, ou None se ela não corresponde à consulta.
+    // `text` são os campos da música já normalizados.
+    // Termos negados nunca usam correspondência aproximada: "-live" não deve excluir "love".
+    pub fn score(&self, song: &Song, text: &SearchText, fuzzy: bool) -> Option<u32> {
+        match self {
+            Query::All => Some(0),
+            Query::Term(term) => term.score(song, text, fuzzy),
+            Query::Not(inner) => match inner.score(song, text, false) {
+                Some(_) => None,
+                None => Some(0),
+            },
+            Query::And(terms) => terms
+                .iter()
+                .map(|q| q.score(song, text, fuzzy))
+                .sum::<Option<u32>>(),
+            Query::Or(branches) => branches
+                .iter()
+                .filter_map(|q| q.score(song, text, fuzzy))
+                .max(),
+        }
+    }
+}
+
+impl Term {
+    fn score(&self, song: &Song, fields: &SearchText, fuzzy: bool) -> Option<u32> {
+        match (&self.matcher, self.field) {
+            (Matcher::Text { text, exact }, Some(field)) => {
+                match_score(text, field.text(fields)?, fuzzy && !exact)
+            }
+            (Matcher::Text { text, exact }, None) => {
+                let fuzzy = fuzzy && !exact;
+                [
+                    (&fields.title, TITLE_WEIGHT),
+                    (&fields.artist, ARTIST_WEIGHT),
+                    (&fields.album, ALBUM_WEIGHT),
+                ]
+                .iter()
+                .filter_map(|(value, weight)| {
+                    match_score(text, value, fuzzy).map(|score| score * weight)
+                })
+                .max()
+            }
+            (Matcher::Range(low, high), Some(field)) => {
+                let n = field.number(song)?;
+                let in_range = low.map(|l| n >= l).unwrap_or(true) && high.map(|h| n <= h).unwrap_or(true);
+                in_range.then_some(0)
+            }
+            (Matcher::Range(..), None) => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn text(field: Option<QueryField>, text: &str, exact: bool) -> Query {
+        Query::Term(Term {
+            field,
+            matcher: Matcher::Text { text: text.to_string(), exact },
+        })
+    }
+
+    fn range(field: QueryField, low: Option<u64>, high: Option<u64>) -> Query {
+        Query::Term(Term {
+            field: Some(field),
+            matcher: Matcher::Range(low, high),
+        })
+    }
+
+    fn matches(query: &str, song: &Song, fuzzy: bool) -> Option<u32> {
+        Query::parse(query).unwrap().score(song, &SearchText::new(song), fuzzy)
+    }
+
+    fn song(title: &str, artist: &str, album: &str) -> Song {
+        Song::new(
+            PathBuf::from(format!("/music/{}.mp3", title)),
+            title.to_string(),
+            artist.to_string(),
+            album.to_string(),
+            None,
+        )
+    }
+
+    #[test]
+    fn empty_query_matches_everything() {
+        assert_eq!(Query::parse("").unwrap(), Query::All);
+        assert_eq!(Query::parse("   ").unwrap(), Query::All);
+    }
+
+    #[test]
+    fn parses_terms_and_operators() {
+        assert_eq!(
+            Query::parse("Beatles help").unwrap(),
+            Query::And(vec![text(None, "beatles", false), text(None, "help", false)])
+        );
+        assert_eq!(
+            Query::parse("a OR b").unwrap(),
+            Query::Or(vec![text(None, "a", false), text(None, "b", false)])
+        );
+        assert_eq!(Query::parse("a | b").unwrap(), Query::parse("a OR b").unwrap());
+        assert_eq!(
+            Query::parse("-live").unwrap(),
+            Query::Not(Box::new(text(None, "live", false)))
+        );
+        assert_eq!(
+            Query::parse("NOT (a | b)").unwrap(),
+            Query::Not(Box::new(Query::Or(vec![text(None, "a", false), text(None, "b", false)])))
+        );
+        assert_eq!(
+            Query::parse("artist:\"Los Hermanos\"").unwrap(),
+            text(Some(QueryField::Artist), "los hermanos", true)
+        );
+    }
+
+    #[test]
+    fn parses_numeric_ranges() {
+        assert_eq!(
+            Query::parse("year:1990..1999").unwrap(),
+            range(QueryField::Year, Some(1990), Some(1999))
+        );
+        assert_eq!(Query::parse("rating:>=4").unwrap(), range(QueryField::Rating, Some(4), None));
+        assert_eq!(Query::parse("plays:>3").unwrap(), range(QueryField::PlayCount, Some(4), None));
+        assert_eq!(Query::parse("plays:<3").unwrap(), range(QueryField::PlayCount, None, Some(2)));
+        assert_eq!(
+            Query::parse("duration:3:30").unwrap(),
+            range(QueryField::Duration, Some(210), Some(210))
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_queries() {
+        for input in [
+            "(a",
+            "a)",
+            "a OR",
+            "NOT",
+            "foo:bar",
+            "year:abc",
+            "plays:<0",
+            "title:",
+            "\"unterminated",
+            "-",
+            "a - b",
+            "\"\"",
+        ] {
+            assert!(Query::parse(input).is_err(), "{:?} should not parse", input);
+        }
+    }
+
+    #[test]
+    fn rejects_numbers_that_overflow() {
+        assert!(Query::parse("plays:>18446744073709551615").is_err());
+        assert!(Query::parse("duration:307445734561825861:00").is_err());
+        assert!(Query::parse("duration:1:18446744073709551615").is_err());
+        assert!(Query::parse("year:99999999999999999999").is_err());
+    }
+
+    #[test]
+    fn error_reports_position() {
+        let error = Query::parse("rock foo:bar").unwrap_err();
+        assert_eq!(error.position, 5);
+    }
+
+    #[test]
+    fn highlights_keep_their_field() {
+        let terms = Query::parse("miles artist:davis -live year:1959").unwrap().highlight_terms();
+        assert_eq!(
+            terms,
+            vec![
+                Highlight { field: None, text: "miles".to_string() },
+                Highlight { field: Some(QueryField::Artist), text: "davis".to_string() },
+            ]
+        );
+        assert_eq!(Highlight::texts_for(&terms, QueryField::Artist), vec!["miles", "davis"]);
+        assert_eq!(Highlight::texts_for(&terms, QueryField::Title), vec!["miles"]);
+        assert!(Highlight::texts_for(&terms, QueryField::Genre).is_empty());
+    }
+
+    #[test]
+    fn scores_songs() {
+        let song = song("Help!", "The Beatles", "Help!");
+        assert!(matches("beatles", &song, false).is_some());
+        assert!(matches("artist:help", &song, false).is_none());
+        assert!(matches("-beatles", &song, false).is_none());
+        assert!(matches("stones | beatles", &song, false).is_some());
+        assert!(matches("beatels", &song, true).is_some());
+        assert!(matches("beatels", &song, false).is_none());
+
+        // O título pesa mais que o álbum
+        let title = matches("help", &song, false).unwrap();
+        let album_only = matches("help", &self::song("Yesterday", "The Beatles", "Help!"), false).unwrap();
+        assert!(title > album_only);
+    }
+
+    #[test]
+    fn filters_numeric_fields() {
+        let mut song = song("Song", "Artist", "Album");
+        song.year = Some(1995);
+        song.play_count = 7;
+        assert!(matches("year:1990..1999", &song, false).is_some());
+        assert!(matches("year:<1990", &song, false).is_none());
+        assert!(matches("plays:>=7", &song, false).is_some());
+        assert!(matches("bitrate:>128", &song, false).is_none());
+    }
+}
@@ -0,0 +1,245 @@
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
+use super::Song;
+
+// Pontuação base de cada tipo de correspondência, antes do peso do campo
+const EXACT_SCORE: u32 = 100;
+const PREFIX_SCORE: u32 = 80;
+const WORD_PREFIX_SCORE: u32 = 70;
+const SUBSTRING_SCORE: u32 = 60;
+const FUZZY_SCORE: u32 = 40;
+
+// Nos alfabetos latino, grego e cirílico o acento é uma variação da mesma letra.
+// Em outras escritas a marca faz parte da letra (sinais vocálicos do devanágari, dakuten do japonês)
+fn drops_marks(base: char) -> bool {
+    matches!(
+        base,
+        '\u{0000}'..='\u{024F}' | '\u{0370}'..='\u{03FF}' | '\u{0400}'..='\u{052F}' | '\u{1E00}'..='\u{1FFF}'
+    )
+}
+
+// Remove acentos e normaliza: "João" e "JOAO" viram "joao", "Straße" e "STRASSE" viram "strasse"
+pub fn normalize(text: &str) -> String {
+    let mut folded = String::with_capacity(text.len());
+    let mut base = None;
+    for c in text.nfkd() {
+        if !is_combining_mark(c) {
+            base = Some(c);
+        } else if base.is_some_and(drops_marks) {
+            continue;
+        }
+        for lower in c.to_lowercase() {
+            match lower {
+                'ß' => folded.push_str("ss"),
+                'ς' => folded.push('σ'),
+                _ => folded.push(lower),
+            }
+        }
+    }
+    folded.nfkc().collect()
+}
+
+// Campos de texto já normalizados, calculados uma vez por música e não a cada tecla
+#[derive(Debug, Clone)]
+pub struct SearchText {
+    pub title: String,
+    pub artist: String,
+    pub album: String,
+    pub album_artist: Option<String>,
+    pub genre: Option<String>,
+    pub path: String,
+}
+
+impl SearchText {
+    pub fn new(song: &Song) -> Self {
+        Self {
+            title: normalize(&song.title),
+            artist: normalize(&song.artist),
+            album: normalize(&song.album),
+            album_artist: song.album_artist.as_deref().map(normalize),
+            genre: song.genre.as_deref().map(normalize),
+            path: normalize(&song.file_path.to_string_lossy()),
+        }
+    }
+}
+
+fn max_typos(word: &str) -> usize {
+    match word.chars().count() {
+        0..=3 => 0,
+        4..=6 => 1,
+        _ => 2,
+    }
+}
+
+// Distância de Damerau-Levenshtein (variante "optimal string alignment").
+// A transposição olha duas linhas para trás, então só essas ficam guardadas
+fn edit_distance(a: &[char], b: &[char]) -> usize {
+    let mut before = vec![0; b.len() + 1];
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current = vec![0; b.len() + 1];
+
+    for i in 1..=a.len() {
+        current[0] = i;
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut best = (previous[j] + 1)
+                .min(current[j - 1] + 1)
+                .min(previous[j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                best = best.min(before[j - 2] + 1);
+            }
+            current[j] = best;
+        }
+        std::mem::swap(&mut before, &mut previous);
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    previous[b.len()]
+}
+
+fn word_distance(needle: &str, word: &str) -> usize {
+    let needle: Vec<char> = needle.chars().collect();
+    let word: Vec<char> = word.chars().collect();
+
+    // Também compara com o início da palavra, para quem ainda está digitando
+    let full = edit_distance(&needle, &word);
+    if word.len() > needle.len() {
+        full.min(edit_distance(&needle, &word[..needle.len()]))
+    } else {
+        full
+    }
+}
+
+fn fuzzy_words(needle: &str, haystack: &str) -> Option<u32> {
+    let words: Vec<&str> = haystack.split_whitespace().collect();
+    let mut total_distance = 0;
+
+    for needle_word in needle.split_whitespace() {
+        let allowed = max_typos(needle_word);
+        let distance = words
+            .iter()
+            .map(|word| word_distance(needle_word, word))
+            .min()?;
+        if distance > allowed {
+            return None;
+        }
+        total_distance += distance;
+    }
+
+    Some(FUZZY_SCORE.saturating_sub(10 * total_distance as u32).max(1))
+}
+
+// Ambos os textos já devem estar normalizados
+pub fn match_score(needle: &str, haystack: &str, fuzzy: bool) -> Option<u32> {
+    if needle.is_empty() {
+        return Some(0);
+    }
+
+    if haystack == needle {
+        Some(EXACT_SCORE)
+    } else if haystack.starts_with(needle) {
+        Some(PREFIX_SCORE)
+    } else if haystack
+        .match_indices(needle)
+        .any(|(pos, _)| haystack[..pos].ends_with(|c: char| !c.is_alphanumeric()))
+    {
+        Some(WORD_PREFIX_SCORE)
+    } else if haystack.contains(needle) {
+        Some(SUBSTRING_SCORE)
+    } else if fuzzy {
+        fuzzy_words(needle, haystack)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn distance(a: &str, b: &str) -> usize {
+        let a: Vec<char> = a.chars().collect();
+        let b: Vec<char> = b.chars().collect();
+        edit_distance(&a, &b)
+    }
+
+    #[test]
+    fn removes_latin_accents_and_case() {
+        assert_eq!(normalize("João"), "joao");
+        assert_eq!(normalize("JOAO"), "joao");
+        assert_eq!(normalize("Beyoncé"), normalize("BEYONCE"));
+        assert_eq!(normalize("Sigur Rós"), "sigur ros");
+        assert_eq!(normalize("Motörhead"), "motorhead");
+    }
+
+    #[test]
+    fn folds_special_cases() {
+        assert_eq!(normalize("Straße"), "strasse");
+        assert_eq!(normalize("STRASSE"), "strasse");
+        assert_eq!(normalize("STRAẞE"), "strasse");
+        // Sigma final e medial são a mesma letra
+        assert_eq!(normalize("ΣΟΦΟΣ"), normalize("σοφος"));
+        // Compatibilidade: ligaduras e largura total
+        assert_eq!(normalize("ﬁne"), "fine");
+        assert_eq!(normalize("ＡＢＣ"), "abc");
+    }
+
+    #[test]
+    fn keeps_marks_that_are_part_of_the_letter() {
+        assert_eq!(normalize("हिंदी"), "हिंदी");
+        assert_ne!(normalize("कि"), normalize("क"));
+        assert_eq!(normalize("が"), "が");
+        assert_ne!(normalize("ガ"), normalize("カ"));
+        // Katakana de meia largura com dakuten separado vira a forma composta
+        assert_eq!(normalize("ｶﾞ"), "ガ");
+    }
+
+    #[test]
+    fn ranks_match_kinds() {
+        assert_eq!(match_score("joao", "joao", false), Some(EXACT_SCORE));
+        assert_eq!(match_score("joao", "joao gilberto", false), Some(PREFIX_SCORE));
+        assert_eq!(match_score("gil", "joao gilberto", false), Some(WORD_PREFIX_SCORE));
+        assert_eq!(match_score("bert", "joao gilberto", false), Some(SUBSTRING_SCORE));
+        assert_eq!(match_score("xyz", "joao gilberto", false), None);
+    }
+
+    #[test]
+    fn fuzzy_match_tolerates_typos() {
+        assert!(match_score("beyonse", &normalize("Beyoncé"), true).is_some());
+        assert!(match_score("beyonse", &normalize("Beyoncé"), false).is_none());
+        assert!(match_score("gilbreto", "joao gilberto", true).is_some());
+        // Palavras curtas precisam ser exatas
+        assert!(match_score("joa", "jao", true).is_none());
+        assert!(match_score("metallica", "madonna", true).is_none());
+    }
+
+    #[test]
+    fn edit_distance_counts_operations() {
+        assert_eq!(distance("", ""), 0);
+        assert_eq!(distance("abc", ""), 3);
+        assert_eq!(distance("", "abc"), 3);
+        assert_eq!(distance("kitten", "sitting"), 3);
+        assert_eq!(distance("beatles", "beatels"), 1);
+        assert_eq!(distance("ab", "ba"), 1);
+        assert_eq!(distance("ca", "abc"), 3);
+    }
+
+    #[test]
+    fn search_text_normalizes_every_field() {
+        let mut song = Song::new(
+            "/música/João.mp3".into(),
+            "Águas de Março".to_string(),
+            "Tom JOBIM".to_string(),
+            "Elis & Tom".to_string(),
+            None,
+        );
+        song.genre = Some("Bossa Nova".to_string());
+        let text = SearchText::new(&song);
+        assert_eq!(text.title, "aguas de marco");
+        assert_eq!(text.artist, "tom jobim");
+        assert_eq!(text.genre.as_deref(), Some("bossa nova"));
+        assert_eq!(text.album_artist, None);
+        assert_eq!(text.path, "/musica/joao.mp3");
+    }
+}
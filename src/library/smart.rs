@@ -0,0 +1,533 @@
+use serde::{Deserialize, Serialize};
+
+use super::{unix_now, MusicLibrary, Song};
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+// Maior prazo aceito pelo editor em "nos últimos N dias" (cem anos)
+pub const MAX_DAYS: u64 = 100 * 366;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MatchMode {
+    All,
+    Any,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RuleField {
+    Title,
+    Artist,
+    Album,
+    AlbumArtist,
+    Genre,
+    Year,
+    Duration,
+    Bitrate,
+    PlayCount,
+    SkipCount,
+    Rating,
+    LastPlayed,
+    DateAdded,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RuleOperator {
+    Is,
+    IsNot,
+    Contains,
+    DoesNotContain,
+    StartsWith,
+    GreaterThan,
+    LessThan,
+    InLastDays,
+    NotInLastDays,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Rule {
+    pub field: RuleField,
+    pub operator: RuleOperator,
+    pub value: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum RuleNode {
+    Rule(Rule),
+    Group(RuleGroup),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuleGroup {
+    pub mode: MatchMode,
+    pub rules: Vec<RuleNode>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SortField {
+    Title,
+    Artist,
+    Album,
+    Year,
+    Duration,
+    PlayCount,
+    SkipCount,
+    Rating,
+    LastPlayed,
+    DateAdded,
+    Random,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct SortOrder {
+    pub field: SortField,
+    pub descending: bool,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum Limit {
+    Songs(usize),
+    Minutes(u64),
+    Megabytes(u64),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SmartPlaylist {
+    pub name: String,
+    pub rules: RuleGroup,
+    pub sort: Option<SortOrder>,
+    pub limit: Option<Limit>,
+}
+
+enum FieldValue<'a> {
+    Text(&'a str),
+    Number(Option<u64>),
+    Date(Option<u64>),
+}
+
+impl RuleField {
+    pub const ALL: [RuleField; 13] = [
+        RuleField::Title,
+        RuleField::Artist,
+        RuleField::Album,
+        RuleField::AlbumArtist,
+        RuleField::Genre,
+        RuleField::Year,
+        RuleField::Duration,
+        RuleField::Bitrate,
+        RuleField::PlayCount,
+        RuleField::SkipCount,
+        RuleField::Rating,
+        RuleField::LastPlayed,
+        RuleField::DateAdded,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            RuleField::Title => "Title",
+            RuleField::Artist => "Artist",
+            RuleField::Album => "Album",
+            RuleField::AlbumArtist => "Album Artist",
+            RuleField::Genre => "Genre",
+            RuleField::Year => "Year",
+            RuleField::Duration => "Duration (seconds)",
+            RuleField::Bitrate => "Bitrate (kbps)",
+            RuleField::PlayCount => "Play Count",
+            RuleField::SkipCount => "Skip Count",
+            RuleField::Rating => "Rating (stars)",
+            RuleField::LastPlayed => "Last Played",
+            RuleField::DateAdded => "Date Added",
+        }
+    }
+
+    pub fn operators(&self) -> &'static [RuleOperator] {
+        match self {
+            RuleField::Title
+            | RuleField::Artist
+            | RuleField::Album
+            | RuleField::AlbumArtist
+            | RuleField::Genre => &[
+                RuleOperator::Is,
+                RuleOperator::IsNot,
+                RuleOperator::Contains,
+                RuleOperator::DoesNotContain,
+                RuleOperator::StartsWith,
+            ],
+            RuleField::Year
+            | RuleField::Duration
+            | RuleField::Bitrate
+            | RuleField::PlayCount
+            | RuleField::SkipCount
+            | RuleField::Rating => &[
+                RuleOperator::Is,
+                RuleOperator::IsNot,
+                RuleOperator::GreaterThan,
+                RuleOperator::LessThan,
+            ],
+            RuleField::LastPlayed | RuleField::DateAdded => {
+                &[RuleOperator::InLastDays, RuleOperator::NotInLastDays]
+            }
+        }
+    }
+
+    fn value<'a>(&self, song: &'a Song) -> FieldValue<'a> {
+        match self {
+            RuleField::Title => FieldValue::Text(&song.title),
+            RuleField::Artist => FieldValue::Text(&song.artist),
+            RuleField::Album => FieldValue::Text(&song.album),
+            RuleField::AlbumArtist => {
+                FieldValue::Text(song.album_artist.as_deref().unwrap_or(&song.artist))
+            }
+            RuleField::Genre => FieldValue::Text(song.genre.as_deref().unwrap_or("")),
+            RuleField::Year => FieldValue::Number(song.year.map(u64::from)),
+            RuleField::Duration => FieldValue::Number(song.duration),
+            RuleField::Bitrate => FieldValue::Number(song.bitrate.map(u64::from)),
+            RuleField::PlayCount => FieldValue::Number(Some(u64::from(song.play_count))),
+            RuleField::SkipCount => FieldValue::Number(Some(u64::from(song.skip_count))),
+            RuleField::Rating => FieldValue::Number(Some(u64::from(song.rating))),
+            RuleField::LastPlayed => FieldValue::Date(song.last_played),
+            RuleField::DateAdded => FieldValue::Date(Some(song.date_added)),
+        }
+    }
+}
+
+impl RuleOperator {
+    pub fn label(&self) -> &'static str {
+        match self {
+            RuleOperator::Is => "is",
+            RuleOperator::IsNot => "is not",
+            RuleOperator::Contains => "contains",
+            RuleOperator::DoesNotContain => "does not contain",
+            RuleOperator::StartsWith => "starts with",
+            RuleOperator::GreaterThan => "is greater than",
+            RuleOperator::LessThan => "is less than",
+            RuleOperator::InLastDays => "in the last (days)",
+            RuleOperator::NotInLastDays => "not in the last (days)",
+        }
+    }
+}
+
+impl Rule {
+    pub fn matches(&self, song: &Song, now: u64) -> bool {
+        match self.field.value(song) {
+            FieldValue::Text(text) => {
+                let text = text.to_lowercase();
+                let value = self.value.trim().to_lowercase();
+                match self.operator {
+                    RuleOperator::Is => text == value,
+                    RuleOperator::IsNot => text != value,
+                    RuleOperator::Contains => text.contains(&value),
+                    RuleOperator::DoesNotContain => !text.contains(&value),
+                    RuleOperator::StartsWith => text.starts_with(&value),
+                    _ => false,
+                }
+            }
+            FieldValue::Number(number) => {
+                let Ok(value) = self.value.trim().parse::<u64>() else {
+                    return false;
+                };
+                match (self.operator, number) {
+                    (RuleOperator::Is, Some(n)) => n == value,
+                    (RuleOperator::IsNot, Some(n)) => n != value,
+                    (RuleOperator::IsNot, None) => true,
+                    (RuleOperator::GreaterThan, Some(n)) => n > value,
+                    (RuleOperator::LessThan, Some(n)) => n < value,
+                    _ => false,
+                }
+            }
+            FieldValue::Date(timestamp) => {
+                let Ok(days) = self.value.trim().parse::<u64>() else {
+                    return false;
+                };
+                let cutoff = now.saturating_sub(days.saturating_mul(SECONDS_PER_DAY));
+                let recent = timestamp.map(|t| t >= cutoff).unwrap_or(false);
+                match self.operator {
+                    RuleOperator::InLastDays => recent,
+                    RuleOperator::NotInLastDays => !recent,
+                    _ => false,
+                }
+            }
+        }
+    }
+}
+
+impl RuleGroup {
+    // Regras "nos últimos N dias" mudam de resultado com o tempo, sem a biblioteca mudar
+    pub fn uses_dates(&self) -> bool {
+        self.rules.iter().any(|node| match node {
+            RuleNode::Rule(rule) => matches!(rule.operator, RuleOperator::InLastDays | RuleOperator::NotInLastDays),
+            RuleNode::Group(group) => group.uses_dates(),
+        })
+    }
+
+    pub fn matches(&self, song: &Song, now: u64) -> bool {
+        // Um grupo vazio não filtra nada
+        if self.rules.is_empty() {
+            return true;
+        }
+
+        let node_matches = |node: &RuleNode| match node {
+            RuleNode::Rule(rule) => rule.matches(song, now),
+            RuleNode::Group(group) => group.matches(song, now),
+        };
+
+        match self.mode {
+            MatchMode::All => self.rules.iter().all(node_matches),
+            MatchMode::Any => self.rules.iter().any(node_matches),
+        }
+    }
+}
+
+impl SortField {
+    pub const ALL: [SortField; 11] = [
+        SortField::Title,
+        SortField::Artist,
+        SortField::Album,
+        SortField::Year,
+        SortField::Duration,
+        SortField::PlayCount,
+        SortField::SkipCount,
+        SortField::Rating,
+        SortField::LastPlayed,
+        SortField::DateAdded,
+        SortField::Random,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            SortField::Title => "Title",
+            SortField::Artist => "Artist",
+            SortField::Album => "Album",
+            SortField::Year => "Year",
+            SortField::Duration => "Duration",
+            SortField::PlayCount => "Play Count",
+            SortField::SkipCount => "Skip Count",
+            SortField::Rating => "Rating",
+            SortField::LastPlayed => "Last Played",
+            SortField::DateAdded => "Date Added",
+            SortField::Random => "Random",
+        }
+    }
+}
+
+impl SortOrder {
+    fn apply(&self, songs: &mut Vec<&Song>) {
+        match self.field {
+            SortField::Title => songs.sort_by_key(|s| s.title.to_lowercase()),
+            SortField::Artist => songs.sort_by_key(|s| s.artist.to_lowercase()),
+            SortField::Album => songs.sort_by_key(|s| s.album.to_lowercase()),
+            SortField::Year => songs.sort_by_key(|s| s.year),
+            SortField::Duration => songs.sort_by_key(|s| s.duration),
+            SortField::PlayCount => songs.sort_by_key(|s| s.play_count),
+            SortField::SkipCount => songs.sort_by_key(|s| s.skip_count),
+            SortField::Rating => songs.sort_by_key(|s| s.rating),
+            SortField::LastPlayed => songs.sort_by_key(|s| s.last_played),
+            SortField::DateAdded => songs.sort_by_key(|s| s.date_added),
+            SortField::Random => {
+                // Fisher-Yates usando o gerador do GLib
+                for i in (1..songs.len()).rev() {
+                    let j = glib::random_int_range(0, i as i32 + 1) as usize;
+                    songs.swap(i, j);
+                }
+                return;
+            }
+        }
+
+        if self.descending {
+            songs.reverse();
+        }
+    }
+}
+
+impl Limit {
+    fn apply<'a>(&self, songs: Vec<&'a Song>) -> Vec<&'a Song> {
+        let (max, measure): (u64, fn(&Song) -> u64) = match *self {
+            Limit::Songs(count) => return songs.into_iter().take(count).collect(),
+            Limit::Minutes(minutes) => (minutes.saturating_mul(60), |s| s.duration.unwrap_or(0)),
+            Limit::Megabytes(megabytes) => (megabytes.saturating_mul(1024 * 1024), |s| s.file_size),
+        };
+
+        let mut total = 0;
+        songs
+            .into_iter()
+            .take_while(|song| {
+                total += measure(song);
+                total <= max
+            })
+            .collect()
+    }
+}
+
+impl SmartPlaylist {
+    pub fn new(name: &str, rules: RuleGroup) -> Self {
+        Self {
+            name: name.to_string(),
+            rules,
+            sort: None,
+            limit: None,
+        }
+    }
+}
+
+impl MusicLibrary {
+    // Avaliada sempre sob demanda, então reflete o estado atual da biblioteca
+    pub fn evaluate_smart_playlist(&self, playlist: &SmartPlaylist) -> Vec<&Song> {
+        let now = unix_now();
+        let mut songs: Vec<&Song> = self
+            .songs
+            .iter()
+            .filter(|song| playlist.rules.matches(song, now))
+            .collect();
+
+        if let Some(sort) = &playlist.sort {
+            sort.apply(&mut songs);
+        }
+
+        match &playlist.limit {
+            Some(limit) => limit.apply(songs),
+            None => songs,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    const NOW: u64 = 1_700_000_000;
+
+    fn song(title: &str, artist: &str, year: u32, plays: u32) -> Song {
+        let mut song = Song::new(
+            PathBuf::from(format!("/music/{}.mp3", title)),
+            title.to_string(),
+            artist.to_string(),
+            "Album".to_string(),
+            None,
+        );
+        song.year = Some(year);
+        song.play_count = plays;
+        song
+    }
+
+    fn rule(field: RuleField, operator: RuleOperator, value: &str) -> Rule {
+        Rule { field, operator, value: value.to_string() }
+    }
+
+    fn group(mode: MatchMode, rules: Vec<Rule>) -> RuleGroup {
+        RuleGroup { mode, rules: rules.into_iter().map(RuleNode::Rule).collect() }
+    }
+
+    fn titles(songs: &[&Song]) -> Vec<String> {
+        songs.iter().map(|song| song.title.clone()).collect()
+    }
+
+    #[test]
+    fn text_rules_ignore_case() {
+        let song = song("Blue Moon", "Someone", 1990, 0);
+        assert!(rule(RuleField::Title, RuleOperator::Is, " blue moon ").matches(&song, NOW));
+        assert!(rule(RuleField::Title, RuleOperator::IsNot, "Moon").matches(&song, NOW));
+        assert!(rule(RuleField::Title, RuleOperator::Contains, "MOON").matches(&song, NOW));
+        assert!(rule(RuleField::Title, RuleOperator::DoesNotContain, "sun").matches(&song, NOW));
+        assert!(rule(RuleField::Title, RuleOperator::StartsWith, "blue").matches(&song, NOW));
+        // Sem artista do álbum vale o artista
+        assert!(rule(RuleField::AlbumArtist, RuleOperator::Is, "someone").matches(&song, NOW));
+        assert!(!rule(RuleField::Genre, RuleOperator::Contains, "rock").matches(&song, NOW));
+    }
+
+    #[test]
+    fn number_rules_compare_and_skip_missing_values() {
+        let song = song("A", "B", 1990, 3);
+        assert!(rule(RuleField::Year, RuleOperator::Is, "1990").matches(&song, NOW));
+        assert!(rule(RuleField::Year, RuleOperator::GreaterThan, "1989").matches(&song, NOW));
+        assert!(rule(RuleField::PlayCount, RuleOperator::LessThan, "4").matches(&song, NOW));
+        assert!(!rule(RuleField::PlayCount, RuleOperator::IsNot, "3").matches(&song, NOW));
+        assert!(!rule(RuleField::Year, RuleOperator::Is, "nineteen").matches(&song, NOW));
+        // Sem duração conhecida só "is not" combina
+        assert!(!rule(RuleField::Duration, RuleOperator::LessThan, "100").matches(&song, NOW));
+        assert!(rule(RuleField::Duration, RuleOperator::IsNot, "100").matches(&song, NOW));
+    }
+
+    #[test]
+    fn date_rules_count_days_back_from_now() {
+        let mut song = song("A", "B", 1990, 0);
+        song.date_added = NOW - 2 * SECONDS_PER_DAY;
+        assert!(rule(RuleField::DateAdded, RuleOperator::InLastDays, "3").matches(&song, NOW));
+        assert!(!rule(RuleField::DateAdded, RuleOperator::InLastDays, "1").matches(&song, NOW));
+        assert!(rule(RuleField::DateAdded, RuleOperator::NotInLastDays, "1").matches(&song, NOW));
+        // Nunca tocada não está entre as recentes
+        assert!(!rule(RuleField::LastPlayed, RuleOperator::InLastDays, "30").matches(&song, NOW));
+        assert!(rule(RuleField::LastPlayed, RuleOperator::NotInLastDays, "30").matches(&song, NOW));
+    }
+
+    #[test]
+    fn huge_day_counts_do_not_overflow() {
+        let song = song("A", "B", 1990, 0);
+        let days = u64::MAX.to_string();
+        assert!(rule(RuleField::DateAdded, RuleOperator::InLastDays, &days).matches(&song, NOW));
+    }
+
+    #[test]
+    fn groups_match_all_or_any() {
+        let song = song("A", "B", 1990, 0);
+        let rules = || {
+            vec![
+                rule(RuleField::Artist, RuleOperator::Is, "B"),
+                rule(RuleField::Year, RuleOperator::GreaterThan, "2000"),
+            ]
+        };
+        assert!(!group(MatchMode::All, rules()).matches(&song, NOW));
+        assert!(group(MatchMode::Any, rules()).matches(&song, NOW));
+        assert!(group(MatchMode::All, Vec::new()).matches(&song, NOW));
+
+        let mut nested = group(MatchMode::All, vec![rule(RuleField::Title, RuleOperator::Is, "A")]);
+        nested.rules.push(RuleNode::Group(group(MatchMode::Any, rules())));
+        assert!(nested.matches(&song, NOW));
+        assert!(!nested.uses_dates());
+        nested.rules.push(RuleNode::Group(group(
+            MatchMode::Any,
+            vec![rule(RuleField::LastPlayed, RuleOperator::NotInLastDays, "7")],
+        )));
+        assert!(nested.uses_dates());
+    }
+
+    #[test]
+    fn sorts_then_limits() {
+        let songs = [song("b", "x", 2001, 5), song("A", "x", 1999, 1), song("c", "x", 2000, 9)];
+        let mut sorted: Vec<&Song> = songs.iter().collect();
+        SortOrder { field: SortField::Title, descending: false }.apply(&mut sorted);
+        assert_eq!(titles(&sorted), vec!["A", "b", "c"]);
+        SortOrder { field: SortField::PlayCount, descending: true }.apply(&mut sorted);
+        assert_eq!(titles(&sorted), vec!["c", "b", "A"]);
+
+        assert_eq!(titles(&Limit::Songs(2).apply(sorted.clone())), vec!["c", "b"]);
+        assert!(Limit::Songs(0).apply(sorted.clone()).is_empty());
+    }
+
+    #[test]
+    fn limits_by_minutes_and_size() {
+        let mut songs = [song("a", "x", 2000, 0), song("b", "x", 2000, 0), song("c", "x", 2000, 0)];
+        for song in &mut songs {
+            song.duration = Some(100);
+            song.file_size = 600 * 1024;
+        }
+        let all: Vec<&Song> = songs.iter().collect();
+        // Para antes da música que passaria do limite
+        assert_eq!(titles(&Limit::Minutes(4).apply(all.clone())), vec!["a", "b"]);
+        assert_eq!(titles(&Limit::Megabytes(1).apply(all.clone())), vec!["a"]);
+        assert_eq!(Limit::Minutes(u64::MAX).apply(all).len(), 3);
+    }
+
+    #[test]
+    fn evaluates_rules_sort_and_limit_together() {
+        let mut library = MusicLibrary::new();
+        library.songs = vec![song("a", "x", 1990, 2), song("b", "y", 1995, 7), song("c", "x", 2005, 4)];
+        let rules = group(MatchMode::All, vec![rule(RuleField::Artist, RuleOperator::Is, "x")]);
+        let mut playlist = SmartPlaylist::new("X", rules);
+        assert_eq!(titles(&library.evaluate_smart_playlist(&playlist)), vec!["a", "c"]);
+
+        playlist.rules.mode = MatchMode::Any;
+        playlist.rules.rules.push(RuleNode::Rule(rule(RuleField::PlayCount, RuleOperator::GreaterThan, "5")));
+        playlist.sort = Some(SortOrder { field: SortField::Year, descending: true });
+        playlist.limit = Some(Limit::Songs(2));
+        assert_eq!(titles(&library.evaluate_smart_playlist(&playlist)), vec!["c", "b"]);
+    }
+}
@@ -0,0 +1,536 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+use anyhow::Result;
+use serde::Serialize;
+
+use super::history::PlayEvent;
+use super::{tags, unix_now, MusicLibrary, Song};
+use crate::config::ListeningSettings;
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+// Maior avanço entre duas leituras da posição que ainda conta como reprodução normal
+const MAX_LISTEN_STEP: Duration = Duration::from_secs(3);
+// Uma gravação de tags por vez, para duas notas seguidas não escreverem o mesmo arquivo juntas
+static TAG_WRITES: Mutex<()> = Mutex::new(());
+const MONTHS: [&str; 12] = [
+    "January", "February", "March", "April", "May", "June",
+    "July", "August", "September", "October", "November", "December",
+];
+
+// Acompanha a música atual até ela contar como reproduzida
+#[derive(Debug, Clone)]
+pub struct ListenTracker {
+    pub path: PathBuf,
+    pub started: u64,
+    duration: Option<u64>,
+    pub counted: bool,
+    // Tempo realmente tocado; a posição sozinha conta seeks e sessões retomadas
+    played: Duration,
+    last_position: Duration,
+}
+
+impl ListenTracker {
+    pub fn new(song: &Song) -> Self {
+        Self {
+            path: song.file_path.clone(),
+            started: unix_now(),
+            duration: song.duration,
+            counted: false,
+            played: Duration::ZERO,
+            last_position: Duration::ZERO,
+        }
+    }
+
+    // Soma só o avanço normal da reprodução; saltos para frente ou para trás não contam
+    pub fn advance(&mut self, position: Duration) {
+        if let Some(step) = position.checked_sub(self.last_position).filter(|step| *step <= MAX_LISTEN_STEP) {
+            self.played += step;
+        }
+        self.last_position = position;
+    }
+
+    pub fn played(&self) -> Duration {
+        self.played
+    }
+
+    pub fn threshold(&self, settings: &ListeningSettings) -> Duration {
+        let seconds = match self.duration {
+            Some(duration) => {
+                let by_percent = duration * u64::from(settings.count_after_percent.min(100)) / 100;
+                by_percent.min(settings.count_after_seconds)
+            }
+            None => settings.count_after_seconds,
+        };
+        Duration::from_secs(seconds.max(1))
+    }
+}
+
+impl MusicLibrary {
+    fn song_mut(&mut self, path: &Path) -> Option<&mut Song> {
+        self.songs.iter_mut().find(|song| song.file_path == path)
+    }
+
+    // Reescrever as tags pode levar segundos em arquivos grandes; roda fora da thread da interface
+    fn sync_stats(&self, songs: &[Song]) {
+        if !self.sync_rating_tags || songs.is_empty() {
+            return;
+        }
+        let stats: Vec<(PathBuf, u8, u32)> = songs
+            .iter()
+            .map(|song| (song.file_path.clone(), song.rating, song.play_count))
+            .collect();
+        gio::spawn_blocking(move || {
+            let _guard = TAG_WRITES.lock().unwrap_or_else(|e| e.into_inner());
+            for (path, rating, plays) in stats {
+                if let Err(e) = tags::write_stats(&path, rating, plays) {
+                    eprintln!("Could not write rating tags to {}: {}", path.display(), e);
+                }
+            }
+        });
+    }
+
+    pub fn record_play(&mut self, path: &Path) -> Option<Song> {
+        let song = self.song_mut(path)?;
+        song.play_count += 1;
+        song.last_played = Some(unix_now());
+        let song = song.clone();
+        self.sync_stats(std::slice::from_ref(&song));
+        self.refresh_song(&song);
+        Some(song)
+    }
+
+    pub fn record_skip(&mut self, path: &Path) -> Option<Song> {
+        let song = self.song_mut(path)?;
+        song.skip_count += 1;
+        let song = song.clone();
+        self.refresh_song(&song);
+        Some(song)
+    }
+
+    pub fn set_rating(&mut self, paths: &[PathBuf], rating: u8) -> Vec<Song> {
+        let mut changed = Vec::new();
+        for path in paths {
+            if let Some(song) = self.song_mut(path) {
+                song.rating = rating.min(5);
+                changed.push(song.clone());
+            }
+        }
+        self.sync_stats(&changed);
+        for song in &changed {
+            self.refresh_song(song);
+        }
+        changed
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatsPeriod {
+    Week,
+    Month,
+    Year,
+    AllTime,
+}
+
+impl StatsPeriod {
+    pub const ALL: [StatsPeriod; 4] = [
+        StatsPeriod::Week,
+        StatsPeriod::Month,
+        StatsPeriod::Year,
+        StatsPeriod::AllTime,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            StatsPeriod::Week => "Last 7 Days",
+            StatsPeriod::Month => "Last 30 Days",
+            StatsPeriod::Year => "Last 365 Days",
+            StatsPeriod::AllTime => "All Time",
+        }
+    }
+
+    pub fn since(&self, now: u64) -> u64 {
+        let days = match self {
+            StatsPeriod::Week => 7,
+            StatsPeriod::Month => 30,
+            StatsPeriod::Year => 365,
+            StatsPeriod::AllTime => return 0,
+        };
+        now.saturating_sub(days * SECONDS_PER_DAY)
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TopEntry {
+    pub name: String,
+    pub plays: u32,
+    pub seconds: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ListeningStats {
+    pub plays: u32,
+    pub skips: u32,
+    pub total_seconds: u64,
+    pub top_artists: Vec<TopEntry>,
+    pub top_albums: Vec<TopEntry>,
+    pub top_tracks: Vec<TopEntry>,
+}
+
+#[derive(Default)]
+struct Tally(HashMap<String, (u32, u64)>);
+
+impl Tally {
+    fn add(&mut self, name: String, counted: bool, seconds: u64) {
+        let entry = self.0.entry(name).or_default();
+        entry.0 += u32::from(counted);
+        entry.1 += seconds;
+    }
+
+    // Mais reproduções primeiro; empate pelo tempo ouvido
+    fn top(self, limit: usize) -> Vec<TopEntry> {
+        let mut entries: Vec<TopEntry> = self
+            .0
+            .into_iter()
+            .filter(|(_, (plays, _))| *plays > 0)
+            .map(|(name, (plays, seconds))| TopEntry { name, plays, seconds })
+            .collect();
+        entries.sort_by(|a, b| {
+            b.plays
+                .cmp(&a.plays)
+                .then(b.seconds.cmp(&a.seconds))
+                .then_with(|| a.name.cmp(&b.name))
+        });
+        entries.truncate(limit);
+        entries
+    }
+}
+
+// Eventos com timestamp em [from, to)
+pub fn listening_stats(events: &[PlayEvent], from: u64, to: u64, limit: usize) -> ListeningStats {
+    let mut stats = ListeningStats::default();
+    let mut artists = Tally::default();
+    let mut albums = Tally::default();
+    let mut tracks = Tally::default();
+
+    for event in events.iter().filter(|e| e.timestamp >= from && e.timestamp < to) {
+        let counted = !event.skipped;
+        if counted {
+            stats.plays += 1;
+        } else {
+            stats.skips += 1;
+        }
+        stats.total_seconds += event.played_seconds;
+        artists.add(event.artist.clone(), counted, event.played_seconds);
+        albums.add(format!("{} — {}", event.album, event.artist), counted, event.played_seconds);
+        tracks.add(format!("{} — {}", event.artist, event.title), counted, event.played_seconds);
+    }
+
+    stats.top_artists = artists.top(limit);
+    stats.top_albums = albums.top(limit);
+    stats.top_tracks = tracks.top(limit);
+    stats
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CategoryCount {
+    pub name: String,
+    pub songs: usize,
+    pub bytes: u64,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct LibraryStats {
+    pub songs: usize,
+    pub total_seconds: u64,
+    pub total_bytes: u64,
+    pub by_format: Vec<CategoryCount>,
+    pub by_genre: Vec<CategoryCount>,
+}
+
+fn count_by<F: Fn(&Song) -> String>(songs: &[Song], key: F) -> Vec<CategoryCount> {
+    let mut counts: HashMap<String, (usize, u64)> = HashMap::new();
+    for song in songs {
+        let entry = counts.entry(key(song)).or_default();
+        entry.0 += 1;
+        entry.1 += song.file_size;
+    }
+    let mut counts: Vec<CategoryCount> = counts
+        .into_iter()
+        .map(|(name, (songs, bytes))| CategoryCount { name, songs, bytes })
+        .collect();
+    counts.sort_by(|a, b| b.songs.cmp(&a.songs).then_with(|| a.name.cmp(&b.name)));
+    counts
+}
+
+pub fn library_stats(songs: &[Song]) -> LibraryStats {
+    LibraryStats {
+        songs: songs.len(),
+        total_seconds: songs.iter().filter_map(|s| s.duration).sum(),
+        total_bytes: songs.iter().map(|s| s.file_size).sum(),
+        by_format: count_by(songs, |song| {
+            song.file_path
+                .extension()
+                .map(|e| e.to_string_lossy().to_uppercase())
+                .unwrap_or_else(|| "Unknown".to_string())
+        }),
+        by_genre: count_by(songs, |song| {
+            song.genre
+                .clone()
+                .filter(|g| !g.trim().is_empty())
+                .unwrap_or_else(|| "Unknown".to_string())
+        }),
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct MonthSummary {
+    pub month: String,
+    pub plays: u32,
+    pub seconds: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct YearInReview {
+    pub year: i32,
+    pub plays: u32,
+    pub skips: u32,
+    pub total_seconds: u64,
+    pub distinct_artists: usize,
+    pub distinct_tracks: usize,
+    pub songs_added: usize,
+    pub top_artists: Vec<TopEntry>,
+    pub top_albums: Vec<TopEntry>,
+    pub top_tracks: Vec<TopEntry>,
+    pub months: Vec<MonthSummary>,
+}
+
+fn year_start(year: i32) -> u64 {
+    glib::DateTime::from_local(year, 1, 1, 0, 0, 0.0)
+        .map(|date| date.to_unix().max(0) as u64)
+        .unwrap_or(0)
+}
+
+pub fn current_year() -> i32 {
+    glib::DateTime::now_local().map(|now| now.year()).unwrap_or(1970)
+}
+
+pub fn year_in_review(events: &[PlayEvent], songs: &[Song], year: i32) -> YearInReview {
+    let (from, to) = (year_start(year), year_start(year + 1));
+    let stats = listening_stats(events, from, to, 10);
+    let in_year: Vec<&PlayEvent> = events
+        .iter()
+        .filter(|e| e.timestamp >= from && e.timestamp < to)
+        .collect();
+
+    let mut months: Vec<MonthSummary> = MONTHS
+        .iter()
+        .map(|month| MonthSummary { month: month.to_string(), plays: 0, seconds: 0 })
+        .collect();
+    for event in &in_year {
+        let Ok(date) = glib::DateTime::from_unix_local(event.timestamp as i64) else {
+            continue;
+        };
+        let month = &mut months[(date.month() - 1) as usize];
+        month.plays += u32::from(!event.skipped);
+        month.seconds += event.played_seconds;
+    }
+
+    let counted = in_year.iter().filter(|e| !e.skipped);
+    let distinct_artists = counted.clone().map(|e| &e.artist).collect::<HashSet<_>>().len();
+    let distinct_tracks = counted.map(|e| &e.path).collect::<HashSet<_>>().len();
+
+    YearInReview {
+        year,
+        plays: stats.plays,
+        skips: stats.skips,
+        total_seconds: stats.total_seconds,
+        distinct_artists,
+        distinct_tracks,
+        songs_added: songs.iter().filter(|s| s.date_added >= from && s.date_added < to).count(),
+        top_artists: stats.top_artists,
+        top_albums: stats.top_albums,
+        top_tracks: stats.top_tracks,
+        months,
+    }
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+impl YearInReview {
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    // Uma tabela só: category,rank,name,plays,minutes
+    pub fn to_csv(&self) -> String {
+        let mut lines = vec!["category,rank,name,plays,minutes".to_string()];
+        let mut row = |category: &str, rank: String, name: &str, plays: String, seconds: Option<u64>| {
+            let minutes = seconds.map(|s| (s / 60).to_string()).unwrap_or_default();
+            lines.push(format!("{},{},{},{},{}", category, rank, csv_field(name), plays, minutes));
+        };
+
+        row("summary", String::new(), "Year", self.year.to_string(), None);
+        row("summary", String::new(), "Plays", self.plays.to_string(), Some(self.total_seconds));
+        row("summary", String::new(), "Skips", self.skips.to_string(), None);
+        row("summary", String::new(), "Distinct artists", self.distinct_artists.to_string(), None);
+        row("summary", String::new(), "Distinct tracks", self.distinct_tracks.to_string(), None);
+        row("summary", String::new(), "Songs added", self.songs_added.to_string(), None);
+
+        for (category, entries) in [
+            ("artist", &self.top_artists),
+            ("album", &self.top_albums),
+            ("track", &self.top_tracks),
+        ] {
+            for (rank, entry) in entries.iter().enumerate() {
+                row(category, (rank + 1).to_string(), &entry.name, entry.plays.to_string(), Some(entry.seconds));
+            }
+        }
+        for (index, month) in self.months.iter().enumerate() {
+            row("month", (index + 1).to_string(), &month.month, month.plays.to_string(), Some(month.seconds));
+        }
+
+        lines.join("\n") + "\n"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(artist: &str, title: &str, timestamp: u64, played_seconds: u64, skipped: bool) -> PlayEvent {
+        PlayEvent {
+            timestamp,
+            path: PathBuf::from(format!("/music/{} - {}.mp3", artist, title)),
+            title: title.to_string(),
+            artist: artist.to_string(),
+            album: format!("{} Album", artist),
+            played_seconds,
+            skipped,
+        }
+    }
+
+    fn local(year: i32, month: i32, day: i32) -> u64 {
+        glib::DateTime::from_local(year, month, day, 12, 0, 0.0).unwrap().to_unix() as u64
+    }
+
+    #[test]
+    fn listen_counts_only_time_played() {
+        let song = Song::new(PathBuf::from("/music/a.mp3"), "A".into(), "B".into(), "C".into(), None);
+        let mut listen = ListenTracker::new(&song);
+        listen.advance(Duration::from_secs(1));
+        listen.advance(Duration::from_secs(2));
+        // Seek para frente e para trás
+        listen.advance(Duration::from_secs(120));
+        listen.advance(Duration::from_secs(121));
+        listen.advance(Duration::from_secs(10));
+        listen.advance(Duration::from_millis(11_500));
+        assert_eq!(listen.played(), Duration::from_millis(4_500));
+    }
+
+    #[test]
+    fn resumed_listen_starts_from_the_resume_point() {
+        // Ao retomar a sessão a posição já começa no meio da música
+        let song = Song::new(PathBuf::from("/music/a.mp3"), "A".into(), "B".into(), "C".into(), None);
+        let mut listen = ListenTracker::new(&song);
+        listen.advance(Duration::from_secs(200));
+        listen.advance(Duration::from_secs(202));
+        assert_eq!(listen.played(), Duration::from_secs(2));
+    }
+
+    #[test]
+    fn stats_count_plays_and_skips_in_range() {
+        let events = vec![
+            event("A", "One", 100, 200, false),
+            event("A", "One", 200, 180, false),
+            event("B", "Two", 300, 10, true),
+            event("B", "Two", 400, 240, false),
+            event("C", "Three", 1000, 300, false),
+        ];
+        let stats = listening_stats(&events, 100, 1000, 10);
+
+        assert_eq!(stats.plays, 3);
+        assert_eq!(stats.skips, 1);
+        assert_eq!(stats.total_seconds, 630);
+        let artists: Vec<(&str, u32, u64)> = stats
+            .top_artists
+            .iter()
+            .map(|e| (e.name.as_str(), e.plays, e.seconds))
+            .collect();
+        assert_eq!(artists, vec![("A", 2, 380), ("B", 1, 250)]);
+        assert_eq!(stats.top_tracks[0].name, "A — One");
+        assert_eq!(stats.top_albums[1].name, "B Album — B");
+    }
+
+    #[test]
+    fn stats_ties_break_by_time_then_name() {
+        let events = vec![
+            event("B", "x", 0, 100, false),
+            event("C", "x", 0, 100, false),
+            event("A", "x", 0, 50, false),
+            event("D", "x", 0, 500, true),
+        ];
+        let stats = listening_stats(&events, 0, 1, 2);
+        let names: Vec<&str> = stats.top_artists.iter().map(|e| e.name.as_str()).collect();
+        // Só pulada não entra no ranking, e o limite corta o resto
+        assert_eq!(names, vec!["B", "C"]);
+    }
+
+    #[test]
+    fn year_in_review_uses_only_that_year() {
+        let mut added = Song::new(PathBuf::from("/music/new.mp3"), "New".into(), "A".into(), "X".into(), None);
+        added.date_added = local(2024, 6, 1);
+        let mut old = added.clone();
+        old.date_added = local(2023, 6, 1);
+
+        let events = vec![
+            event("A", "One", local(2023, 12, 30), 100, false),
+            event("A", "One", local(2024, 1, 2), 100, false),
+            event("A", "Two", local(2024, 3, 10), 200, false),
+            event("B", "One", local(2024, 3, 11), 30, true),
+            event("A", "One", local(2025, 1, 2), 100, false),
+        ];
+        let review = year_in_review(&events, &[added, old], 2024);
+
+        assert_eq!(review.plays, 2);
+        assert_eq!(review.skips, 1);
+        assert_eq!(review.total_seconds, 330);
+        assert_eq!(review.distinct_artists, 1);
+        assert_eq!(review.distinct_tracks, 2);
+        assert_eq!(review.songs_added, 1);
+        assert_eq!(review.months.len(), 12);
+        assert_eq!((review.months[0].plays, review.months[0].seconds), (1, 100));
+        assert_eq!((review.months[2].plays, review.months[2].seconds), (1, 230));
+        assert_eq!(review.months[11].plays, 0);
+    }
+
+    #[test]
+    fn csv_has_header_summary_and_quoted_names() {
+        let events = vec![event("Earth, Wind & Fire", "September", local(2024, 9, 21), 215, false)];
+        let review = year_in_review(&events, &[], 2024);
+        let csv = review.to_csv();
+        let lines: Vec<&str> = csv.lines().collect();
+
+        assert_eq!(lines[0], "category,rank,name,plays,minutes");
+        assert_eq!(lines[1], "summary,,Year,2024,");
+        assert_eq!(lines[2], "summary,,Plays,1,3");
+        assert!(lines.contains(&"artist,1,\"Earth, Wind & Fire\",1,3"));
+        assert!(lines.contains(&"month,9,September,1,3"));
+        assert_eq!(lines.iter().filter(|l| l.starts_with("month,")).count(), 12);
+        assert!(csv.ends_with('\n'));
+    }
+
+    #[test]
+    fn csv_field_escapes_quotes() {
+        assert_eq!(csv_field("plain"), "plain");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_field("a\nb"), "\"a\nb\"");
+    }
+}
@@ -0,0 +1,103 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use super::playlist::Playlist;
+use super::smart::SmartPlaylist;
+use super::{MusicLibrary, Song};
+use crate::config::data_dir;
+
+const LIBRARY_FILE: &str = "library.json";
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct LibraryFile {
+    #[serde(default)]
+    songs: Vec<Song>,
+    #[serde(default)]
+    playlists: Vec<Playlist>,
+    #[serde(default)]
+    smart_playlists: Vec<SmartPlaylist>,
+}
+
+fn library_path() -> PathBuf {
+    data_dir().join(LIBRARY_FILE)
+}
+
+impl MusicLibrary {
+    // Biblioteca salva em $XDG_DATA_HOME/librefy/library.json
+    pub fn load() -> Self {
+        Self::load_from(&library_path())
+    }
+
+    fn load_from(path: &Path) -> Self {
+        let mut library = MusicLibrary::new();
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(_) => return library,
+        };
+
+        match serde_json::from_str::<LibraryFile>(&contents) {
+            Ok(file) => {
+                library.songs = file.songs;
+                library.playlists = file.playlists;
+                library.smart_playlists = file.smart_playlists;
+                library.organize_library();
+                println!("Loaded {} songs from {}", library.songs.len(), path.display());
+            }
+            Err(e) => {
+                // Guarda o arquivo ao lado; a próxima gravação não pode apagar notas e playlists
+                let aside = path.with_extension("json.bad");
+                eprintln!("Ignoring invalid library file {}: {}", path.display(), e);
+                match fs::rename(path, &aside) {
+                    Ok(()) => eprintln!("Kept the invalid library file as {}", aside.display()),
+                    Err(e) => eprintln!("Failed to move {} aside: {}", path.display(), e),
+                }
+            }
+        }
+        library
+    }
+
+    pub fn save(&self) -> Result<()> {
+        self.save_to(&library_path())
+    }
+
+    fn save_to(&self, path: &Path) -> Result<()> {
+        let file = LibraryFile {
+            songs: self.songs.clone(),
+            playlists: self.playlists.clone(),
+            smart_playlists: self.smart_playlists.clone(),
+        };
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        // Grava num arquivo temporário e renomeia, para não corromper a biblioteca
+        let temp = path.with_extension("json.tmp");
+        fs::write(&temp, serde_json::to_string(&file)?)?;
+        fs::rename(&temp, path)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn invalid_library_file_survives_the_next_save() {
+        let dir = std::env::temp_dir().join(format!("librefy-storage-test-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(LIBRARY_FILE);
+        fs::write(&path, "{\"songs\": [").unwrap();
+
+        let library = MusicLibrary::load_from(&path);
+        assert!(library.songs.is_empty());
+        library.save_to(&path).unwrap();
+
+        assert_eq!(fs::read_to_string(path.with_extension("json.bad")).unwrap(), "{\"songs\": [");
+        assert!(MusicLibrary::load_from(&path).songs.is_empty());
+        assert!(path.with_extension("json.bad").exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
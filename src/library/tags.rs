@@ -0,0 +1,386 @@
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use anyhow::{anyhow, Result};
+use lofty::config::WriteOptions;
+use lofty::file::TaggedFile;
+use lofty::picture::{Picture, PictureType};
+use lofty::prelude::*;
+use lofty::tag::{ItemValue, Tag, TagItem, TagType};
+
+#[derive(Debug, Clone, Default)]
+pub struct TagInfo {
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub album_artist: Option<String>,
+    pub genre: Option<String>,
+    pub year: Option<u32>,
+    pub track_number: Option<u32>,
+    pub disc_number: Option<u32>,
+    pub duration: Option<u64>,
+    pub bitrate: Option<u32>,
+    pub rating: Option<u8>,
+    pub play_count: Option<u32>,
+}
+
+const POPM_EMAIL: &str = "librefy";
+const FMPS_RATING: &str = "FMPS_RATING";
+const FMPS_PLAYCOUNT: &str = "FMPS_PLAYCOUNT";
+
+// Conversão do POPM (0–255) em estrelas, a mesma do Windows Media Player
+fn popm_to_stars(value: u8) -> u8 {
+    match value {
+        0 => 0,
+        1..=31 => 1,
+        32..=95 => 2,
+        96..=159 => 3,
+        160..=223 => 4,
+        _ => 5,
+    }
+}
+
+fn stars_to_popm(stars: u8) -> u8 {
+    [0, 1, 64, 128, 196, 255][stars.min(5) as usize]
+}
+
+// Vorbis RATING e o "rate" do MP4 costumam ir de 0 a 100; alguns players gravam direto 0 a 5
+fn percent_to_stars(value: u32) -> u8 {
+    if value <= 5 {
+        value as u8
+    } else {
+        ((value.min(100) + 10) / 20) as u8
+    }
+}
+
+fn stars_to_percent(stars: u8) -> u32 {
+    u32::from(stars.min(5)) * 20
+}
+
+// Conteúdo do POPM: e-mail terminado em zero, nota e contador big-endian
+fn parse_popm(data: &[u8]) -> Option<(u8, u32)> {
+    let email_end = data.iter().position(|b| *b == 0)?;
+    let rating = *data.get(email_end + 1)?;
+    let counter = data
+        .get(email_end + 2..)
+        .unwrap_or_default()
+        .iter()
+        .fold(0u64, |acc, b| (acc << 8) | u64::from(*b));
+    Some((rating, counter.min(u64::from(u32::MAX)) as u32))
+}
+
+fn popm_bytes(stars: u8, play_count: u32) -> Vec<u8> {
+    let mut data = POPM_EMAIL.as_bytes().to_vec();
+    data.push(0);
+    data.push(stars_to_popm(stars));
+    data.extend(play_count.to_be_bytes());
+    data
+}
+
+fn is_own_popm(value: &ItemValue) -> bool {
+    matches!(value, ItemValue::Binary(data) if data.split(|b| *b == 0).next() == Some(POPM_EMAIL.as_bytes()))
+}
+
+fn fmps_key(tag_type: TagType, name: &str) -> ItemKey {
+    // No ID3v2 vira um TXXX com a descrição "FMPS_Rating"
+    match tag_type {
+        TagType::Id3v2 => ItemKey::Unknown(name.replace("RATING", "Rating").replace("PLAYCOUNT", "Playcount")),
+        _ => ItemKey::Unknown(name.to_string()),
+    }
+}
+
+fn read_stats(tag: &Tag, info: &mut TagInfo) {
+    let tag_type = tag.tag_type();
+
+    if let Some(value) = tag.get_string(&fmps_key(tag_type, FMPS_RATING)) {
+        if let Ok(value) = value.trim().parse::<f64>() {
+            info.rating = Some((value.clamp(0.0, 1.0) * 5.0).round() as u8);
+        }
+    }
+    if let Some(value) = tag.get_string(&fmps_key(tag_type, FMPS_PLAYCOUNT)) {
+        if let Ok(value) = value.trim().parse::<f64>() {
+            info.play_count = Some(value.max(0.0) as u32);
+        }
+    }
+
+    for item in tag.get_items(&ItemKey::Popularimeter) {
+        match item.value() {
+            ItemValue::Binary(data) => {
+                if let Some((rating, counter)) = parse_popm(data) {
+                    info.rating = info.rating.or(Some(popm_to_stars(rating)));
+                    info.play_count = info.play_count.or(Some(counter).filter(|c| *c > 0));
+                }
+            }
+            ItemValue::Text(text) => {
+                if let Ok(value) = text.trim().parse::<u32>() {
+                    info.rating = info.rating.or(Some(percent_to_stars(value)));
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+// Sem tag no formato principal (ex.: MP3 só com ID3v1), a nova começa como cópia da existente
+fn ensure_primary_tag(tagged_file: &mut TaggedFile) {
+    if tagged_file.primary_tag().is_some() {
+        return;
+    }
+    let mut tag = Tag::new(tagged_file.primary_tag_type());
+    if let Some(existing) = tagged_file.first_tag() {
+        for item in existing.items() {
+            tag.push(item.clone());
+        }
+        for picture in existing.pictures() {
+            tag.push_picture(picture.clone());
+        }
+    }
+    tagged_file.insert_tag(tag);
+}
+
+// Nota e contagem de reproduções no formato que outros players entendem
+fn apply_stats(tag: &mut Tag, rating: u8, play_count: u32) {
+    let tag_type = tag.tag_type();
+
+    match tag_type {
+        TagType::Id3v2 => {
+            // Troca só o nosso POPM; os de outros players ficam
+            tag.retain(|item| !(item.key() == &ItemKey::Popularimeter && is_own_popm(item.value())));
+            tag.push(TagItem::new(
+                ItemKey::Popularimeter,
+                ItemValue::Binary(popm_bytes(rating, play_count)),
+            ));
+        }
+        TagType::Mp4Ilst => {
+            tag.insert_text(ItemKey::Popularimeter, stars_to_percent(rating).to_string());
+        }
+        _ => {}
+    }
+    if matches!(tag_type, TagType::Id3v2 | TagType::VorbisComments) {
+        // insert_text recusa chaves desconhecidas; na gravação viram TXXX / comentário com esse nome
+        let rating = format!("{}", f64::from(rating.min(5)) / 5.0);
+        for (name, value) in [(FMPS_RATING, rating), (FMPS_PLAYCOUNT, play_count.to_string())] {
+            tag.insert_unchecked(TagItem::new(fmps_key(tag_type, name), ItemValue::Text(value)));
+        }
+    }
+}
+
+pub fn write_stats(path: &Path, rating: u8, play_count: u32) -> Result<()> {
+    let mut tagged_file = lofty::read_from_path(path)?;
+    ensure_primary_tag(&mut tagged_file);
+    let tag = tagged_file
+        .primary_tag_mut()
+        .ok_or_else(|| anyhow!("{} does not support tags", path.display()))?;
+    apply_stats(tag, rating, play_count);
+    tagged_file.save_to_path(path, WriteOptions::default())?;
+    Ok(())
+}
+
+pub fn read_tags(path: &Path) -> Result<TagInfo> {
+    let tagged_file = lofty::read_from_path(path)?;
+    let properties = tagged_file.properties();
+
+    let mut info = TagInfo {
+        duration: Some(properties.duration().as_secs()).filter(|d| *d > 0),
+        bitrate: properties.audio_bitrate(),
+        ..TagInfo::default()
+    };
+
+    let tag = match tagged_file.primary_tag().or_else(|| tagged_file.first_tag()) {
+        Some(tag) => tag,
+        None => return Ok(info),
+    };
+
+    let non_empty = |value: Option<std::borrow::Cow<str>>| {
+        value
+            .map(|v| v.trim().to_string())
+            .filter(|v| !v.is_empty())
+    };
+
+    info.title = non_empty(tag.title());
+    info.artist = non_empty(tag.artist());
+    info.album = non_empty(tag.album());
+    info.genre = non_empty(tag.genre());
+    info.album_artist = tag
+        .get_string(&ItemKey::AlbumArtist)
+        .map(|v| v.trim().to_string())
+        .filter(|v| !v.is_empty());
+    info.year = tag.year();
+    info.track_number = tag.track();
+    info.disc_number = tag.disk();
+    read_stats(tag, &mut info);
+
+    Ok(info)
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum CoverEdit {
+    #[default]
+    Keep,
+    Set(PathBuf),
+    Remove,
+}
+
+// None mantém o valor de cada arquivo; texto vazio ou Some(None) apaga o campo
+#[derive(Debug, Clone, Default)]
+pub struct TagEdit {
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    pub album_artist: Option<String>,
+    pub genre: Option<String>,
+    pub year: Option<Option<u32>>,
+    pub track_number: Option<Option<u32>>,
+    pub disc_number: Option<Option<u32>>,
+    pub cover: CoverEdit,
+}
+
+fn apply_text(tag: &mut Tag, key: ItemKey, value: &Option<String>) {
+    match value.as_deref().map(str::trim) {
+        None => {}
+        Some("") => tag.remove_key(&key),
+        Some(text) => {
+            tag.insert_text(key, text.to_string());
+        }
+    }
+}
+
+// Capa embutida: a frontal ou, sem ela, a primeira imagem
+pub fn read_cover(path: &Path) -> Option<Vec<u8>> {
+    let tagged_file = lofty::read_from_path(path).ok()?;
+    let tag = tagged_file.primary_tag().or_else(|| tagged_file.first_tag())?;
+    let picture = tag
+        .get_picture_type(PictureType::CoverFront)
+        .or_else(|| tag.pictures().first())?;
+    Some(picture.data().to_vec())
+}
+
+// Grava no formato nativo do arquivo: ID3v2.4 (MP3), Vorbis comments (FLAC/Ogg) ou átomos MP4
+pub fn write_tags(path: &Path, edit: &TagEdit) -> Result<()> {
+    let mut tagged_file = lofty::read_from_path(path)?;
+    ensure_primary_tag(&mut tagged_file);
+    let tag = tagged_file
+        .primary_tag_mut()
+        .ok_or_else(|| anyhow!("{} does not support tags", path.display()))?;
+
+    apply_text(tag, ItemKey::TrackTitle, &edit.title);
+    apply_text(tag, ItemKey::TrackArtist, &edit.artist);
+    apply_text(tag, ItemKey::AlbumTitle, &edit.album);
+    apply_text(tag, ItemKey::AlbumArtist, &edit.album_artist);
+    apply_text(tag, ItemKey::Genre, &edit.genre);
+
+    match edit.year {
+        None => {}
+        Some(None) => tag.remove_year(),
+        Some(Some(year)) => tag.set_year(year),
+    }
+    match edit.track_number {
+        None => {}
+        Some(None) => tag.remove_track(),
+        Some(Some(track)) => tag.set_track(track),
+    }
+    match edit.disc_number {
+        None => {}
+        Some(None) => tag.remove_disk(),
+        Some(Some(disc)) => tag.set_disk(disc),
+    }
+
+    match &edit.cover {
+        CoverEdit::Keep => {}
+        CoverEdit::Remove => {
+            while tag.picture_count() > 0 {
+                tag.remove_picture(0);
+            }
+        }
+        CoverEdit::Set(image) => {
+            let mut picture = Picture::from_reader(&mut File::open(image)?)?;
+            picture.set_pic_type(PictureType::CoverFront);
+            tag.remove_picture_type(PictureType::CoverFront);
+            tag.push_picture(picture);
+        }
+    }
+
+    tagged_file.save_to_path(path, WriteOptions::default())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn popm_ratings_round_trip() {
+        for stars in 0..=5 {
+            assert_eq!(popm_to_stars(stars_to_popm(stars)), stars);
+        }
+        assert_eq!(stars_to_popm(9), 255);
+        // Limites das faixas do Windows Media Player
+        let stars: Vec<u8> = [0, 1, 31, 32, 95, 96, 159, 160, 223, 224, 255].map(popm_to_stars).to_vec();
+        assert_eq!(stars, vec![0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5]);
+    }
+
+    #[test]
+    fn parses_popm_frames() {
+        assert_eq!(parse_popm(&popm_bytes(4, 1234)), Some((196, 1234)));
+        assert_eq!(parse_popm(&popm_bytes(7, 0)), Some((255, 0)));
+
+        // Contador ausente ou mais curto que 4 bytes
+        assert_eq!(parse_popm(b"someone@example.com\0\x80"), Some((128, 0)));
+        assert_eq!(parse_popm(b"\0\x40\x01\x02"), Some((64, 258)));
+        // Contadores maiores que 32 bits saturam
+        assert_eq!(parse_popm(b"x\0\xff\x01\x00\x00\x00\x00"), Some((255, u32::MAX)));
+
+        assert_eq!(parse_popm(b"no terminator"), None);
+        assert_eq!(parse_popm(b"no rating\0"), None);
+        assert_eq!(parse_popm(b""), None);
+    }
+
+    #[test]
+    fn percent_ratings_round_trip() {
+        for stars in 0..=5 {
+            assert_eq!(percent_to_stars(stars_to_percent(stars)), stars);
+        }
+        assert_eq!(stars_to_percent(8), 100);
+        let stars: Vec<u8> = [0, 3, 5, 6, 29, 30, 50, 69, 70, 90, 100, 250].map(percent_to_stars).to_vec();
+        assert_eq!(stars, vec![0, 3, 5, 0, 1, 2, 3, 3, 4, 5, 5, 5]);
+    }
+
+    fn stats(tag: &Tag) -> (Option<u8>, Option<u32>) {
+        let mut info = TagInfo::default();
+        read_stats(tag, &mut info);
+        (info.rating, info.play_count)
+    }
+
+    #[test]
+    fn written_stats_read_back() {
+        for tag_type in [TagType::Id3v2, TagType::VorbisComments, TagType::Mp4Ilst] {
+            let mut tag = Tag::new(tag_type);
+            apply_stats(&mut tag, 4, 37);
+            apply_stats(&mut tag, 2, 38);
+            // O MP4 só guarda a nota
+            let plays = (tag_type != TagType::Mp4Ilst).then_some(38);
+            assert_eq!(stats(&tag), (Some(2), plays), "{:?}", tag_type);
+        }
+    }
+
+    #[test]
+    fn keeps_other_players_popm() {
+        let mut tag = Tag::new(TagType::Id3v2);
+        tag.push(TagItem::new(ItemKey::Popularimeter, ItemValue::Binary(b"other\0\x40\x05".to_vec())));
+        apply_stats(&mut tag, 5, 1);
+        apply_stats(&mut tag, 3, 2);
+        let popms: Vec<_> = tag.get_items(&ItemKey::Popularimeter).filter_map(|item| item.value().binary()).collect();
+        assert_eq!(popms, vec![&b"other\0\x40\x05"[..], &popm_bytes(3, 2)[..]]);
+    }
+
+    #[test]
+    fn reads_percent_ratings() {
+        let mut tag = Tag::new(TagType::VorbisComments);
+        tag.insert_text(ItemKey::Popularimeter, "80".to_string());
+        assert_eq!(stats(&tag), (Some(4), None));
+
+        // FMPS tem prioridade sobre o RATING de 0 a 100
+        apply_stats(&mut tag, 2, 12);
+        tag.insert_text(ItemKey::Popularimeter, "100".to_string());
+        assert_eq!(stats(&tag), (Some(2), Some(12)));
+    }
+}
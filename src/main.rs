@@ -1,34 +1,117 @@
 use gtk4::prelude::*;
 use gtk4::{
-    Application, ApplicationWindow, Box, Orientation, Button, Label, 
-    ScrolledWindow, CssProvider, ProgressBar, Window,
-    FileDialog, Entry, ListBox, ListBoxRow
+    ApplicationWindow, Box, Orientation, Button, Label, 
+    ScrolledWindow, ProgressBar, Window,
+    FileDialog, Entry
 };
 use std::rc::Rc;
-use std::cell::RefCell;
+use std::cell::{Cell, OnceCell, RefCell};
+use std::collections::HashSet;
 use std::path::PathBuf;
 
+mod cli;
+mod config;
+mod mpris;
 mod player;
+mod scrobble;
+mod session;
 mod library;
-use player::MusicPlayer;
+mod ui;
+use player::{MusicPlayer, PlayerEvent};
+use player::queue::{PlayQueue, RepeatMode};
 use library::{MusicLibrary, Song};
+use library::playlist::{PathStyle, PlaylistFormat};
+use ui::song_object::SongObject;
+use libadwaita as adw;
+use config::{
+    AppSettings, ColorScheme, EqualizerSettings, ListeningSettings, NotificationSettings, OpenAction, OpenSettings, ScrobbleSettings,
+    TrackTableLayout
+};
+use library::stats::ListenTracker;
+use scrobble::{Scrobble, Scrobbler, SubmitError};
 
 const APP_ID: &str = "org.librefy.Librefy";
 
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+enum LibraryView {
+    AllSongs,
+    Playlist(usize),
+    SmartPlaylist(usize),
+    Artist(String),
+    Album(String),
+}
+
 struct AppState {
-    is_dark_theme: bool,
+    settings: AppSettings,
+    theme: Rc<ui::theme::ThemeManager>,
     player: Option<MusicPlayer>,
     library: MusicLibrary,
     current_song: Option<Song>,
+    current_view: LibraryView,
+    // Revisão da biblioteca, hora da avaliação e músicas da playlist inteligente na tela
+    smart_view: (u64, u64, Vec<PathBuf>),
+    song_store: gio::ListStore,
+    // Seleção da tabela de músicas visível, usada pelas ações "song.*"
+    song_selection: Option<gtk4::MultiSelection>,
+    // Rolagem da tabela de músicas visível
+    song_scroll: Option<gtk4::Adjustment>,
+    queue: PlayQueue,
+    track_layout: TrackTableLayout,
+    listening: ListeningSettings,
+    // Música atual, até contar como reproduzida ou ser pulada
+    listen: Option<ListenTracker>,
+    scrobbler: Scrobbler,
+    equalizer: EqualizerSettings,
+    opening: OpenSettings,
+    notifications: NotificationSettings,
+    // Biblioteca alterada e ainda não gravada no disco
+    library_dirty: Cell<bool>,
 }
 
 impl AppState {
     fn new() -> Self {
+        let listening = ListeningSettings::load();
+        let mut library = MusicLibrary::load();
+        library.sync_rating_tags = listening.sync_rating_tags;
+
+        let song_store = gio::ListStore::new::<SongObject>();
+        let songs: Vec<SongObject> = library.songs.iter().cloned().map(SongObject::new).collect();
+        song_store.extend_from_slice(&songs);
+
         Self {
-            is_dark_theme: false,
+            settings: AppSettings::load(),
+            theme: ui::theme::ThemeManager::new(),
             player: None,
-            library: MusicLibrary::new(),
+            library,
             current_song: None,
+            current_view: LibraryView::AllSongs,
+            smart_view: (0, 0, Vec::new()),
+            song_store,
+            song_selection: None,
+            song_scroll: None,
+            queue: PlayQueue::new(),
+            track_layout: TrackTableLayout::load(),
+            listening,
+            listen: None,
+            scrobbler: Scrobbler::new(ScrobbleSettings::load()),
+            equalizer: EqualizerSettings::load(),
+            opening: OpenSettings::load(),
+            notifications: NotificationSettings::load(),
+            library_dirty: Cell::new(false),
+        }
+    }
+
+    // Gravação adiada: cada reprodução e nota mudam a biblioteca, o timer grava de uma vez
+    fn save_library(&self) {
+        self.library_dirty.set(true);
+    }
+
+    fn flush_library(&self) {
+        if !self.library_dirty.replace(false) {
+            return;
+        }
+        if let Err(e) = self.library.save() {
+            eprintln!("Failed to save library: {}", e);
         }
     }
 }
@@ -36,46 +119,108 @@ impl AppState {
 fn main() -> glib::ExitCode {
     env_logger::init();
     
-    let app = Application::builder()
+    let app = adw::Application::builder()
         .application_id(APP_ID)
+        .flags(gio::ApplicationFlags::HANDLES_COMMAND_LINE | gio::ApplicationFlags::HANDLES_OPEN)
         .build();
 
-    app.connect_activate(|app| {
-        let state = Rc::new(RefCell::new(AppState::new()));
-        build_ui(app, state);
+    cli::add_options(&app);
+    app.connect_handle_local_options(cli::handle_local_options);
+
+    // A janela é criada uma vez só, pela ativação ou pela linha de comando
+    let ui_state: Rc<OnceCell<Rc<RefCell<AppState>>>> = Rc::default();
+
+    let ui_state_clone = Rc::clone(&ui_state);
+    app.connect_activate(move |app| {
+        start_ui(app, &ui_state_clone);
+        if let Some(window) = app.active_window() {
+            window.present();
+        }
+    });
+
+    // Arquivos abertos pelo gerenciador de arquivos (ativação por D-Bus)
+    let ui_state_clone = Rc::clone(&ui_state);
+    app.connect_open(move |app, files, _| {
+        let state = start_ui(app, &ui_state_clone);
+        let paths: Vec<PathBuf> = files.iter().filter_map(|file| file.path()).collect();
+        let action = state.borrow().opening.action;
+        open_files(&state, paths, action, |errors| {
+            for error in errors {
+                eprintln!("{}", error);
+            }
+        });
+        if let Some(window) = app.active_window() {
+            window.present();
+        }
+    });
+
+    app.connect_command_line(move |app, command_line| {
+        let request = cli::Request::parse(command_line);
+        if request.is_empty() {
+            app.activate();
+            return 0;
+        }
+        let state = start_ui(app, &ui_state);
+        if request.opens_files() {
+            if let Some(window) = app.active_window() {
+                window.present();
+            }
+        }
+        cli::run(&state, &request, command_line)
     });
     
     app.run()
 }
 
-fn load_css() {
-    let provider = CssProvider::new();
-    provider.load_from_string(include_str!("../data/themes/librefy.css"));
-    
-    gtk4::style_context_add_provider_for_display(
-        &gtk4::gdk::Display::default().expect("Could not connect to a display."),
-        &provider,
-        gtk4::STYLE_PROVIDER_PRIORITY_APPLICATION,
-    );
+fn start_ui(app: &adw::Application, ui_state: &OnceCell<Rc<RefCell<AppState>>>) -> Rc<RefCell<AppState>> {
+    let state = ui_state.get_or_init(|| {
+        let state = Rc::new(RefCell::new(AppState::new()));
+        build_ui(app, Rc::clone(&state));
+        state
+    });
+    Rc::clone(state)
 }
 
-fn build_ui(app: &Application, state: Rc<RefCell<AppState>>) {
-    load_css();
-    
+fn set_color_scheme(state: &Rc<RefCell<AppState>>, scheme: ColorScheme) {
+    state.borrow_mut().settings.color_scheme = scheme;
+    adw::StyleManager::default().set_color_scheme(match scheme {
+        ColorScheme::System => adw::ColorScheme::Default,
+        ColorScheme::Light => adw::ColorScheme::ForceLight,
+        ColorScheme::Dark => adw::ColorScheme::ForceDark,
+    });
+}
+
+fn build_ui(app: &adw::Application, state: Rc<RefCell<AppState>>) {
+    let settings = state.borrow().settings.clone();
+    set_color_scheme(&state, settings.color_scheme);
+    let theme = Rc::clone(&state.borrow().theme);
+    theme.install();
+    theme.set_theme(settings.theme.as_deref());
+    ui::theme::refresh_accent(&state);
+
+    let (width, height) = if settings.remember_window_size {
+        (settings.window_width, settings.window_height)
+    } else {
+        AppSettings::default().window_size()
+    };
     let window = ApplicationWindow::builder()
         .application(app)
         .title("Librefy")
-        .default_width(1200)
-        .default_height(800)
+        .default_width(width)
+        .default_height(height)
+        .maximized(settings.remember_window_size && settings.window_maximized)
         .build();
 
-    window.add_css_class("light-theme");
 
     // Inicializa o player
     {
         let mut app_state = state.borrow_mut();
         match MusicPlayer::new() {
-            Ok(player) => {
+            Ok(mut player) => {
+                player.set_equalizer(&app_state.equalizer);
+                if let Err(e) = player.set_audio_output(settings.audio_output) {
+                    eprintln!("Failed to use audio output {}: {}", settings.audio_output.label(), e);
+                }
                 app_state.player = Some(player);
                 println!("Player initialized successfully");
             }
@@ -86,36 +231,130 @@ fn build_ui(app: &Application, state: Rc<RefCell<AppState>>) {
     }
 
     let main_vertical = Box::new(Orientation::Vertical, 0);
-    main_vertical.add_css_class("light-theme");
     
     let content_horizontal = Box::new(Orientation::Horizontal, 0);
-    content_horizontal.add_css_class("light-theme");
     
     // Cria a área de conteúdo primeiro
     let (content_scroll, content_box) = create_content_area(&state);
     
     // Cria a sidebar passando o content_box
-    let sidebar = create_sidebar(&state, &window, &main_vertical, &content_horizontal, &content_box, &content_scroll);
+    let (sidebar, playlists_box) = create_sidebar(&state, &window, &content_box);
     sidebar.set_size_request(250, -1);
-    sidebar.add_css_class("light-theme");
     content_horizontal.append(&sidebar);
     
-    content_scroll.add_css_class("light-theme");
-    content_horizontal.append(&content_scroll);
+    // Botões das notificações
+    ui::notification::install_actions(app.upcast_ref(), &state);
+    
+    // Ações do menu de contexto das músicas
+    ui::song_actions::install_song_actions(&window, &state, &content_box, &playlists_box);
+    
+    // Barra de busca acima do conteúdo
+    let content_vertical = Box::new(Orientation::Vertical, 0);
+    let search_bar = ui::search::create_search_bar(&window, &state, &content_box);
+    content_vertical.append(&search_bar);
     
-    let player_controls = create_player_controls(&state);
+    content_vertical.append(&content_scroll);
+    content_horizontal.append(&content_vertical);
+    
+    // Painel de letras à direita, aberto pelo botão no player
+    let lyrics_panel = ui::lyrics::create_lyrics_panel(&window, &state);
+    content_horizontal.append(&lyrics_panel);
+    
+    let player_controls = create_player_controls(&state, &window, &lyrics_panel);
     player_controls.set_size_request(-1, 100);
-    player_controls.add_css_class("light-theme");
     
     main_vertical.append(&content_horizontal);
     main_vertical.append(&player_controls);
     
+    // Arrastar arquivos para a janela abre; para a barra do player, entra na fila
+    let state_clone = Rc::clone(&state);
+    let content_clone = content_box.clone();
+    main_vertical.add_controller(ui::file_drop::file_drop_target(move |paths| {
+        let action = state_clone.borrow().opening.action;
+        drop_files(&state_clone, &content_clone, paths, action);
+    }));
+    let state_clone = Rc::clone(&state);
+    let content_clone = content_box.clone();
+    player_controls.add_controller(ui::file_drop::file_drop_target(move |paths| {
+        drop_files(&state_clone, &content_clone, paths, OpenAction::Enqueue);
+    }));
+    
     content_horizontal.set_vexpand(true);
+    content_vertical.set_hexpand(true);
     content_scroll.set_hexpand(true);
     content_scroll.set_vexpand(true);
 
+    // Conta a reprodução depois de tocar tempo suficiente
+    let state_clone = Rc::clone(&state);
+    glib::timeout_add_seconds_local(1, move || {
+        poll_player(&state_clone);
+        check_listen(&state_clone);
+        glib::ControlFlow::Continue
+    });
+
+    // Playlist inteligente aberta acompanha as mudanças da biblioteca
+    let state_clone = Rc::clone(&state);
+    let content_clone = content_box.clone();
+    glib::timeout_add_seconds_local(1, move || {
+        refresh_smart_playlist(&content_clone, &state_clone);
+        glib::ControlFlow::Continue
+    });
+
+    // Controle pelo GNOME Shell, playerctl e teclas de mídia
+    mpris::start(&window, &state);
+
+    // Reenvia scrobbles que ficaram na fila (ex.: sem conexão)
+    flush_scrobbles(&state);
+    let state_clone = Rc::clone(&state);
+    glib::timeout_add_seconds_local(300, move || {
+        flush_scrobbles(&state_clone);
+        glib::ControlFlow::Continue
+    });
+
+    // Grava as mudanças pendentes da biblioteca
+    let state_clone = Rc::clone(&state);
+    glib::timeout_add_seconds_local(5, move || {
+        state_clone.borrow().flush_library();
+        glib::ControlFlow::Continue
+    });
+
+    // Salva a sessão de tempos em tempos, para não perder tudo se o app travar
+    let state_clone = Rc::clone(&state);
+    glib::timeout_add_seconds_local(30, move || {
+        session::save_in_background(&state_clone);
+        glib::ControlFlow::Continue
+    });
+
+    // Salva a sessão, o layout da tabela de músicas, o tamanho da janela e a biblioteca ao fechar
+    let state_clone = Rc::clone(&state);
+    window.connect_close_request(move |window| {
+        session::save(&state_clone);
+        let mut app_state = state_clone.borrow_mut();
+        finish_listen(&mut app_state);
+        app_state.track_layout.save();
+        app_state.equalizer.save();
+        app_state.settings.window_maximized = window.is_maximized();
+        if !window.is_maximized() {
+            app_state.settings.window_width = window.default_width();
+            app_state.settings.window_height = window.default_height();
+        }
+        app_state.settings.save();
+        app_state.save_library();
+        app_state.flush_library();
+        glib::Propagation::Proceed
+    });
+
     window.set_child(Some(&main_vertical));
     window.present();
+
+    session::restore(&state, &window, &content_box);
+
+    if settings.scan_on_startup {
+        let state_clone = Rc::clone(&state);
+        glib::idle_add_local_once(move || {
+            scan_and_refresh(&state_clone, &content_box);
+        });
+    }
 }
 
 fn update_song_list(content: &Box, state: &Rc<RefCell<AppState>>) {
@@ -124,15 +363,19 @@ fn update_song_list(content: &Box, state: &Rc<RefCell<AppState>>) {
         content.remove(&child);
     }
 
-    let app_state = state.borrow();
+    state.borrow_mut().current_view = LibraryView::AllSongs;
+    let (is_empty, song_store) = {
+        let app_state = state.borrow();
+        (app_state.library.songs.is_empty(), app_state.song_store.clone())
+    };
     
-    if app_state.library.songs.is_empty() {
+    if is_empty {
         // Mostra mensagem de boas-vindas se não há músicas
         let welcome_label = Label::new(Some("Welcome to Librefy! 🎵"));
         welcome_label.add_css_class("title-1");
         content.append(&welcome_label);
         
-        let subtitle = Label::new(Some("Click \"Add Song\" to add music or \"Scan Music Folder\" to import your library"));
+        let subtitle = Label::new(Some("Click \"Add Song\" to add music or \"Scan Library Folders\" to import your library"));
         subtitle.add_css_class("title-4");
         content.append(&subtitle);
     } else {
@@ -141,14 +384,8 @@ fn update_song_list(content: &Box, state: &Rc<RefCell<AppState>>) {
         songs_label.add_css_class("song-list-header");
         content.append(&songs_label);
 
-        let song_list = ListBox::new();
-        song_list.add_css_class("song-list");
-        
-        for song in &app_state.library.songs {
-            let row = create_song_row(song, state);
-            song_list.append(&row);
-        }
-        
+        // O modelo é compartilhado: novas músicas entram sem reconstruir a lista
+        let song_list = ui::song_list::create_song_list_view(state, &song_store, &[]);
         content.append(&song_list);
     }
 }
@@ -288,10 +525,17 @@ fn show_add_song_dialog(parent: &ApplicationWindow, state: &Rc<RefCell<AppState>
                 );
 
                 // Adicionar a música ao state
-                {
+                let needs_rebuild = {
                     let mut app_state = state_clone.borrow_mut();
-                    app_state.library.add_song(song);
+                    app_state.library.add_song(song.clone());
+                    app_state.song_store.append(&SongObject::new(song));
+                    app_state.save_library();
                     println!("Song added to library!");
+                    // A lista de todas as músicas se atualiza sozinha pelo modelo
+                    app_state.library.songs.len() == 1 || app_state.current_view != LibraryView::AllSongs
+                };
+                if !needs_rebuild {
+                    return;
                 }
                 
                 // Atualizar a UI de forma segura usando timeout - clonando aqui dentro
@@ -345,80 +589,784 @@ fn show_add_song_dialog(parent: &ApplicationWindow, state: &Rc<RefCell<AppState>
     dialog.present();
 }
 
-fn create_song_row(song: &Song, state: &Rc<RefCell<AppState>>) -> ListBoxRow {
-    let row = ListBoxRow::new();
-    let row_box = Box::new(Orientation::Horizontal, 10);
-    row_box.set_margin_top(5);
-    row_box.set_margin_bottom(5);
-    row_box.set_margin_start(10);
-    row_box.set_margin_end(10);
+fn refresh_view(content: &Box, window: &ApplicationWindow, state: &Rc<RefCell<AppState>>) {
+    let view = state.borrow().current_view.clone();
+    match view {
+        LibraryView::AllSongs => update_song_list(content, state),
+        LibraryView::Playlist(index) => show_playlist(content, window, state, index),
+        LibraryView::SmartPlaylist(index) => show_smart_playlist(content, state, index),
+        LibraryView::Artist(name) => show_artist(content, state, &name),
+        LibraryView::Album(name) => show_album(content, state, &name),
+    }
+}
 
-    // Album art or placeholder
-    let album_art = Label::new(Some("🎵"));
-    album_art.add_css_class("song-album-art");
-    album_art.set_size_request(40, 40);
+// Ao fim da música toca a próxima da fila
+fn poll_player(state: &Rc<RefCell<AppState>>) {
+    let event = match state.try_borrow_mut() {
+        Ok(mut app_state) => app_state.player.as_mut().and_then(|player| player.poll_event()),
+        Err(_) => return,
+    };
+    match event {
+        Some(PlayerEvent::Finished) => {
+            let repeat = {
+                let app_state = state.borrow();
+                app_state.current_song.clone().filter(|_| app_state.queue.repeat == RepeatMode::Track)
+            };
+            match repeat {
+                Some(song) => play_song(state, &song),
+                None if !play_next_track(state) => println!("Reached the end of the queue"),
+                None => {}
+            }
+        }
+        Some(PlayerEvent::Error(message)) => eprintln!("Playback error: {}", message),
+        None => {}
+    }
+}
 
-    // Song info
-    let song_info = Box::new(Orientation::Vertical, 2);
-    song_info.set_hexpand(true);
-    
-    let title_label = Label::new(Some(&song.title));
-    title_label.add_css_class("song-title");
-    title_label.set_halign(gtk4::Align::Start);
-    
-    let artist_label = Label::new(Some(&format!("{} • {}", song.artist, song.album)));
-    artist_label.add_css_class("song-artist");
-    artist_label.set_halign(gtk4::Align::Start);
+// Controles de reprodução, usados pelos botões e pelo MPRIS
+fn play_next_track(state: &Rc<RefCell<AppState>>) -> bool {
+    let next = {
+        let mut app_state = state.borrow_mut();
+        let current = app_state.current_song.clone();
+        app_state.queue.next(current)
+    };
+    match next {
+        Some(song) => {
+            play_song(state, &song);
+            true
+        }
+        None => false,
+    }
+}
+
+fn play_previous_track(state: &Rc<RefCell<AppState>>) -> bool {
+    let previous = {
+        let mut app_state = state.borrow_mut();
+        let current = app_state.current_song.clone();
+        app_state.queue.previous(current)
+    };
+    match previous {
+        Some(song) => {
+            play_song(state, &song);
+            true
+        }
+        None => false,
+    }
+}
+
+fn resume_playback(state: &Rc<RefCell<AppState>>) {
+    let loaded = state.borrow().player.as_ref().is_some_and(|p| p.current_file().is_some());
+    if !loaded {
+        // Nada carregado ainda: começa pela fila
+        if !play_next_track(state) {
+            println!("Queue is empty");
+        }
+        return;
+    }
+
+    let mut app_state = state.borrow_mut();
+    let app_state = &mut *app_state;
+    let Some(ref mut player) = app_state.player else {
+        return;
+    };
+    match player.play() {
+        Ok(_) => {
+            // Voltar depois de parar conta como uma nova escuta
+            if app_state.listen.is_none() {
+                app_state.listen = app_state.current_song.as_ref().map(ListenTracker::new);
+            }
+            println!("Playing");
+        }
+        Err(e) => {
+            eprintln!("Failed to play: {}", e);
+        }
+    }
+}
+
+fn pause_playback(state: &Rc<RefCell<AppState>>) {
+    if let Some(ref mut player) = state.borrow_mut().player {
+        player.pause();
+        println!("Paused");
+    }
+}
 
-    song_info.append(&title_label);
-    song_info.append(&artist_label);
+fn toggle_playback(state: &Rc<RefCell<AppState>>) {
+    let playing = state.borrow().player.as_ref().is_some_and(|p| p.is_playing());
+    if playing {
+        pause_playback(state);
+    } else {
+        resume_playback(state);
+    }
+}
 
-    // Play button
-    let play_btn = Button::with_label("▶");
-    play_btn.add_css_class("song-play-btn");
+fn stop_playback(state: &Rc<RefCell<AppState>>) {
+    let mut app_state = state.borrow_mut();
+    finish_listen(&mut app_state);
+    if let Some(ref mut player) = app_state.player {
+        player.stop();
+        println!("Stopped");
+    }
+}
 
+// Lê as tags fora da thread da interface; músicas que já estão na biblioteca vêm de lá
+fn read_songs<F>(state: &Rc<RefCell<AppState>>, paths: Vec<PathBuf>, done: F)
+where
+    F: FnOnce(Vec<Song>, Vec<String>) + 'static,
+{
+    let sync_rating_tags = state.borrow().library.sync_rating_tags;
+    let handle = gio::spawn_blocking(move || {
+        let mut reader = MusicLibrary::new();
+        reader.sync_rating_tags = sync_rating_tags;
+        reader.songs_for_paths(&paths)
+    });
+
+    let state = Rc::clone(state);
+    glib::MainContext::default().spawn_local(async move {
+        let Ok((songs, errors)) = handle.await else {
+            eprintln!("Reading tags failed");
+            return;
+        };
+        let songs = state.borrow().library.prefer_known(songs);
+        done(songs, errors);
+    });
+}
+
+// Arquivos e pastas vindos de fora do app; `done` recebe os que não puderam ser abertos
+fn open_files<F>(state: &Rc<RefCell<AppState>>, paths: Vec<PathBuf>, action: OpenAction, done: F)
+where
+    F: FnOnce(Vec<String>) + 'static,
+{
     let state_clone = Rc::clone(state);
-    let song_clone = song.clone();
-    play_btn.connect_clicked(move |_| {
-        let mut app_state = state_clone.borrow_mut();
-        if let Some(ref mut player) = app_state.player {
-            if let Some(path_str) = song_clone.file_path.to_str() {
-                match player.load_file(path_str) {
-                    Ok(_) => {
-                        match player.play() {
-                            Ok(_) => {
-                                app_state.current_song = Some(song_clone.clone());
-                                println!("Now playing: {}", song_clone.get_display_name());
-                            }
-                            Err(e) => {
-                                eprintln!("Failed to play: {}", e);
+    read_songs(state, paths, move |songs, errors| {
+        if state_clone.borrow().opening.add_to_library {
+            import_songs(&state_clone, &songs);
+        }
+        open_songs(&state_clone, songs, action);
+        done(errors);
+    });
+}
+
+// Arrastar e soltar sempre importa para a biblioteca
+fn drop_files(state: &Rc<RefCell<AppState>>, content: &Box, paths: Vec<PathBuf>, action: OpenAction) {
+    let state_clone = Rc::clone(state);
+    let content = content.clone();
+    read_songs(state, paths, move |songs, errors| {
+        for error in errors {
+            eprintln!("{}", error);
+        }
+        let state = &state_clone;
+        let was_empty = state.borrow().library.songs.is_empty();
+        if import_songs(state, &songs) > 0 && was_empty && state.borrow().current_view == LibraryView::AllSongs {
+            // Troca a mensagem de boas-vindas pela lista
+            update_song_list(&content, state);
+        }
+        open_songs(state, songs, action);
+    });
+}
+
+// Coloca na biblioteca as músicas que ainda não estão nela; devolve quantas entraram
+fn import_songs(state: &Rc<RefCell<AppState>>, songs: &[Song]) -> usize {
+    let mut app_state = state.borrow_mut();
+    let mut known: HashSet<PathBuf> = app_state.library.songs.iter().map(|s| s.file_path.clone()).collect();
+    let mut added = Vec::new();
+    for song in songs {
+        if known.insert(song.file_path.clone()) {
+            app_state.library.add_song(song.clone());
+            added.push(SongObject::new(song.clone()));
+        }
+    }
+    if !added.is_empty() {
+        app_state.song_store.extend_from_slice(&added);
+        app_state.save_library();
+        println!("Added {} songs to the library", added.len());
+    }
+    added.len()
+}
+
+// Músicas abertas de fora do app: tocam na hora ou vão para o fim da fila
+fn open_songs(state: &Rc<RefCell<AppState>>, songs: Vec<Song>, action: OpenAction) {
+    let idle = state.borrow().current_song.is_none();
+    if action == OpenAction::Enqueue {
+        println!("Added to queue: {} songs", songs.len());
+        state.borrow_mut().queue.enqueue(songs);
+        if idle {
+            play_next_track(state);
+        }
+        return;
+    }
+
+    let mut songs = songs.into_iter();
+    let Some(first) = songs.next() else {
+        return;
+    };
+    state.borrow_mut().queue.replace(songs.collect());
+    play_song(state, &first);
+}
+
+fn check_listen(state: &Rc<RefCell<AppState>>) {
+    let Ok(mut app_state) = state.try_borrow_mut() else {
+        return;
+    };
+    let app_state = &mut *app_state;
+    let position = app_state.player.as_ref().and_then(|p| p.position());
+    let (Some(listen), Some(position)) = (app_state.listen.as_mut(), position) else {
+        return;
+    };
+    listen.advance(position);
+    if listen.counted || listen.played() < listen.threshold(&app_state.listening) {
+        return;
+    }
+
+    listen.counted = true;
+    let path = listen.path.clone();
+    if let Some(song) = app_state.library.record_play(&path) {
+        ui::song_list::replace_songs(&app_state.song_store, &[song]);
+        app_state.save_library();
+    }
+}
+
+// Fecha a escuta atual: registra no histórico e, se não chegou a contar, como pulada
+fn finish_listen(app_state: &mut AppState) {
+    let position = app_state.player.as_ref().and_then(|p| p.position());
+    let Some(mut listen) = app_state.listen.take() else {
+        return;
+    };
+    if let Some(position) = position {
+        listen.advance(position);
+    }
+    let played = listen.played();
+    if played < std::time::Duration::from_secs(1) {
+        return;
+    }
+
+    let skipped = !listen.counted;
+    let song = if skipped {
+        app_state.library.record_skip(&listen.path)
+    } else {
+        app_state.library.songs.iter().find(|s| s.file_path == listen.path).cloned()
+    };
+    let Some(song) = song else {
+        return;
+    };
+    if app_state.scrobbler.is_enabled() && scrobble::should_scrobble(song.duration, played.as_secs()) {
+        app_state.scrobbler.enqueue(Scrobble::new(&song, listen.started));
+    }
+    let event = library::history::PlayEvent::new(&song, listen.started, played.as_secs(), skipped);
+    if let Err(e) = library::history::append(&event) {
+        eprintln!("Failed to write listening history: {}", e);
+    }
+    if skipped {
+        ui::song_list::replace_songs(&app_state.song_store, &[song]);
+    }
+}
+
+fn play_song(state: &Rc<RefCell<AppState>>, song: &Song) {
+    let mut app_state = state.borrow_mut();
+    finish_listen(&mut app_state);
+    apply_auto_equalizer(&mut app_state, song);
+    if let Some(ref mut player) = app_state.player {
+        if let Some(path_str) = song.file_path.to_str() {
+            // Não deixa a música anterior tocando por cima
+            player.stop();
+            match player.load_file(path_str) {
+                Ok(_) => {
+                    match player.play() {
+                        Ok(_) => {
+                            app_state.current_song = Some(song.clone());
+                            app_state.listen = Some(ListenTracker::new(song));
+                            println!("Now playing: {}", song.get_display_name());
+                            if app_state.notifications.track_change {
+                                ui::notification::notify_track_change(song);
                             }
                         }
+                        Err(e) => {
+                            eprintln!("Failed to play: {}", e);
+                        }
                     }
-                    Err(e) => {
-                        eprintln!("Failed to load: {}", e);
-                    }
+                }
+                Err(e) => {
+                    eprintln!("Failed to load: {}", e);
                 }
             }
         }
+    }
+
+    let playing = app_state.listen.as_ref().is_some_and(|listen| listen.path == song.file_path);
+    let settings = app_state.scrobbler.settings.clone();
+    let accent_from_cover = app_state.settings.accent_from_cover;
+    drop(app_state);
+    if playing && accent_from_cover {
+        ui::theme::refresh_accent(state);
+    }
+    if playing && settings.enabled {
+        let scrobble = Scrobble::new(song, library::unix_now());
+        let handle = gio::spawn_blocking(move || scrobble::now_playing(&settings, &scrobble));
+        glib::MainContext::default().spawn_local(async move {
+            if let Ok(Err(e)) = handle.await {
+                eprintln!("Failed to send now playing: {}", e.message());
+            }
+        });
+    }
+    flush_scrobbles(state);
+}
+
+// Troca o preset pelo do álbum ou gênero da música, se configurado
+fn apply_auto_equalizer(app_state: &mut AppState, song: &Song) {
+    let preset = app_state.equalizer.auto_preset(song);
+    if !app_state.equalizer.apply_auto(preset) {
+        return;
+    }
+    if let Some(ref player) = app_state.player {
+        player.set_equalizer(&app_state.equalizer);
+    }
+    println!("Equalizer preset: {}", app_state.equalizer.preset.as_deref().unwrap_or("Custom"));
+}
+
+// Envia a fila de scrobbles em lotes, um de cada vez, fora da thread da interface
+fn flush_scrobbles(state: &Rc<RefCell<AppState>>) {
+    let Some((settings, batch)) = state.borrow_mut().scrobbler.take_batch() else {
+        return;
+    };
+    let sent = batch.len();
+    let handle = gio::spawn_blocking(move || scrobble::submit(&settings, &batch));
+
+    let state = Rc::clone(state);
+    glib::MainContext::default().spawn_local(async move {
+        let result = handle
+            .await
+            .unwrap_or_else(|_| Err(SubmitError::Retry("scrobble thread panicked".to_string())));
+        let more = state.borrow_mut().scrobbler.batch_done(sent, result);
+        if more {
+            flush_scrobbles(&state);
+        }
     });
+}
 
-    row_box.append(&album_art);
-    row_box.append(&song_info);
-    row_box.append(&play_btn);
-    
-    row.set_child(Some(&row_box));
-    row
+// Varre as pastas da biblioteca configuradas nas preferências
+fn scan_and_refresh(state: &Rc<RefCell<AppState>>, content: &Box) {
+    let folders = state.borrow().settings.library_folders.clone();
+    let previous_count = state.borrow().library.songs.len();
+    for folder in &folders {
+        let Some(dir) = folder.to_str() else {
+            continue;
+        };
+        if let Err(e) = scan_music_folder(state, dir) {
+            eprintln!("Failed to scan music directory: {}", e);
+            println!("Please make sure {} exists and contains music files.", folder.display());
+        }
+    }
+    if folders.is_empty() {
+        println!("No library folders configured; add one in Preferences");
+    }
+
+    // A lista de todas as músicas se atualiza sozinha pelo modelo
+    let added = state.borrow().library.songs.len() > previous_count;
+    if !added || (previous_count > 0 && state.borrow().current_view == LibraryView::AllSongs) {
+        return;
+    }
+    // Atualiza a lista de músicas de forma segura - clonando aqui dentro
+    let content_for_update = content.clone();
+    let state_for_update = Rc::clone(state);
+    let _ = glib::timeout_add_local(std::time::Duration::from_millis(100), move || {
+        update_song_list(&content_for_update, &state_for_update);
+        glib::ControlFlow::Break
+    });
 }
 
-fn create_sidebar(
+// Adiciona as músicas novas da pasta à biblioteca e ao modelo; devolve quantas entraram
+fn scan_music_folder(state: &Rc<RefCell<AppState>>, dir: &str) -> anyhow::Result<usize> {
+    let mut app_state = state.borrow_mut();
+    let previous_count = app_state.library.songs.len();
+    app_state.library.scan_directory(dir)?;
+    println!("Successfully scanned music library. Found {} songs.", app_state.library.songs.len());
+    for song in app_state.library.get_all_songs() {
+        println!("Found: {}", song.get_display_name());
+    }
+
+    // Só as músicas novas entram no modelo
+    let new_songs: Vec<SongObject> = app_state.library.songs[previous_count..]
+        .iter()
+        .cloned()
+        .map(SongObject::new)
+        .collect();
+    app_state.song_store.extend_from_slice(&new_songs);
+    app_state.save_library();
+    Ok(new_songs.len())
+}
+
+fn update_playlist_list(
+    playlists_box: &Box,
+    window: &ApplicationWindow,
     state: &Rc<RefCell<AppState>>,
+    content: &Box
+) {
+    while let Some(child) = playlists_box.first_child() {
+        playlists_box.remove(&child);
+    }
+
+    let app_state = state.borrow();
+    for (index, playlist) in app_state.library.playlists.iter().enumerate() {
+        let button = Button::with_label(&format!("🎶 {}", playlist.name));
+        button.set_halign(gtk4::Align::Start);
+        button.set_hexpand(false);
+
+        let state_clone = Rc::clone(state);
+        let window_clone = window.clone();
+        let content_clone = content.clone();
+        button.connect_clicked(move |_| {
+            show_playlist(&content_clone, &window_clone, &state_clone, index);
+        });
+
+        // Soltar arquivos na playlist importa e adiciona ao fim dela
+        let state_clone = Rc::clone(state);
+        let window_clone = window.clone();
+        let content_clone = content.clone();
+        button.add_controller(ui::file_drop::file_drop_target(move |paths| {
+            let state = Rc::clone(&state_clone);
+            let window = window_clone.clone();
+            let content = content_clone.clone();
+            read_songs(&state_clone, paths, move |songs, errors| {
+                for error in errors {
+                    eprintln!("{}", error);
+                }
+                import_songs(&state, &songs);
+                let showing = {
+                    let mut app_state = state.borrow_mut();
+                    let Some(playlist) = app_state.library.playlists.get_mut(index) else {
+                        return;
+                    };
+                    println!("Added {} songs to {}", songs.len(), playlist.name);
+                    playlist.songs.extend(songs.into_iter().map(|song| song.file_path));
+                    app_state.save_library();
+                    app_state.current_view == LibraryView::Playlist(index)
+                };
+                if showing {
+                    show_playlist(&content, &window, &state, index);
+                }
+            });
+        }));
+
+        playlists_box.append(&button);
+    }
+
+    for (index, playlist) in app_state.library.smart_playlists.iter().enumerate() {
+        let button = Button::with_label(&format!("✨ {}", playlist.name));
+        button.set_halign(gtk4::Align::Start);
+        button.set_hexpand(false);
+
+        let state_clone = Rc::clone(state);
+        let content_clone = content.clone();
+        button.connect_clicked(move |_| {
+            show_smart_playlist(&content_clone, &state_clone, index);
+        });
+
+        playlists_box.append(&button);
+    }
+}
+
+fn show_song_collection(content: &Box, state: &Rc<RefCell<AppState>>, name: &str, songs: &[Song]) -> Box {
+    while let Some(child) = content.first_child() {
+        content.remove(&child);
+    }
+
+    let header = Box::new(Orientation::Horizontal, 10);
+    let name_label = Label::new(Some(name));
+    name_label.add_css_class("song-list-header");
+    name_label.set_hexpand(true);
+    name_label.set_halign(gtk4::Align::Start);
+    header.append(&name_label);
+    content.append(&header);
+
+    if songs.is_empty() {
+        let empty_label = Label::new(Some("This playlist is empty"));
+        empty_label.add_css_class("title-4");
+        content.append(&empty_label);
+        return header;
+    }
+
+    let model = ui::song_list::song_store(songs);
+    let song_list = ui::song_list::create_song_list_view(state, &model, &[]);
+    content.append(&song_list);
+
+    header
+}
+
+fn show_playlist(content: &Box, window: &ApplicationWindow, state: &Rc<RefCell<AppState>>, index: usize) {
+    state.borrow_mut().current_view = LibraryView::Playlist(index);
+    let (name, songs) = {
+        let app_state = state.borrow();
+        match app_state.library.playlists.get(index) {
+            Some(playlist) => {
+                let songs: Vec<Song> = app_state.library
+                    .playlist_songs(playlist)
+                    .into_iter()
+                    .cloned()
+                    .collect();
+                (playlist.name.clone(), songs)
+            }
+            None => return,
+        }
+    };
+
+    let header = show_song_collection(content, state, &name, &songs);
+
+    let export_btn = Button::with_label("📤 Export");
+    let state_clone = Rc::clone(state);
+    let window_clone = window.clone();
+    export_btn.connect_clicked(move |_| {
+        show_export_playlist_dialog(&window_clone, &state_clone, index);
+    });
+    header.append(&export_btn);
+}
+
+fn smart_playlist_songs(app_state: &AppState, index: usize) -> Option<(String, Vec<Song>)> {
+    let playlist = app_state.library.smart_playlists.get(index)?;
+    let songs = app_state.library
+        .evaluate_smart_playlist(playlist)
+        .into_iter()
+        .cloned()
+        .collect();
+    Some((playlist.name.clone(), songs))
+}
+
+fn show_smart_playlist(content: &Box, state: &Rc<RefCell<AppState>>, index: usize) {
+    let (name, songs) = {
+        let mut app_state = state.borrow_mut();
+        app_state.current_view = LibraryView::SmartPlaylist(index);
+        let Some((name, songs)) = smart_playlist_songs(&app_state, index) else {
+            return;
+        };
+        let paths = songs.iter().map(|song| song.file_path.clone()).collect();
+        app_state.smart_view = (app_state.library.revision, library::unix_now(), paths);
+        (name, songs)
+    };
+
+    let header = show_song_collection(content, state, &format!("✨ {}", name), &songs);
+
+    let edit_btn = Button::with_label("✏ Edit");
+    edit_btn.set_action_name(Some("win.edit-smart-playlist"));
+    edit_btn.set_action_target_value(Some(&(index as u64).to_variant()));
+    header.append(&edit_btn);
+
+    let delete_btn = Button::with_label("🗑 Delete");
+    delete_btn.set_action_name(Some("win.delete-smart-playlist"));
+    delete_btn.set_action_target_value(Some(&(index as u64).to_variant()));
+    header.append(&delete_btn);
+}
+
+// Reavalia quando a biblioteca mudou ou, com regras de data, a cada minuto;
+// redesenha só se o resultado mudou
+fn refresh_smart_playlist(content: &Box, state: &Rc<RefCell<AppState>>) {
+    const DATE_RULES_INTERVAL: u64 = 60;
+    let index = {
+        let Ok(mut app_state) = state.try_borrow_mut() else {
+            return;
+        };
+        let LibraryView::SmartPlaylist(index) = app_state.current_view else {
+            return;
+        };
+        let revision = app_state.library.revision;
+        let now = library::unix_now();
+        let dated = app_state
+            .library
+            .smart_playlists
+            .get(index)
+            .is_some_and(|playlist| playlist.rules.uses_dates());
+        let expired = dated && now >= app_state.smart_view.1.saturating_add(DATE_RULES_INTERVAL);
+        if app_state.smart_view.0 == revision && !expired {
+            return;
+        }
+        let paths: Vec<PathBuf> = smart_playlist_songs(&app_state, index)
+            .map(|(_, songs)| songs.into_iter().map(|song| song.file_path).collect())
+            .unwrap_or_default();
+        if paths == app_state.smart_view.2 {
+            app_state.smart_view.0 = revision;
+            app_state.smart_view.1 = now;
+            return;
+        }
+        index
+    };
+    show_smart_playlist(content, state, index);
+}
+
+fn show_artist(content: &Box, state: &Rc<RefCell<AppState>>, artist: &str) {
+    state.borrow_mut().current_view = LibraryView::Artist(artist.to_string());
+    let songs = state.borrow().library.artists.get(artist).cloned().unwrap_or_default();
+    show_song_collection(content, state, &format!("🎤 {}", artist), &songs);
+}
+
+fn show_album(content: &Box, state: &Rc<RefCell<AppState>>, album: &str) {
+    state.borrow_mut().current_view = LibraryView::Album(album.to_string());
+    let mut songs = state.borrow().library.albums.get(album).cloned().unwrap_or_default();
+    songs.sort_by_key(|song| (song.disc_number, song.track_number));
+    show_song_collection(content, state, &format!("💿 {}", album), &songs);
+}
+
+fn playlist_file_filters() -> gio::ListStore {
+    let filter = gtk4::FileFilter::new();
+    filter.set_name(Some("Playlists"));
+    for format in PlaylistFormat::ALL {
+        filter.add_suffix(format.extension());
+    }
+
+    let filters = gio::ListStore::new::<gtk4::FileFilter>();
+    filters.append(&filter);
+    filters
+}
+
+fn show_import_playlist_dialog(
     window: &ApplicationWindow,
-    main_vertical: &Box,
-    content_horizontal: &Box,
+    state: &Rc<RefCell<AppState>>,
     content: &Box,
-    content_scroll: &ScrolledWindow
-) -> Box {
+    playlists_box: &Box
+) {
+    let chooser = FileDialog::builder()
+        .title("Import Playlist")
+        .modal(true)
+        .filters(&playlist_file_filters())
+        .build();
+
+    let state_clone = Rc::clone(state);
+    let window_clone = window.clone();
+    let content_clone = content.clone();
+    let playlists_box_clone = playlists_box.clone();
+
+    chooser.open(Some(window), gio::Cancellable::NONE, move |result| {
+        let path = match result {
+            Ok(file) => match file.path() {
+                Some(path) => path,
+                None => return,
+            },
+            Err(e) => {
+                eprintln!("Error selecting file: {}", e);
+                return;
+            }
+        };
+
+        let report = state_clone.borrow_mut().library.import_playlist(&path);
+        match report {
+            Ok(report) => {
+                update_playlist_list(&playlists_box_clone, &window_clone, &state_clone, &content_clone);
+                let index = state_clone.borrow().library.playlists.len() - 1;
+                show_playlist(&content_clone, &window_clone, &state_clone, index);
+
+                // Avisa sobre as entradas que não foram encontradas na biblioteca
+                if !report.unmatched.is_empty() {
+                    let alert = gtk4::AlertDialog::builder()
+                        .modal(true)
+                        .message(format!(
+                            "{} of {} entries could not be matched",
+                            report.unmatched.len(),
+                            report.unmatched.len() + report.playlist.songs.len()
+                        ))
+                        .detail(report.unmatched.join("\n"))
+                        .build();
+                    alert.show(Some(&window_clone));
+                }
+            }
+            Err(e) => {
+                eprintln!("Failed to import playlist: {}", e);
+            }
+        }
+    });
+}
+
+fn show_export_playlist_dialog(parent: &ApplicationWindow, state: &Rc<RefCell<AppState>>, index: usize) {
+    let dialog = Window::builder()
+        .title("Export Playlist")
+        .transient_for(parent)
+        .modal(true)
+        .default_width(350)
+        .build();
+
+    let content_area = Box::new(Orientation::Vertical, 10);
+    content_area.set_margin_top(15);
+    content_area.set_margin_bottom(15);
+    content_area.set_margin_start(15);
+    content_area.set_margin_end(15);
+
+    let labels: Vec<&str> = PlaylistFormat::ALL.iter().map(|f| f.label()).collect();
+    let format_dropdown = gtk4::DropDown::from_strings(&labels);
+
+    let relative_check = gtk4::CheckButton::with_label("Use paths relative to the playlist file");
+
+    let export_btn = Button::with_label("Export");
+    export_btn.add_css_class("suggested-action");
+
+    let state_clone = Rc::clone(state);
+    let dialog_clone = dialog.clone();
+    let parent_clone = parent.clone();
+    let format_dropdown_clone = format_dropdown.clone();
+    let relative_check_clone = relative_check.clone();
+
+    export_btn.connect_clicked(move |_| {
+        let format = PlaylistFormat::ALL[format_dropdown_clone.selected() as usize];
+        let style = if relative_check_clone.is_active() {
+            PathStyle::Relative
+        } else {
+            PathStyle::Absolute
+        };
+
+        let name = match state_clone.borrow().library.playlists.get(index) {
+            Some(playlist) => playlist.name.clone(),
+            None => return,
+        };
+
+        dialog_clone.close();
+
+        let chooser = FileDialog::builder()
+            .title("Export Playlist")
+            .modal(true)
+            .initial_name(format!("{}.{}", name, format.extension()))
+            .build();
+
+        let state_for_save = Rc::clone(&state_clone);
+        chooser.save(Some(&parent_clone), gio::Cancellable::NONE, move |result| {
+            let path = match result {
+                Ok(file) => match file.path() {
+                    Some(path) => path,
+                    None => return,
+                },
+                Err(e) => {
+                    eprintln!("Error selecting file: {}", e);
+                    return;
+                }
+            };
+
+            let app_state = state_for_save.borrow();
+            if let Some(playlist) = app_state.library.playlists.get(index) {
+                if let Err(e) = app_state.library.export_playlist(playlist, &path, format, style) {
+                    eprintln!("Failed to export playlist: {}", e);
+                }
+            }
+        });
+    });
+
+    let cancel_btn = Button::with_label("Cancel");
+    let dialog_clone2 = dialog.clone();
+    cancel_btn.connect_clicked(move |_| {
+        dialog_clone2.close();
+    });
+
+    content_area.append(&Label::new(Some("Format:")));
+    content_area.append(&format_dropdown);
+    content_area.append(&relative_check);
+
+    let button_box = Box::new(Orientation::Horizontal, 10);
+    button_box.set_halign(gtk4::Align::End);
+    button_box.append(&cancel_btn);
+    button_box.append(&export_btn);
+    content_area.append(&button_box);
+
+    dialog.set_child(Some(&content_area));
+    dialog.present();
+}
+
+fn create_sidebar(
+    state: &Rc<RefCell<AppState>>,
+    window: &ApplicationWindow,
+    content: &Box
+) -> (Box, Box) {
     let sidebar = Box::new(Orientation::Vertical, 10);
     sidebar.add_css_class("sidebar");
     sidebar.set_margin_top(10);
@@ -478,100 +1426,221 @@ fn create_sidebar(
     new_playlist_btn.set_hexpand(false);
     sidebar.append(&new_playlist_btn);
     
+    let import_playlist_btn = Button::with_label("📥 Import Playlist");
+    import_playlist_btn.set_halign(gtk4::Align::Start);
+    import_playlist_btn.set_hexpand(false);
+    sidebar.append(&import_playlist_btn);
+    
+    let smart_playlist_btn = Button::with_label("✨ New Smart Playlist");
+    smart_playlist_btn.set_halign(gtk4::Align::Start);
+    smart_playlist_btn.set_hexpand(false);
+    sidebar.append(&smart_playlist_btn);
+    
+    // Lista das playlists existentes
+    let playlists_box = Box::new(Orientation::Vertical, 5);
+    update_playlist_list(&playlists_box, window, state, content);
+    sidebar.append(&playlists_box);
+    
+    let state_clone = Rc::clone(state);
+    let window_clone = window.clone();
+    let content_clone = content.clone();
+    let playlists_box_clone = playlists_box.clone();
+    import_playlist_btn.connect_clicked(move |_| {
+        show_import_playlist_dialog(&window_clone, &state_clone, &content_clone, &playlists_box_clone);
+    });
+    
+    let state_clone = Rc::clone(state);
+    let window_clone = window.clone();
+    let content_clone = content.clone();
+    let playlists_box_clone = playlists_box.clone();
+    smart_playlist_btn.connect_clicked(move |_| {
+        let state_for_update = Rc::clone(&state_clone);
+        let window_for_update = window_clone.clone();
+        let content_for_update = content_clone.clone();
+        let playlists_box_for_update = playlists_box_clone.clone();
+        ui::smart_playlist::show_smart_playlist_dialog(&window_clone, &state_clone, None, move |index| {
+            update_playlist_list(&playlists_box_for_update, &window_for_update, &state_for_update, &content_for_update);
+            show_smart_playlist(&content_for_update, &state_for_update, index);
+        });
+    });
+
+    // Editar e apagar, pelos botões da tela da playlist inteligente
+    let edit_action = gio::SimpleAction::new("edit-smart-playlist", Some(glib::VariantTy::UINT64));
+    let state_clone = Rc::clone(state);
+    let window_clone = window.clone();
+    let content_clone = content.clone();
+    let playlists_box_clone = playlists_box.clone();
+    edit_action.connect_activate(move |_, parameter| {
+        let Some(index) = parameter.and_then(|p| p.get::<u64>()) else {
+            return;
+        };
+        let state_for_update = Rc::clone(&state_clone);
+        let window_for_update = window_clone.clone();
+        let content_for_update = content_clone.clone();
+        let playlists_box_for_update = playlists_box_clone.clone();
+        ui::smart_playlist::show_smart_playlist_dialog(&window_clone, &state_clone, Some(index as usize), move |index| {
+            update_playlist_list(&playlists_box_for_update, &window_for_update, &state_for_update, &content_for_update);
+            show_smart_playlist(&content_for_update, &state_for_update, index);
+        });
+    });
+    window.add_action(&edit_action);
+
+    let delete_action = gio::SimpleAction::new("delete-smart-playlist", Some(glib::VariantTy::UINT64));
+    let state_clone = Rc::clone(state);
+    let window_clone = window.clone();
+    let content_clone = content.clone();
+    let playlists_box_clone = playlists_box.clone();
+    delete_action.connect_activate(move |_, parameter| {
+        let Some(index) = parameter.and_then(|p| p.get::<u64>()).map(|index| index as usize) else {
+            return;
+        };
+        let Some(name) = state_clone.borrow().library.smart_playlists.get(index).map(|p| p.name.clone()) else {
+            return;
+        };
+        let alert = gtk4::AlertDialog::builder()
+            .modal(true)
+            .message(format!("Delete the smart playlist \"{}\"?", name))
+            .detail("The songs stay in the library.")
+            .buttons(["Cancel", "Delete"])
+            .cancel_button(0)
+            .default_button(0)
+            .build();
+        let state_clone = Rc::clone(&state_clone);
+        let window_for_update = window_clone.clone();
+        let content_clone = content_clone.clone();
+        let playlists_box_clone = playlists_box_clone.clone();
+        alert.choose(Some(&window_clone), gio::Cancellable::NONE, move |result| {
+            if result != Ok(1) {
+                return;
+            }
+            let showing = {
+                let mut app_state = state_clone.borrow_mut();
+                if index >= app_state.library.smart_playlists.len() {
+                    return;
+                }
+                app_state.library.smart_playlists.remove(index);
+                app_state.save_library();
+                println!("Deleted smart playlist {}", name);
+                matches!(app_state.current_view, LibraryView::SmartPlaylist(_))
+            };
+            update_playlist_list(&playlists_box_clone, &window_for_update, &state_clone, &content_clone);
+            // Os índices das seguintes mudaram; volta para todas as músicas
+            if showing {
+                update_song_list(&content_clone, &state_clone);
+            }
+        });
+    });
+    window.add_action(&delete_action);
+    
     // Scan music button
-    let scan_music_btn = Button::with_label("🔍 Scan Music Folder");
+    let scan_music_btn = Button::with_label("🔍 Scan Library Folders");
     scan_music_btn.set_halign(gtk4::Align::Start);
     
     let state_clone = Rc::clone(state);
     let content_clone = content.clone();
     scan_music_btn.connect_clicked(move |_| {
-        let mut app_state = state_clone.borrow_mut();
-        
-        let home_dir = std::env::var("HOME").unwrap_or_else(|_| ".".to_string());
-        let music_dir = format!("{}/Music", home_dir);
-        
-        match app_state.library.scan_directory(&music_dir) {
-            Ok(_) => {
-                println!("Successfully scanned music library. Found {} songs.", app_state.library.songs.len());
-                for song in app_state.library.get_all_songs() {
-                    println!("Found: {}", song.get_display_name());
-                }
-                // Atualiza a lista de músicas de forma segura - clonando aqui dentro
-                let content_for_update = content_clone.clone();
-                let state_for_update = Rc::clone(&state_clone);
-                let _ = glib::timeout_add_local(std::time::Duration::from_millis(100), move || {
-                    update_song_list(&content_for_update, &state_for_update);
-                    glib::ControlFlow::Break
-                });
-            }
-            Err(e) => {
-                eprintln!("Failed to scan music directory: {}", e);
-                println!("Please make sure ~/Music directory exists and contains music files.");
-            }
-        }
+        scan_and_refresh(&state_clone, &content_clone);
     });
     
     sidebar.append(&scan_music_btn);
     
-    // Theme toggle
-    let theme_toggle = Button::with_label("🌙 Dark Mode");
-    theme_toggle.set_halign(gtk4::Align::Start);
+    // Organizar arquivos no disco a partir das tags
+    let organize_btn = Button::with_label("🗂 Organize Files");
+    organize_btn.set_halign(gtk4::Align::Start);
+    
+    let state_clone = Rc::clone(state);
+    let window_clone = window.clone();
+    let content_clone = content.clone();
+    organize_btn.connect_clicked(move |_| {
+        let state_for_update = Rc::clone(&state_clone);
+        let window_for_update = window_clone.clone();
+        let content_for_update = content_clone.clone();
+        ui::organizer::show_organizer_dialog(&window_clone, &state_clone, move || {
+            refresh_view(&content_for_update, &window_for_update, &state_for_update);
+        });
+    });
+    
+    sidebar.append(&organize_btn);
+    
+    let duplicates_btn = Button::with_label("👥 Find Duplicates");
+    duplicates_btn.set_halign(gtk4::Align::Start);
     
     let state_clone = Rc::clone(state);
     let window_clone = window.clone();
-    let main_vertical_clone = main_vertical.clone();
-    let content_horizontal_clone = content_horizontal.clone();
-    let sidebar_clone = sidebar.clone();
-    let content_scroll_clone = content_scroll.clone();
+    let content_clone = content.clone();
+    duplicates_btn.connect_clicked(move |_| {
+        let state_for_update = Rc::clone(&state_clone);
+        let window_for_update = window_clone.clone();
+        let content_for_update = content_clone.clone();
+        ui::duplicates::show_duplicates_dialog(&window_clone, &state_clone, move || {
+            refresh_view(&content_for_update, &window_for_update, &state_for_update);
+        });
+    });
     
-    theme_toggle.connect_clicked(move |btn| {
-        let mut app_state = state_clone.borrow_mut();
-        app_state.is_dark_theme = !app_state.is_dark_theme;
-        
-        if app_state.is_dark_theme {
-            btn.set_label("☀️ Light Mode");
-            
-            window_clone.remove_css_class("light-theme");
-            window_clone.add_css_class("dark-theme");
-            
-            main_vertical_clone.remove_css_class("light-theme");
-            main_vertical_clone.add_css_class("dark-theme");
-            
-            content_horizontal_clone.remove_css_class("light-theme");
-            content_horizontal_clone.add_css_class("dark-theme");
-            
-            sidebar_clone.remove_css_class("light-theme");
-            sidebar_clone.add_css_class("dark-theme");
-            
-            content_scroll_clone.remove_css_class("light-theme");
-            content_scroll_clone.add_css_class("dark-theme");
-            
-            println!("🌙 Dark theme activated");
-        } else {
-            btn.set_label("🌙 Dark Mode");
-            
-            window_clone.remove_css_class("dark-theme");
-            window_clone.add_css_class("light-theme");
-            
-            main_vertical_clone.remove_css_class("dark-theme");
-            main_vertical_clone.add_css_class("light-theme");
-            
-            content_horizontal_clone.remove_css_class("dark-theme");
-            content_horizontal_clone.add_css_class("light-theme");
-            
-            sidebar_clone.remove_css_class("dark-theme");
-            sidebar_clone.add_css_class("light-theme");
-            
-            content_scroll_clone.remove_css_class("dark-theme");
-            content_scroll_clone.add_css_class("light-theme");
-            
+    sidebar.append(&duplicates_btn);
+    
+    let stats_btn = Button::with_label("📊 Statistics");
+    stats_btn.set_halign(gtk4::Align::Start);
+    
+    let state_clone = Rc::clone(state);
+    let window_clone = window.clone();
+    stats_btn.connect_clicked(move |_| {
+        ui::stats::show_stats_window(&window_clone, &state_clone);
+    });
+    
+    sidebar.append(&stats_btn);
+    
+    let equalizer_btn = Button::with_label("🎚 Equalizer");
+    equalizer_btn.set_halign(gtk4::Align::Start);
+    
+    let state_clone = Rc::clone(state);
+    let window_clone = window.clone();
+    equalizer_btn.connect_clicked(move |_| {
+        ui::equalizer::show_equalizer_window(&window_clone, &state_clone);
+    });
+    
+    sidebar.append(&equalizer_btn);
+    
+    let preferences_btn = Button::with_label("⚙ Preferences");
+    preferences_btn.set_halign(gtk4::Align::Start);
+    
+    let state_clone = Rc::clone(state);
+    let window_clone = window.clone();
+    let content_clone = content.clone();
+    preferences_btn.connect_clicked(move |_| {
+        ui::preferences::show_preferences_window(&window_clone, &state_clone, &content_clone);
+    });
+    
+    sidebar.append(&preferences_btn);
+    
+    // Alterna entre claro e escuro; "Follow System" fica nas preferências
+    let style_manager = adw::StyleManager::default();
+    let theme_toggle = Button::with_label(theme_toggle_label(style_manager.is_dark()));
+    theme_toggle.set_halign(gtk4::Align::Start);
+    
+    let state_clone = Rc::clone(state);
+    theme_toggle.connect_clicked(move |_| {
+        if adw::StyleManager::default().is_dark() {
+            set_color_scheme(&state_clone, ColorScheme::Light);
             println!("☀️ Light theme activated");
+        } else {
+            set_color_scheme(&state_clone, ColorScheme::Dark);
+            println!("🌙 Dark theme activated");
         }
     });
     
+    let theme_toggle_clone = theme_toggle.clone();
+    style_manager.connect_dark_notify(move |style_manager| {
+        theme_toggle_clone.set_label(theme_toggle_label(style_manager.is_dark()));
+    });
+    
     sidebar.append(&theme_toggle);
     
-    sidebar
+    (sidebar, playlists_box)
+}
+
+fn theme_toggle_label(dark: bool) -> &'static str {
+    if dark { "☀️ Light Mode" } else { "🌙 Dark Mode" }
 }
 
 fn create_content_area(state: &Rc<RefCell<AppState>>) -> (ScrolledWindow, Box) {
@@ -582,17 +1651,20 @@ fn create_content_area(state: &Rc<RefCell<AppState>>) -> (ScrolledWindow, Box) {
     content.set_margin_start(20);
     content.set_margin_end(20);
     
-    // Adiciona a classe do tema ao content box também
-    content.add_css_class("light-theme");
-    
     // Inicializa com a lista de músicas (ou mensagem de boas-vindas)
     update_song_list(&content, state);
     
+    // A tabela de músicas tem a própria rolagem; rolar aqui também daria duas barras
+    scroll.set_vscrollbar_policy(gtk4::PolicyType::Never);
     scroll.set_child(Some(&content));
     (scroll, content)
 }
 
-fn create_player_controls(state: &Rc<RefCell<AppState>>) -> Box {
+fn create_player_controls(
+    state: &Rc<RefCell<AppState>>,
+    window: &ApplicationWindow,
+    lyrics_panel: &gtk4::Revealer
+) -> Box {
     let controls = Box::new(Orientation::Horizontal, 15);
     controls.add_css_class("player-controls");
     controls.set_margin_top(15);
@@ -627,41 +1699,43 @@ fn create_player_controls(state: &Rc<RefCell<AppState>>) -> Box {
     let prev_btn = Button::with_label("⏮");
     let state_prev = Rc::clone(state);
     prev_btn.connect_clicked(move |_| {
-        let _app_state = state_prev.borrow();
-        println!("Previous track");
+        if !play_previous_track(&state_prev) {
+            println!("No previous track");
+        }
     });
     
     // Play/Pause button
     let play_btn = Button::with_label("⏯");
     play_btn.add_css_class("suggested-action");
     let state_play = Rc::clone(state);
-    play_btn.connect_clicked(move |btn| {
-        let mut app_state = state_play.borrow_mut();
-        if let Some(ref mut player) = app_state.player {
-            if player.is_playing() {
-                player.pause();
-                btn.set_label("⏯");
-                println!("Paused");
-            } else {
-                match player.play() {
-                    Ok(_) => {
-                        btn.set_label("⏸");
-                        println!("Playing");
-                    }
-                    Err(e) => {
-                        eprintln!("Failed to play: {}", e);
-                    }
-                }
-            }
+    play_btn.connect_clicked(move |_| {
+        toggle_playback(&state_play);
+    });
+    
+    // O estado também muda pelo MPRIS e no fim da fila
+    let play_btn_weak = play_btn.downgrade();
+    let state_play = Rc::clone(state);
+    glib::timeout_add_local(std::time::Duration::from_millis(250), move || {
+        let Some(btn) = play_btn_weak.upgrade() else {
+            return glib::ControlFlow::Break;
+        };
+        let Ok(app_state) = state_play.try_borrow() else {
+            return glib::ControlFlow::Continue;
+        };
+        let label = if app_state.player.as_ref().is_some_and(|p| p.is_playing()) { "⏸" } else { "⏯" };
+        if btn.label().as_deref() != Some(label) {
+            btn.set_label(label);
         }
+        glib::ControlFlow::Continue
     });
     
     // Next button
     let next_btn = Button::with_label("⏭");
     let state_next = Rc::clone(state);
     next_btn.connect_clicked(move |_| {
-        let _app_state = state_next.borrow();
-        println!("Next track");
+        if !play_next_track(&state_next) {
+            println!("Queue is empty");
+        }
     });
     
     control_buttons.append(&prev_btn);
@@ -710,5 +1784,26 @@ fn create_player_controls(state: &Rc<RefCell<AppState>>) -> Box {
     
     controls.append(&volume_btn);
     
+    // Lyrics toggle
+    let lyrics_btn = gtk4::ToggleButton::with_label("📝");
+    lyrics_btn.set_tooltip_text(Some("Lyrics"));
+    let lyrics_panel_clone = lyrics_panel.clone();
+    lyrics_btn.connect_toggled(move |btn| {
+        lyrics_panel_clone.set_reveal_child(btn.is_active());
+    });
+    
+    controls.append(&lyrics_btn);
+    
+    // Mini player
+    let mini_btn = Button::with_label("🗗");
+    mini_btn.set_tooltip_text(Some("Mini Player"));
+    let state_clone = Rc::clone(state);
+    let window_clone = window.clone();
+    mini_btn.connect_clicked(move |_| {
+        ui::mini_player::show_mini_player(&window_clone, &state_clone);
+    });
+    
+    controls.append(&mini_btn);
+    
     controls
 }
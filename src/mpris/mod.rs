@@ -0,0 +1,602 @@
+mod playlists;
+mod tracklist;
+
+use gio::prelude::*;
+use glib::variant::ObjectPath;
+use glib::{ToVariant, Variant, VariantDict};
+use gtk4::prelude::*;
+use gtk4::ApplicationWindow;
+use std::cell::RefCell;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use crate::AppState;
+use crate::library::Song;
+use crate::player::queue::RepeatMode;
+
+const BUS_NAME: &str = "org.mpris.MediaPlayer2.librefy";
+const OBJECT_PATH: &str = "/org/mpris/MediaPlayer2";
+const ROOT_IFACE: &str = "org.mpris.MediaPlayer2";
+const PLAYER_IFACE: &str = "org.mpris.MediaPlayer2.Player";
+const TRACKLIST_IFACE: &str = "org.mpris.MediaPlayer2.TrackList";
+const PLAYLISTS_IFACE: &str = "org.mpris.MediaPlayer2.Playlists";
+const NO_TRACK: &str = "/org/mpris/MediaPlayer2/TrackList/NoTrack";
+const TICK: Duration = Duration::from_millis(500);
+
+// Propriedades do Player acompanhadas para o PropertiesChanged
+const WATCHED: [&str; 10] = [
+    "PlaybackStatus", "LoopStatus", "Shuffle", "Metadata", "Volume",
+    "CanGoNext", "CanGoPrevious", "CanPlay", "CanPause", "CanSeek",
+];
+
+const MIME_TYPES: [&str; 7] = [
+    "audio/mpeg", "audio/flac", "audio/x-flac", "audio/ogg", "audio/x-wav", "audio/mp4", "audio/aac",
+];
+
+const INTROSPECTION: &str = r#"
+<node>
+  <interface name="org.mpris.MediaPlayer2">
+    <method name="Raise"/>
+    <method name="Quit"/>
+    <property name="CanQuit" type="b" access="read"/>
+    <property name="CanRaise" type="b" access="read"/>
+    <property name="HasTrackList" type="b" access="read"/>
+    <property name="Identity" type="s" access="read"/>
+    <property name="DesktopEntry" type="s" access="read"/>
+    <property name="SupportedUriSchemes" type="as" access="read"/>
+    <property name="SupportedMimeTypes" type="as" access="read"/>
+  </interface>
+  <interface name="org.mpris.MediaPlayer2.Player">
+    <method name="Next"/>
+    <method name="Previous"/>
+    <method name="Pause"/>
+    <method name="PlayPause"/>
+    <method name="Stop"/>
+    <method name="Play"/>
+    <method name="Seek">
+      <arg name="Offset" type="x" direction="in"/>
+    </method>
+    <method name="SetPosition">
+      <arg name="TrackId" type="o" direction="in"/>
+      <arg name="Position" type="x" direction="in"/>
+    </method>
+    <method name="OpenUri">
+      <arg name="Uri" type="s" direction="in"/>
+    </method>
+    <signal name="Seeked">
+      <arg name="Position" type="x"/>
+    </signal>
+    <property name="PlaybackStatus" type="s" access="read"/>
+    <property name="LoopStatus" type="s" access="readwrite"/>
+    <property name="Rate" type="d" access="readwrite"/>
+    <property name="Shuffle" type="b" access="readwrite"/>
+    <property name="Metadata" type="a{sv}" access="read"/>
+    <property name="Volume" type="d" access="readwrite"/>
+    <property name="Position" type="x" access="read"/>
+    <property name="MinimumRate" type="d" access="read"/>
+    <property name="MaximumRate" type="d" access="read"/>
+    <property name="CanGoNext" type="b" access="read"/>
+    <property name="CanGoPrevious" type="b" access="read"/>
+    <property name="CanPlay" type="b" access="read"/>
+    <property name="CanPause" type="b" access="read"/>
+    <property name="CanSeek" type="b" access="read"/>
+    <property name="CanControl" type="b" access="read"/>
+  </interface>
+  <interface name="org.mpris.MediaPlayer2.TrackList">
+    <method name="GetTracksMetadata">
+      <arg name="TrackIds" type="ao" direction="in"/>
+      <arg name="Metadata" type="aa{sv}" direction="out"/>
+    </method>
+    <method name="AddTrack">
+      <arg name="Uri" type="s" direction="in"/>
+      <arg name="AfterTrack" type="o" direction="in"/>
+      <arg name="SetAsCurrent" type="b" direction="in"/>
+    </method>
+    <method name="RemoveTrack">
+      <arg name="TrackId" type="o" direction="in"/>
+    </method>
+    <method name="GoTo">
+      <arg name="TrackId" type="o" direction="in"/>
+    </method>
+    <signal name="TrackListReplaced">
+      <arg name="Tracks" type="ao"/>
+      <arg name="CurrentTrack" type="o"/>
+    </signal>
+    <property name="Tracks" type="ao" access="read"/>
+    <property name="CanEditTracks" type="b" access="read"/>
+  </interface>
+  <interface name="org.mpris.MediaPlayer2.Playlists">
+    <method name="ActivatePlaylist">
+      <arg name="PlaylistId" type="o" direction="in"/>
+    </method>
+    <method name="GetPlaylists">
+      <arg name="Index" type="u" direction="in"/>
+      <arg name="MaxCount" type="u" direction="in"/>
+      <arg name="Order" type="s" direction="in"/>
+      <arg name="ReverseOrder" type="b" direction="in"/>
+      <arg name="Playlists" type="a(oss)" direction="out"/>
+    </method>
+    <property name="PlaylistCount" type="u" access="read"/>
+    <property name="Orderings" type="as" access="read"/>
+    <property name="ActivePlaylist" type="(b(oss))" access="read"/>
+  </interface>
+</node>
+"#;
+
+type CallResult = Result<Option<Variant>, String>;
+
+// Últimos valores enviados, para avisar só do que mudou
+#[derive(Default)]
+struct Snapshot {
+    player: Vec<Variant>,
+    // Revisão da fila e música atual da última lista enviada
+    tracks: Option<(u64, Option<PathBuf>)>,
+    playlist_count: u32,
+    // Faixa, posição e quando foi lida, para perceber saltos
+    position: Option<(ObjectPath, Duration, bool, Instant)>,
+}
+
+struct Mpris {
+    state: Rc<RefCell<AppState>>,
+    window: ApplicationWindow,
+    connection: RefCell<Option<gio::DBusConnection>>,
+    last: RefCell<Snapshot>,
+    active_playlist: RefCell<Option<ObjectPath>>,
+}
+
+thread_local! {
+    // As chamadas do D-Bus chegam na thread principal, onde vive o estado da interface
+    static MPRIS: RefCell<Option<Rc<Mpris>>> = const { RefCell::new(None) };
+}
+
+fn with_mpris<R>(f: impl FnOnce(&Mpris) -> R) -> Option<R> {
+    let mpris = MPRIS.with(|mpris| mpris.borrow().clone())?;
+    Some(f(&mpris))
+}
+
+fn object_path(path: String) -> ObjectPath {
+    ObjectPath::try_from(path).expect("Invalid D-Bus object path")
+}
+
+fn no_track() -> ObjectPath {
+    object_path(NO_TRACK.to_string())
+}
+
+// Id estável enquanto o arquivo não muda de lugar; a mesma música repetida
+// na lista ganha o número da repetição
+fn track_id(song: &Song, repeat: usize) -> ObjectPath {
+    let mut hasher = DefaultHasher::new();
+    song.file_path.hash(&mut hasher);
+    match repeat {
+        0 => object_path(format!("/org/librefy/Librefy/Track/{:016x}", hasher.finish())),
+        _ => object_path(format!("/org/librefy/Librefy/Track/{:016x}_{}", hasher.finish(), repeat)),
+    }
+}
+
+fn micros(duration: Duration) -> i64 {
+    i64::try_from(duration.as_micros()).unwrap_or(i64::MAX)
+}
+
+// Valor vazio do tipo de cada propriedade, para quando o estado está ocupado
+fn empty_property(property: &str) -> Variant {
+    match property {
+        "Identity" | "DesktopEntry" => "".to_variant(),
+        "PlaybackStatus" => "Stopped".to_variant(),
+        "LoopStatus" => "None".to_variant(),
+        "SupportedUriSchemes" | "SupportedMimeTypes" | "Orderings" => Vec::<String>::new().to_variant(),
+        "Metadata" => metadata(None),
+        "Volume" => 0.0f64.to_variant(),
+        "Rate" | "MinimumRate" | "MaximumRate" => 1.0f64.to_variant(),
+        "Position" => 0i64.to_variant(),
+        "Tracks" => Vec::<ObjectPath>::new().to_variant(),
+        "PlaylistCount" => 0u32.to_variant(),
+        "ActivePlaylist" => (false, (object_path("/".to_string()), String::new(), String::new())).to_variant(),
+        _ => false.to_variant(),
+    }
+}
+
+fn metadata(song: Option<&Song>) -> Variant {
+    match song {
+        Some(song) => track_metadata(&track_id(song, 0), song),
+        None => {
+            let dict = VariantDict::new(None);
+            dict.insert_value("mpris:trackid", &no_track().to_variant());
+            dict.end()
+        }
+    }
+}
+
+fn track_metadata(id: &ObjectPath, song: &Song) -> Variant {
+    let dict = VariantDict::new(None);
+    dict.insert_value("mpris:trackid", &id.to_variant());
+    if let Some(duration) = song.duration {
+        dict.insert_value("mpris:length", &micros(Duration::from_secs(duration)).to_variant());
+    }
+    let art_url = song
+        .get_cover_path()
+        .filter(|path| path.exists())
+        .and_then(|path| glib::filename_to_uri(path, None).ok());
+    if let Some(uri) = art_url {
+        dict.insert_value("mpris:artUrl", &uri.as_str().to_variant());
+    }
+    if let Ok(uri) = glib::filename_to_uri(&song.file_path, None) {
+        dict.insert_value("xesam:url", &uri.as_str().to_variant());
+    }
+    dict.insert_value("xesam:title", &song.title.to_variant());
+    dict.insert_value("xesam:artist", &vec![song.artist.clone()].to_variant());
+    dict.insert_value("xesam:album", &song.album.to_variant());
+    if let Some(ref album_artist) = song.album_artist {
+        dict.insert_value("xesam:albumArtist", &vec![album_artist.clone()].to_variant());
+    }
+    if let Some(ref genre) = song.genre {
+        dict.insert_value("xesam:genre", &vec![genre.clone()].to_variant());
+    }
+    if let Some(track) = song.track_number {
+        dict.insert_value("xesam:trackNumber", &(track as i32).to_variant());
+    }
+    if let Some(disc) = song.disc_number {
+        dict.insert_value("xesam:discNumber", &(disc as i32).to_variant());
+    }
+    dict.insert_value("xesam:useCount", &(song.play_count as i32).to_variant());
+    dict.insert_value("xesam:userRating", &(f64::from(song.rating) / 5.0).to_variant());
+    dict.end()
+}
+
+fn loop_status(repeat: RepeatMode) -> &'static str {
+    match repeat {
+        RepeatMode::None => "None",
+        RepeatMode::Track => "Track",
+        RepeatMode::Playlist => "Playlist",
+    }
+}
+
+// Caminho local de uma URI file:// (ou de um caminho comum)
+fn uri_to_path(uri: &str) -> Option<PathBuf> {
+    if uri.contains("://") {
+        gio::File::for_uri(uri).path()
+    } else {
+        Some(PathBuf::from(uri))
+    }
+}
+
+// Publica a interface MPRIS no barramento de sessão (DBUS_SESSION_BUS_ADDRESS)
+pub fn start(window: &ApplicationWindow, state: &Rc<RefCell<AppState>>) {
+    let mpris = Rc::new(Mpris {
+        state: Rc::clone(state),
+        window: window.clone(),
+        connection: RefCell::new(None),
+        last: RefCell::new(Snapshot::default()),
+        active_playlist: RefCell::new(None),
+    });
+    MPRIS.with(|cell| *cell.borrow_mut() = Some(mpris));
+
+    gio::bus_own_name(
+        gio::BusType::Session,
+        BUS_NAME,
+        gio::BusNameOwnerFlags::NONE,
+        |connection, _| register(&connection),
+        |_, name| println!("MPRIS: acquired {}", name),
+        |_, name| eprintln!("MPRIS: could not own {}", name),
+    );
+
+    glib::timeout_add_local(TICK, || {
+        with_mpris(|mpris| mpris.emit_changes());
+        glib::ControlFlow::Continue
+    });
+}
+
+fn register(connection: &gio::DBusConnection) {
+    let node = match gio::DBusNodeInfo::for_xml(INTROSPECTION) {
+        Ok(node) => node,
+        Err(e) => {
+            eprintln!("Invalid MPRIS introspection data: {}", e);
+            return;
+        }
+    };
+
+    for name in [ROOT_IFACE, PLAYER_IFACE, TRACKLIST_IFACE, PLAYLISTS_IFACE] {
+        let Some(info) = node.lookup_interface(name) else {
+            continue;
+        };
+        let result = connection.register_object(
+            OBJECT_PATH,
+            &info,
+            |_, _, _, interface, method, params, invocation| {
+                with_mpris(|mpris| mpris.call(interface, method, &params, invocation));
+            },
+            |_, _, _, interface, property| {
+                with_mpris(|mpris| mpris.get(interface, property))
+                    .flatten()
+                    .unwrap_or_else(|| empty_property(property))
+            },
+            |_, _, _, interface, property, value| {
+                with_mpris(|mpris| mpris.set(interface, property, &value)).unwrap_or(false)
+            },
+        );
+        if let Err(e) = result {
+            eprintln!("Failed to export {}: {}", name, e);
+        }
+    }
+
+    with_mpris(|mpris| *mpris.connection.borrow_mut() = Some(connection.clone()));
+}
+
+impl Mpris {
+    fn call(&self, interface: &str, method: &str, params: &Variant, invocation: gio::DBusMethodInvocation) {
+        let result = match interface {
+            ROOT_IFACE => self.call_root(method),
+            PLAYER_IFACE => self.call_player(method, params),
+            TRACKLIST_IFACE => self.call_tracklist(method, params),
+            PLAYLISTS_IFACE => self.call_playlists(method, params),
+            _ => Err(format!("Unknown interface {}", interface)),
+        };
+        match result {
+            Ok(value) => invocation.return_value(value.as_ref()),
+            Err(message) => invocation.return_dbus_error("org.freedesktop.DBus.Error.Failed", &message),
+        }
+    }
+
+    fn get(&self, interface: &str, property: &str) -> Option<Variant> {
+        match interface {
+            ROOT_IFACE => self.root_property(property),
+            PLAYER_IFACE => self.player_property(property),
+            TRACKLIST_IFACE => self.tracklist_property(property),
+            PLAYLISTS_IFACE => self.playlists_property(property),
+            _ => None,
+        }
+    }
+
+    fn set(&self, interface: &str, property: &str, value: &Variant) -> bool {
+        if interface != PLAYER_IFACE {
+            return false;
+        }
+        let mut app_state = self.state.borrow_mut();
+        match property {
+            "Volume" => match (value.get::<f64>(), &app_state.player) {
+                (Some(volume), Some(player)) => {
+                    player.set_volume(volume);
+                    true
+                }
+                _ => false,
+            },
+            "Shuffle" => match value.get::<bool>() {
+                Some(shuffle) => {
+                    app_state.queue.shuffle = shuffle;
+                    true
+                }
+                None => false,
+            },
+            "LoopStatus" => {
+                let repeat = match value.str() {
+                    Some("None") => RepeatMode::None,
+                    Some("Track") => RepeatMode::Track,
+                    Some("Playlist") => RepeatMode::Playlist,
+                    _ => return false,
+                };
+                app_state.queue.repeat = repeat;
+                true
+            }
+            // Só tocamos em velocidade normal
+            "Rate" => value.get::<f64>() == Some(1.0),
+            _ => false,
+        }
+    }
+
+    fn call_root(&self, method: &str) -> CallResult {
+        match method {
+            "Raise" => self.window.present(),
+            // Fechar a janela passa pelo mesmo caminho que salva tudo
+            "Quit" => self.window.close(),
+            _ => return Err(format!("Unknown method {}", method)),
+        }
+        Ok(None)
+    }
+
+    fn root_property(&self, property: &str) -> Option<Variant> {
+        let value = match property {
+            "CanQuit" | "CanRaise" | "HasTrackList" => true.to_variant(),
+            "Identity" => "Librefy".to_variant(),
+            "DesktopEntry" => crate::APP_ID.to_variant(),
+            "SupportedUriSchemes" => vec!["file"].to_variant(),
+            "SupportedMimeTypes" => MIME_TYPES.to_vec().to_variant(),
+            _ => return None,
+        };
+        Some(value)
+    }
+
+    fn call_player(&self, method: &str, params: &Variant) -> CallResult {
+        let state = &self.state;
+        match method {
+            "Next" => {
+                crate::play_next_track(state);
+            }
+            "Previous" => {
+                crate::play_previous_track(state);
+            }
+            "Pause" => crate::pause_playback(state),
+            "PlayPause" => crate::toggle_playback(state),
+            "Stop" => crate::stop_playback(state),
+            "Play" => {
+                let playing = state.borrow().player.as_ref().is_some_and(|p| p.is_playing());
+                if !playing {
+                    crate::resume_playback(state);
+                }
+            }
+            "Seek" => {
+                let (offset,) = params.get::<(i64,)>().ok_or("Invalid arguments")?;
+                let (position, length) = {
+                    let app_state = state.borrow();
+                    let position = app_state.player.as_ref().and_then(|p| p.position());
+                    let length = app_state.current_song.as_ref().and_then(|s| s.duration);
+                    (position, length)
+                };
+                let Some(position) = position else {
+                    return Ok(None);
+                };
+                let target = micros(position).saturating_add(offset).max(0);
+                // Passar do fim vai para a próxima faixa
+                if length.is_some_and(|length| target > micros(Duration::from_secs(length))) {
+                    crate::play_next_track(state);
+                } else {
+                    self.seek(Duration::from_micros(target as u64))?;
+                }
+            }
+            "SetPosition" => {
+                let (track, position) = params.get::<(ObjectPath, i64)>().ok_or("Invalid arguments")?;
+                let song = state.borrow().current_song.clone();
+                // Pedidos para uma faixa que já não é a atual são ignorados
+                let Some(song) = song.filter(|song| track_id(song, 0) == track) else {
+                    return Ok(None);
+                };
+                let length = song.duration.map(|d| micros(Duration::from_secs(d)));
+                if position < 0 || length.is_some_and(|length| position > length) {
+                    return Ok(None);
+                }
+                self.seek(Duration::from_micros(position as u64))?;
+            }
+            "OpenUri" => {
+                let (uri,) = params.get::<(String,)>().ok_or("Invalid arguments")?;
+                let path = uri_to_path(&uri).ok_or_else(|| format!("Unsupported URI: {}", uri))?;
+                let song = state.borrow().library.song_for_file(&path).map_err(|e| e.to_string())?;
+                crate::play_song(state, &song);
+            }
+            _ => return Err(format!("Unknown method {}", method)),
+        }
+        Ok(None)
+    }
+
+    fn seek(&self, position: Duration) -> Result<(), String> {
+        match self.state.borrow_mut().player {
+            Some(ref mut player) => player.seek(position).map_err(|e| e.to_string()),
+            None => Err("Player is not available".to_string()),
+        }
+    }
+
+    fn player_property(&self, property: &str) -> Option<Variant> {
+        let app_state = self.state.try_borrow().ok()?;
+        let player = app_state.player.as_ref();
+        let current = app_state.current_song.as_ref();
+        let position = player.and_then(|p| p.position());
+        let queue = &app_state.queue;
+
+        let value = match property {
+            "PlaybackStatus" => {
+                let status = if player.is_some_and(|p| p.is_playing()) {
+                    "Playing"
+                } else if position.is_some() {
+                    "Paused"
+                } else {
+                    "Stopped"
+                };
+                status.to_variant()
+            }
+            "LoopStatus" => loop_status(queue.repeat).to_variant(),
+            "Shuffle" => queue.shuffle.to_variant(),
+            "Metadata" => metadata(current),
+            "Volume" => player.map_or(0.0, |p| p.volume()).to_variant(),
+            "Position" => position.map_or(0, micros).to_variant(),
+            "Rate" | "MinimumRate" | "MaximumRate" => 1.0f64.to_variant(),
+            "CanGoNext" => {
+                let wraps = queue.repeat == RepeatMode::Playlist && current.is_some();
+                (!queue.upcoming().is_empty() || wraps).to_variant()
+            }
+            "CanGoPrevious" => queue.has_previous().to_variant(),
+            "CanPlay" => (current.is_some() || !queue.upcoming().is_empty()).to_variant(),
+            "CanPause" => current.is_some().to_variant(),
+            "CanSeek" => current.is_some_and(|song| song.duration.is_some()).to_variant(),
+            "CanControl" => true.to_variant(),
+            _ => return None,
+        };
+        Some(value)
+    }
+
+    fn emit(&self, interface: &str, signal: &str, params: Variant) {
+        let Some(connection) = self.connection.borrow().clone() else {
+            return;
+        };
+        if let Err(e) = connection.emit_signal(None, OBJECT_PATH, interface, signal, Some(&params)) {
+            eprintln!("Failed to emit {}: {}", signal, e);
+        }
+    }
+
+    fn properties_changed(&self, interface: &str, changed: &[(&str, Variant)]) {
+        if changed.is_empty() {
+            return;
+        }
+        let dict = VariantDict::new(None);
+        for (name, value) in changed {
+            dict.insert_value(name, value);
+        }
+        let params = Variant::tuple_from_iter([
+            interface.to_variant(),
+            dict.end(),
+            Vec::<String>::new().to_variant(),
+        ]);
+        self.emit("org.freedesktop.DBus.Properties", "PropertiesChanged", params);
+    }
+
+    // Compara com o último estado enviado e avisa os clientes do que mudou
+    fn emit_changes(&self) {
+        if self.connection.borrow().is_none() {
+            return;
+        }
+        let Some(values) = WATCHED.iter().map(|p| self.player_property(p)).collect::<Option<Vec<_>>>() else {
+            return;
+        };
+        let Ok(app_state) = self.state.try_borrow() else {
+            return;
+        };
+        let current = app_state.current_song.as_ref().map(|song| track_id(song, 0));
+        let playing = app_state.player.as_ref().is_some_and(|p| p.is_playing());
+        let position = app_state.player.as_ref().and_then(|p| p.position());
+        // A lista só é refeita quando a fila ou a música atual mudam
+        let tracks_key = (app_state.queue.revision(), app_state.current_song.as_ref().map(|s| s.file_path.clone()));
+        let tracks = (self.last.borrow().tracks.as_ref() != Some(&tracks_key)).then(|| {
+            tracklist::tracks(&app_state).into_iter().map(|(id, _)| id).collect::<Vec<_>>()
+        });
+        let playlist_count = playlists::entries(&app_state).len() as u32;
+        drop(app_state);
+
+        let mut last = self.last.borrow_mut();
+        let changed: Vec<(&str, Variant)> = WATCHED
+            .iter()
+            .zip(&values)
+            .enumerate()
+            .filter(|(index, (_, value))| last.player.get(*index) != Some(*value))
+            .map(|(_, (name, value))| (*name, value.clone()))
+            .collect();
+        last.player = values;
+
+        // Posição fora do esperado para a mesma faixa: alguém fez seek
+        let now = Instant::now();
+        if let (Some(track), Some(position)) = (&current, position) {
+            if let Some((last_track, last_position, was_playing, at)) = &last.position {
+                let expected = if *was_playing { *last_position + now.duration_since(*at) } else { *last_position };
+                let drift = micros(position).saturating_sub(micros(expected));
+                if last_track == track && drift.abs() > 1_000_000 {
+                    self.emit(PLAYER_IFACE, "Seeked", (micros(position),).to_variant());
+                }
+            }
+        }
+        last.position = current.clone().zip(position).map(|(track, position)| (track, position, playing, now));
+
+        if tracks.is_some() {
+            last.tracks = Some(tracks_key);
+        }
+        let count_changed = last.playlist_count != playlist_count;
+        last.playlist_count = playlist_count;
+        drop(last);
+
+        self.properties_changed(PLAYER_IFACE, &changed);
+        if let Some(tracks) = tracks {
+            let current = current.unwrap_or_else(no_track);
+            self.emit(TRACKLIST_IFACE, "TrackListReplaced", (tracks, current).to_variant());
+        }
+        if count_changed {
+            self.properties_changed(PLAYLISTS_IFACE, &[("PlaylistCount", playlist_count.to_variant())]);
+        }
+    }
+}
@@ -0,0 +1,102 @@
+use glib::variant::ObjectPath;
+use glib::{ToVariant, Variant};
+
+use crate::AppState;
+use crate::library::Song;
+use super::{object_path, CallResult, Mpris};
+
+const ORDERINGS: [&str; 2] = ["Alphabetical", "UserDefined"];
+
+// Playlists comuns e inteligentes, na ordem da barra lateral
+pub(super) fn entries(app_state: &AppState) -> Vec<(ObjectPath, String)> {
+    let library = &app_state.library;
+    let playlists = library
+        .playlists
+        .iter()
+        .enumerate()
+        .map(|(index, p)| (object_path(format!("/org/librefy/Librefy/Playlist/{}", index)), p.name.clone()));
+    let smart = library
+        .smart_playlists
+        .iter()
+        .enumerate()
+        .map(|(index, p)| (object_path(format!("/org/librefy/Librefy/SmartPlaylist/{}", index)), p.name.clone()));
+    playlists.chain(smart).collect()
+}
+
+fn songs(app_state: &AppState, id: &ObjectPath) -> Option<Vec<Song>> {
+    let library = &app_state.library;
+    let (kind, index) = id.as_str().rsplit_once('/')?;
+    let index: usize = index.parse().ok()?;
+    let songs = match kind {
+        "/org/librefy/Librefy/Playlist" => library.playlist_songs(library.playlists.get(index)?),
+        "/org/librefy/Librefy/SmartPlaylist" => library.evaluate_smart_playlist(library.smart_playlists.get(index)?),
+        _ => return None,
+    };
+    Some(songs.into_iter().cloned().collect())
+}
+
+// (oss): id, nome e ícone (não temos ícones)
+fn playlist_variant(id: &ObjectPath, name: &str) -> (ObjectPath, String, String) {
+    (id.clone(), name.to_string(), String::new())
+}
+
+impl Mpris {
+    pub(super) fn call_playlists(&self, method: &str, params: &Variant) -> CallResult {
+        match method {
+            "ActivatePlaylist" => {
+                let (id,) = params.get::<(ObjectPath,)>().ok_or("Invalid arguments")?;
+                let songs = songs(&self.state.borrow(), &id).ok_or("Unknown playlist")?;
+                let mut songs = songs.into_iter();
+                let first = songs.next().ok_or("Playlist is empty")?;
+                self.state.borrow_mut().queue.replace(songs.collect());
+                crate::play_song(&self.state, &first);
+                *self.active_playlist.borrow_mut() = Some(id);
+                let active = self.playlists_property("ActivePlaylist");
+                if let Some(active) = active {
+                    self.properties_changed(super::PLAYLISTS_IFACE, &[("ActivePlaylist", active)]);
+                }
+                Ok(None)
+            }
+            "GetPlaylists" => {
+                let (index, max_count, order, reverse) = params
+                    .get::<(u32, u32, String, bool)>()
+                    .ok_or("Invalid arguments")?;
+                let mut entries = entries(&self.state.borrow());
+                // Ordenações que não guardamos caem na ordem do usuário
+                if order == "Alphabetical" {
+                    entries.sort_by_key(|(_, name)| name.to_lowercase());
+                }
+                if reverse {
+                    entries.reverse();
+                }
+                let list: Vec<_> = entries
+                    .iter()
+                    .skip(index as usize)
+                    .take(max_count as usize)
+                    .map(|(id, name)| playlist_variant(id, name))
+                    .collect();
+                Ok(Some((list,).to_variant()))
+            }
+            _ => Err(format!("Unknown method {}", method)),
+        }
+    }
+
+    pub(super) fn playlists_property(&self, property: &str) -> Option<Variant> {
+        let app_state = self.state.try_borrow().ok()?;
+        let value = match property {
+            "PlaylistCount" => (entries(&app_state).len() as u32).to_variant(),
+            "Orderings" => ORDERINGS.to_vec().to_variant(),
+            "ActivePlaylist" => {
+                let active = self.active_playlist.borrow().clone().and_then(|id| {
+                    entries(&app_state).into_iter().find(|(entry, _)| *entry == id)
+                });
+                match active {
+                    Some((id, name)) => (true, playlist_variant(&id, &name)).to_variant(),
+                    None => (false, playlist_variant(&object_path("/".to_string()), "")).to_variant(),
+                }
+            }
+            _ => return None,
+        };
+        Some(value)
+    }
+}
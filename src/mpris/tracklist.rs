@@ -0,0 +1,120 @@
+use glib::variant::ObjectPath;
+use glib::{ToVariant, Variant, VariantTy};
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::AppState;
+use crate::library::Song;
+use super::{track_id, track_metadata, uri_to_path, CallResult, Mpris};
+
+// A lista exposta é a música atual seguida da fila
+pub(super) fn tracks(app_state: &AppState) -> Vec<(ObjectPath, &Song)> {
+    let mut repeats: HashMap<&Path, usize> = HashMap::new();
+    app_state
+        .current_song
+        .iter()
+        .chain(app_state.queue.upcoming())
+        .map(|song| {
+            let repeat = repeats.entry(song.file_path.as_path()).or_default();
+            let id = track_id(song, *repeat);
+            *repeat += 1;
+            (id, song)
+        })
+        .collect()
+}
+
+// Onde a faixa está: é a atual ou está na fila
+enum TrackPosition {
+    Current,
+    Queued(usize),
+}
+
+impl Mpris {
+    fn find_track(&self, id: &ObjectPath) -> Option<TrackPosition> {
+        let app_state = self.state.borrow();
+        let has_current = app_state.current_song.is_some();
+        let index = tracks(&app_state).iter().position(|(track, _)| track == id)?;
+        Some(match index.checked_sub(usize::from(has_current)) {
+            None => TrackPosition::Current,
+            Some(index) => TrackPosition::Queued(index),
+        })
+    }
+
+    pub(super) fn call_tracklist(&self, method: &str, params: &Variant) -> CallResult {
+        let state = &self.state;
+        match method {
+            "GetTracksMetadata" => {
+                let (ids,) = params.get::<(Vec<ObjectPath>,)>().ok_or("Invalid arguments")?;
+                let app_state = state.borrow();
+                let songs = tracks(&app_state);
+                // Ids desconhecidos ficam de fora da resposta
+                let items: Vec<Variant> = ids
+                    .iter()
+                    .filter_map(|id| songs.iter().find(|(track, _)| track == id))
+                    .map(|(id, song)| track_metadata(id, song))
+                    .collect();
+                let list = Variant::array_from_iter_with_type(VariantTy::VARDICT, items);
+                return Ok(Some(Variant::tuple_from_iter([list])));
+            }
+            "AddTrack" => {
+                let (uri, after, set_as_current) = params
+                    .get::<(String, ObjectPath, bool)>()
+                    .ok_or("Invalid arguments")?;
+                let path = uri_to_path(&uri).ok_or_else(|| format!("Unsupported URI: {}", uri))?;
+                let song = state.borrow().library.song_for_file(&path).map_err(|e| e.to_string())?;
+                let after = match self.find_track(&after) {
+                    Some(TrackPosition::Queued(index)) => Some(index),
+                    _ => None,
+                };
+                state.borrow_mut().queue.insert_after(after, song);
+                if set_as_current {
+                    self.go_to(after.map_or(0, |index| index + 1));
+                }
+            }
+            "RemoveTrack" => {
+                let (id,) = params.get::<(ObjectPath,)>().ok_or("Invalid arguments")?;
+                match self.find_track(&id) {
+                    Some(TrackPosition::Current) => {
+                        crate::play_next_track(state);
+                    }
+                    Some(TrackPosition::Queued(index)) => {
+                        state.borrow_mut().queue.remove(index);
+                    }
+                    None => {}
+                }
+            }
+            "GoTo" => {
+                let (id,) = params.get::<(ObjectPath,)>().ok_or("Invalid arguments")?;
+                match self.find_track(&id).ok_or("Unknown track")? {
+                    TrackPosition::Current => self.seek(std::time::Duration::ZERO)?,
+                    TrackPosition::Queued(index) => self.go_to(index),
+                }
+            }
+            _ => return Err(format!("Unknown method {}", method)),
+        }
+        Ok(None)
+    }
+
+    fn go_to(&self, index: usize) {
+        let next = {
+            let mut app_state = self.state.borrow_mut();
+            let current = app_state.current_song.clone();
+            app_state.queue.skip_to(index, current)
+        };
+        if let Some(next) = next {
+            crate::play_song(&self.state, &next);
+        }
+    }
+
+    pub(super) fn tracklist_property(&self, property: &str) -> Option<Variant> {
+        let value = match property {
+            "Tracks" => {
+                let app_state = self.state.try_borrow().ok()?;
+                tracks(&app_state).into_iter().map(|(id, _)| id).collect::<Vec<_>>().to_variant()
+            }
+            "CanEditTracks" => true.to_variant(),
+            _ => return None,
+        };
+        Some(value)
+    }
+}
@@ -0,0 +1,164 @@
+use crate::config::{EqualizerPreset, EqualizerSettings, EQ_BANDS};
+use crate::library::Song;
+
+// Frequências centrais do equalizer-10bands do GStreamer
+pub const BAND_LABELS: [&str; EQ_BANDS] = [
+    "29", "59", "119", "237", "474", "947", "1.9k", "3.8k", "7.5k", "15k",
+];
+pub const MIN_GAIN: f64 = -24.0;
+pub const MAX_GAIN: f64 = 12.0;
+
+fn preset(name: &str, preamp: f64, bands: [f64; EQ_BANDS]) -> EqualizerPreset {
+    EqualizerPreset {
+        name: name.to_string(),
+        preamp,
+        bands,
+    }
+}
+
+pub fn builtin_presets() -> Vec<EqualizerPreset> {
+    vec![
+        preset("Flat", 0.0, [0.0; EQ_BANDS]),
+        preset("Rock", -3.0, [5.0, 4.0, 3.0, 1.0, -1.0, -1.0, 1.0, 3.0, 4.0, 5.0]),
+        preset("Jazz", -2.0, [3.0, 2.0, 1.0, 2.0, -1.0, -1.0, 0.0, 1.0, 2.0, 3.0]),
+        preset("Classical", 0.0, [0.0, 0.0, 0.0, 0.0, 0.0, 0.0, -4.0, -4.0, -4.0, -6.0]),
+        preset("Bass Boost", -5.0, [7.0, 6.0, 5.0, 3.0, 1.0, 0.0, 0.0, 0.0, 0.0, 0.0]),
+        preset("Vocal", -2.0, [-3.0, -2.0, -1.0, 1.0, 4.0, 4.0, 3.0, 1.0, 0.0, -2.0]),
+    ]
+}
+
+pub fn is_builtin(name: &str) -> bool {
+    builtin_presets().iter().any(|preset| preset.name == name)
+}
+
+impl EqualizerSettings {
+    // Presets do usuário com o mesmo nome de um embutido ficam escondidos
+    pub fn all_presets(&self) -> Vec<EqualizerPreset> {
+        let mut presets = builtin_presets();
+        presets.extend(self.user_presets.iter().filter(|p| !is_builtin(&p.name)).cloned());
+        presets
+    }
+
+    // Nome exato: "rock" do usuário e "Rock" embutido são presets diferentes
+    pub fn find_preset(&self, name: &str) -> Option<EqualizerPreset> {
+        self.all_presets().into_iter().find(|preset| preset.name == name)
+    }
+
+    // Escolha do usuário; descarta o ajuste guardado pelo preset automático
+    pub fn select_preset(&mut self, preset: &EqualizerPreset) {
+        self.preamp = preset.preamp;
+        self.bands = preset.bands;
+        self.preset = Some(preset.name.clone());
+        self.manual = None;
+    }
+
+    pub fn save_preset(&mut self, name: &str) {
+        let preset = preset(name, self.preamp, self.bands);
+        self.user_presets.retain(|p| p.name != name);
+        self.user_presets.push(preset);
+        self.preset = Some(name.to_string());
+        self.manual = None;
+    }
+
+    // Preset automático da música por cima do ajuste manual, que volta
+    // quando tocar uma música sem preset. Retorna true se mudou algo
+    pub fn apply_auto(&mut self, preset: Option<EqualizerPreset>) -> bool {
+        match preset {
+            Some(preset) => {
+                if self.preset.as_deref() == Some(preset.name.as_str()) {
+                    return false;
+                }
+                let manual = self.manual.take().unwrap_or((self.preamp, self.bands, self.preset.clone()));
+                self.select_preset(&preset);
+                self.manual = Some(manual);
+                true
+            }
+            None => {
+                let Some((preamp, bands, preset)) = self.manual.take() else {
+                    return false;
+                };
+                self.preamp = preamp;
+                self.bands = bands;
+                self.preset = preset;
+                true
+            }
+        }
+    }
+
+    pub fn delete_preset(&mut self, name: &str) {
+        self.user_presets.retain(|p| p.name != name);
+        self.album_presets.retain(|_, preset| preset != name);
+        self.genre_presets.retain(|_, preset| preset != name);
+        if self.preset.as_deref() == Some(name) {
+            self.preset = None;
+        }
+    }
+
+    // Álbum tem prioridade sobre gênero; um gênero sem preset atribuído
+    // usa o embutido de mesmo nome (ex.: "Jazz")
+    pub fn auto_preset(&self, song: &Song) -> Option<EqualizerPreset> {
+        if self.auto_by_album {
+            if let Some(preset) = self.album_presets.get(&song.album).and_then(|n| self.find_preset(n)) {
+                return Some(preset);
+            }
+        }
+        if self.auto_by_genre {
+            let genre = song.genre.as_deref()?.trim().to_lowercase();
+            return match self.genre_presets.get(&genre) {
+                Some(name) => self.find_preset(name),
+                None => builtin_presets()
+                    .into_iter()
+                    .find(|preset| preset.name.to_lowercase() == genre),
+            };
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn settings() -> EqualizerSettings {
+        let mut settings = EqualizerSettings::default();
+        settings.user_presets.push(preset("rock", 1.0, [1.0; EQ_BANDS]));
+        settings
+    }
+
+    #[test]
+    fn finds_presets_by_exact_name() {
+        let settings = settings();
+        assert_eq!(settings.find_preset("rock").unwrap().preamp, 1.0);
+        assert_eq!(settings.find_preset("Rock").unwrap().preamp, -3.0);
+        assert!(settings.find_preset("ROCK").is_none());
+    }
+
+    #[test]
+    fn auto_preset_restores_manual_settings() {
+        let mut settings = settings();
+        settings.bands[0] = 2.0;
+        settings.preset = None;
+        let jazz = settings.find_preset("Jazz").unwrap();
+        let rock = settings.find_preset("Rock").unwrap();
+
+        assert!(settings.apply_auto(Some(jazz.clone())));
+        assert!(!settings.apply_auto(Some(jazz)));
+        assert!(settings.apply_auto(Some(rock)));
+        assert_eq!(settings.preset.as_deref(), Some("Rock"));
+
+        assert!(settings.apply_auto(None));
+        assert_eq!(settings.preset, None);
+        assert_eq!(settings.bands[0], 2.0);
+        assert!(!settings.apply_auto(None));
+    }
+
+    #[test]
+    fn choosing_a_preset_drops_the_manual_settings() {
+        let mut settings = settings();
+        settings.apply_auto(settings.find_preset("Jazz"));
+        let vocal = settings.find_preset("Vocal").unwrap();
+        settings.select_preset(&vocal);
+        assert!(!settings.apply_auto(None));
+        assert_eq!(settings.preset.as_deref(), Some("Vocal"));
+    }
+}
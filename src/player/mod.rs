@@ -1,79 +1,230 @@
-use anyhow::Result;
+pub mod equalizer;
+pub mod queue;
+
+use anyhow::{anyhow, Result};
+use gstreamer as gst;
+use gst::prelude::*;
 use std::path::Path;
-use std::process::Command;
+use std::time::Duration;
+
+use crate::config::{AudioOutput, EqualizerSettings};
+
+pub enum PlayerEvent {
+    Finished,
+    Error(String),
+}
 
 pub struct MusicPlayer {
     current_file: Option<String>,
     is_playing: bool,
-    child_process: Option<std::process::Child>,
+    playbin: gst::Element,
+    // Sem o plugin do equalizador (gst-plugins-good) toca sem filtro
+    equalizer: Option<(gst::Element, gst::Element)>,
+    // O seek só funciona depois do preroll; fica guardado até o AsyncDone
+    pending_seek: Option<Duration>,
+}
+
+// audioconvert ! volume (pré-amplificação) ! equalizer-10bands ! audioconvert
+fn build_audio_filter() -> Result<(gst::Bin, gst::Element, gst::Element)> {
+    let bin = gst::Bin::builder().name("audio-filter").build();
+    let convert_in = gst::ElementFactory::make("audioconvert").build()?;
+    let preamp = gst::ElementFactory::make("volume").name("preamp").build()?;
+    let equalizer = gst::ElementFactory::make("equalizer-10bands").name("equalizer").build()?;
+    let convert_out = gst::ElementFactory::make("audioconvert").build()?;
+
+    let elements = [&convert_in, &preamp, &equalizer, &convert_out];
+    bin.add_many(elements)?;
+    gst::Element::link_many(elements)?;
+
+    let sink_pad = convert_in.static_pad("sink").ok_or_else(|| anyhow!("audioconvert has no sink pad"))?;
+    let src_pad = convert_out.static_pad("src").ok_or_else(|| anyhow!("audioconvert has no src pad"))?;
+    bin.add_pad(&gst::GhostPad::with_target(&sink_pad)?)?;
+    bin.add_pad(&gst::GhostPad::with_target(&src_pad)?)?;
+    Ok((bin, equalizer, preamp))
 }
 
 impl MusicPlayer {
     pub fn new() -> Result<Self> {
+        gst::init()?;
+        let playbin = gst::ElementFactory::make("playbin").name("player").build()?;
+        let equalizer = match build_audio_filter() {
+            Ok((filter, equalizer, preamp)) => {
+                playbin.set_property("audio-filter", &filter);
+                Some((equalizer, preamp))
+            }
+            Err(e) => {
+                eprintln!("Equalizer unavailable, playing without it: {}", e);
+                None
+            }
+        };
+
+        // Sem vídeo nem legendas, só áudio
+        let flags = playbin.property_value("flags");
+        let flags_class = glib::FlagsClass::with_type(flags.type_()).ok_or_else(|| anyhow!("Invalid playbin flags"))?;
+        let flags = flags_class
+            .builder_with_value(flags)
+            .ok_or_else(|| anyhow!("Invalid playbin flags"))?
+            .unset_by_nick("video")
+            .unset_by_nick("text")
+            .build()
+            .ok_or_else(|| anyhow!("Invalid playbin flags"))?;
+        playbin.set_property_from_value("flags", &flags);
+
         Ok(Self {
             current_file: None,
             is_playing: false,
-            child_process: None,
+            playbin,
+            equalizer,
+            pending_seek: None,
         })
     }
 
     pub fn load_file(&mut self, file_path: &str) -> Result<()> {
         let path = Path::new(file_path);
         if path.exists() {
+            let uri = glib::filename_to_uri(path, None)?;
+            self.playbin.set_state(gst::State::Null)?;
+            self.playbin.set_property("uri", uri.as_str());
             self.current_file = Some(file_path.to_string());
+            self.pending_seek = None;
             println!("Loaded: {}", file_path);
             Ok(())
         } else {
-            Err(anyhow::anyhow!("File not found: {}", file_path))
+            Err(anyhow!("File not found: {}", file_path))
         }
     }
 
     pub fn play(&mut self) -> Result<()> {
-        if let Some(ref file_path) = self.current_file {
-            // Tenta usar mpv primeiro, depois vlc, depois ffplay
-            let players = ["mpv", "vlc", "ffplay"];
-            
-            for player in players {
-                let result = Command::new(player)
-                    .arg("--no-video")
-                    .arg("--no-terminal")
-                    .arg(file_path)
-                    .spawn();
-                
-                match result {
-                    Ok(child) => {
-                        self.child_process = Some(child);
-                        self.is_playing = true;
-                        println!("Playing with {}: {}", player, file_path);
-                        return Ok(());
-                    }
-                    Err(_) => continue,
-                }
-            }
-            
-            Err(anyhow::anyhow!("No supported media player found. Please install mpv, vlc, or ffmpeg."))
-        } else {
-            Err(anyhow::anyhow!("No file loaded"))
+        if self.current_file.is_none() {
+            return Err(anyhow!("No file loaded"));
+        }
+        // Com um seek pendente, poll_event começa a tocar depois dele
+        if self.pending_seek.is_none() {
+            self.playbin.set_state(gst::State::Playing)?;
+        }
+        self.is_playing = true;
+        Ok(())
+    }
+
+    // Carrega pausado já na posição, sem começar a tocar
+    pub fn cue(&mut self, path: &Path, position: Duration) -> Result<()> {
+        let file_path = path.to_str().ok_or_else(|| anyhow!("Invalid file name"))?;
+        self.load_file(file_path)?;
+        self.playbin.set_state(gst::State::Paused)?;
+        if !position.is_zero() {
+            self.pending_seek = Some(position);
+        }
+        self.is_playing = false;
+        Ok(())
+    }
+
+    pub fn seek(&mut self, position: Duration) -> Result<()> {
+        if self.pending_seek.is_some() {
+            self.pending_seek = Some(position);
+            return Ok(());
         }
+        let position = gst::ClockTime::from_mseconds(position.as_millis() as u64);
+        self.playbin.seek_simple(gst::SeekFlags::FLUSH | gst::SeekFlags::KEY_UNIT, position)?;
+        Ok(())
     }
 
     pub fn pause(&mut self) {
-        // Para processos externos, pausar é complexo, então vamos apenas parar
-        self.stop();
+        if let Err(e) = self.playbin.set_state(gst::State::Paused) {
+            eprintln!("Failed to pause: {}", e);
+        }
         self.is_playing = false;
     }
 
     pub fn stop(&mut self) {
-        if let Some(ref mut child) = self.child_process {
-            let _ = child.kill();
-            self.child_process = None;
+        if let Err(e) = self.playbin.set_state(gst::State::Null) {
+            eprintln!("Failed to stop: {}", e);
         }
         self.is_playing = false;
+        self.pending_seek = None;
     }
 
-    pub fn set_volume(&self, _volume: f64) {
-        // Volume control não suportado nesta versão simples
-        println!("Volume control not supported in basic player");
+    pub fn set_volume(&self, volume: f64) {
+        self.playbin.set_property("volume", volume.clamp(0.0, 1.0));
+    }
+
+    // Trocar a saída exige reiniciar o pipeline; volta ao mesmo ponto da música
+    pub fn set_audio_output(&mut self, output: AudioOutput) -> Result<()> {
+        let sink = gst::ElementFactory::make(output.element()).build()?;
+        let position = self.position();
+        self.playbin.set_state(gst::State::Null)?;
+        self.playbin.set_property("audio-sink", &sink);
+
+        // Volta a tocar quando o preroll terminar, em poll_event
+        if let Some(position) = position {
+            self.playbin.set_state(gst::State::Paused)?;
+            self.pending_seek = Some(position);
+        }
+        Ok(())
+    }
+
+    pub fn volume(&self) -> f64 {
+        self.playbin.property("volume")
+    }
+
+    // Muda os filtros com o pipeline rodando, sem interromper a música
+    pub fn set_equalizer(&self, settings: &EqualizerSettings) {
+        let Some((equalizer, preamp_element)) = &self.equalizer else {
+            return;
+        };
+        let (preamp, bands) = if settings.enabled {
+            (settings.preamp, settings.bands)
+        } else {
+            (0.0, Default::default())
+        };
+        preamp_element.set_property("volume", 10f64.powf(preamp / 20.0));
+        for (index, gain) in bands.iter().enumerate() {
+            let gain = gain.clamp(equalizer::MIN_GAIN, equalizer::MAX_GAIN);
+            equalizer.set_property(&format!("band{}", index), gain);
+        }
+    }
+
+    pub fn position(&self) -> Option<Duration> {
+        self.current_file.as_ref()?;
+        if self.pending_seek.is_some() {
+            return self.pending_seek;
+        }
+        self.playbin
+            .query_position::<gst::ClockTime>()
+            .map(|position| Duration::from_millis(position.mseconds()))
+    }
+
+    // Consultado periodicamente pela interface
+    pub fn poll_event(&mut self) -> Option<PlayerEvent> {
+        let bus = self.playbin.bus()?;
+        let message = bus.pop_filtered(&[
+            gst::MessageType::Eos,
+            gst::MessageType::Error,
+            gst::MessageType::AsyncDone,
+        ])?;
+        match message.view() {
+            gst::MessageView::AsyncDone(_) => {
+                let position = self.pending_seek.take()?;
+                if let Err(e) = self.seek(position) {
+                    eprintln!("Failed to seek: {}", e);
+                }
+                if self.is_playing {
+                    if let Err(e) = self.playbin.set_state(gst::State::Playing) {
+                        eprintln!("Failed to resume: {}", e);
+                    }
+                }
+                None
+            }
+            gst::MessageView::Eos(_) => {
+                // Fica parado no fim, para a posição ainda valer no histórico
+                self.is_playing = false;
+                Some(PlayerEvent::Finished)
+            }
+            gst::MessageView::Error(err) => {
+                self.stop();
+                Some(PlayerEvent::Error(err.error().to_string()))
+            }
+            _ => None,
+        }
     }
 
     pub fn is_playing(&self) -> bool {
@@ -84,3 +235,9 @@ impl MusicPlayer {
         self.current_file.as_ref()
     }
 }
+
+impl Drop for MusicPlayer {
+    fn drop(&mut self) {
+        let _ = self.playbin.set_state(gst::State::Null);
+    }
+}
@@ -0,0 +1,264 @@
+use std::collections::{HashMap, VecDeque};
+use std::path::PathBuf;
+use serde::{Deserialize, Serialize};
+
+use crate::library::Song;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RepeatMode {
+    #[default]
+    None,
+    Track,
+    Playlist,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PlayQueue {
+    upcoming: VecDeque<Song>,
+    history: Vec<Song>,
+    #[serde(default)]
+    pub shuffle: bool,
+    #[serde(default)]
+    pub repeat: RepeatMode,
+    // Muda a cada alteração da fila, para quem acompanha não precisar compará-la inteira
+    #[serde(skip)]
+    revision: u64,
+}
+
+impl PlayQueue {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn upcoming(&self) -> &VecDeque<Song> {
+        &self.upcoming
+    }
+
+    pub fn has_previous(&self) -> bool {
+        !self.history.is_empty()
+    }
+
+    pub fn revision(&self) -> u64 {
+        self.revision
+    }
+
+    // Troca a fila inteira, ex.: pela da sessão anterior
+    pub fn load(&mut self, saved: PlayQueue) {
+        let revision = self.revision + 1;
+        *self = saved;
+        self.revision = revision;
+    }
+
+    // Troca as músicas guardadas pelas versões atuais, ex.: as da biblioteca
+    pub fn refresh_songs(&mut self, refresh: impl Fn(Vec<Song>) -> Vec<Song>) {
+        self.upcoming = refresh(self.upcoming.drain(..).collect()).into();
+        self.history = refresh(std::mem::take(&mut self.history));
+        self.revision += 1;
+    }
+
+    // Substitui o que viria a seguir
+    pub fn replace(&mut self, songs: Vec<Song>) {
+        self.upcoming = songs.into();
+        self.revision += 1;
+    }
+
+    // Coloca as músicas logo depois da atual, mantendo a ordem delas
+    pub fn play_next(&mut self, songs: Vec<Song>) {
+        for song in songs.into_iter().rev() {
+            self.upcoming.push_front(song);
+        }
+        self.revision += 1;
+    }
+
+    pub fn enqueue(&mut self, songs: Vec<Song>) {
+        self.upcoming.extend(songs);
+        self.revision += 1;
+    }
+
+    // Sem `after` (ou fora da fila) entra logo depois da atual
+    pub fn insert_after(&mut self, after: Option<usize>, song: Song) {
+        let index = after
+            .filter(|index| *index < self.upcoming.len())
+            .map_or(0, |index| index + 1);
+        self.upcoming.insert(index, song);
+        self.revision += 1;
+    }
+
+    pub fn remove(&mut self, index: usize) -> Option<Song> {
+        let song = self.upcoming.remove(index)?;
+        self.revision += 1;
+        Some(song)
+    }
+
+    // Arquivos movidos (ex.: pelo organizador) continuam na fila com o caminho novo
+    pub fn relocate(&mut self, moved: &HashMap<PathBuf, PathBuf>) {
+        for song in self.upcoming.iter_mut().chain(self.history.iter_mut()) {
+            if let Some(to) = moved.get(&song.file_path) {
+                song.file_path = to.clone();
+            }
+        }
+        self.revision += 1;
+    }
+
+    // Pula direto para uma música da fila; as do meio vão para o histórico
+    pub fn skip_to(&mut self, index: usize, current: Option<Song>) -> Option<Song> {
+        if index >= self.upcoming.len() {
+            return None;
+        }
+        if let Some(current) = current {
+            self.history.push(current);
+        }
+        self.history.extend(self.upcoming.drain(..index));
+        self.revision += 1;
+        self.upcoming.pop_front()
+    }
+
+    pub fn next(&mut self, current: Option<Song>) -> Option<Song> {
+        self.revision += 1;
+        let mut current = current;
+        // Repetindo a fila, tudo o que já tocou volta para o fim
+        if self.upcoming.is_empty() && self.repeat == RepeatMode::Playlist {
+            self.upcoming.extend(self.history.drain(..));
+            self.upcoming.extend(current.take());
+        }
+        let index = if self.shuffle && self.upcoming.len() > 1 {
+            glib::random_int_range(0, self.upcoming.len() as i32) as usize
+        } else {
+            0
+        };
+        let next = self.upcoming.remove(index)?;
+        if let Some(current) = current {
+            self.history.push(current);
+        }
+        Some(next)
+    }
+
+    pub fn previous(&mut self, current: Option<Song>) -> Option<Song> {
+        let previous = self.history.pop()?;
+        self.revision += 1;
+        if let Some(current) = current {
+            self.upcoming.push_front(current);
+        }
+        Some(previous)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn song(name: &str) -> Song {
+        Song::new(PathBuf::from(format!("/music/{}.mp3", name)), name.to_string(), String::new(), String::new(), None)
+    }
+
+    fn names(songs: &VecDeque<Song>) -> Vec<&str> {
+        songs.iter().map(|song| song.title.as_str()).collect()
+    }
+
+    fn queue(items: &[&str]) -> PlayQueue {
+        let mut queue = PlayQueue::new();
+        queue.enqueue(items.iter().map(|name| song(name)).collect());
+        queue
+    }
+
+    #[test]
+    fn plays_next_in_order() {
+        let mut queue = queue(&["c"]);
+        queue.play_next(vec![song("a"), song("b")]);
+        assert_eq!(names(queue.upcoming()), vec!["a", "b", "c"]);
+
+        assert_eq!(queue.next(Some(song("x"))).unwrap().title, "a");
+        assert_eq!(queue.next(Some(song("a"))).unwrap().title, "b");
+        assert_eq!(queue.previous(Some(song("b"))).unwrap().title, "a");
+        assert_eq!(names(queue.upcoming()), vec!["b", "c"]);
+    }
+
+    #[test]
+    fn refreshes_saved_songs() {
+        let mut queue = queue(&["b", "c"]);
+        queue.next(Some(song("a")));
+        let revision = queue.revision();
+        queue.refresh_songs(|songs| {
+            songs
+                .into_iter()
+                .map(|mut song| {
+                    song.title = song.title.to_uppercase();
+                    song
+                })
+                .collect()
+        });
+        assert_eq!(names(queue.upcoming()), vec!["C"]);
+        assert_eq!(queue.previous(None).unwrap().title, "A");
+        assert!(queue.revision() > revision);
+    }
+
+    #[test]
+    fn inserts_removes_and_skips_by_index() {
+        let mut queue = queue(&["a", "b", "a"]);
+        queue.insert_after(Some(1), song("new"));
+        queue.insert_after(None, song("first"));
+        queue.insert_after(Some(99), song("also first"));
+        assert_eq!(names(queue.upcoming()), vec!["also first", "first", "a", "b", "new", "a"]);
+
+        // Só a repetição escolhida sai da fila
+        assert_eq!(queue.remove(5).unwrap().title, "a");
+        assert!(queue.remove(5).is_none());
+        assert_eq!(names(queue.upcoming()), vec!["also first", "first", "a", "b", "new"]);
+
+        assert_eq!(queue.skip_to(3, Some(song("x"))).unwrap().title, "b");
+        assert_eq!(names(queue.upcoming()), vec!["new"]);
+        assert!(queue.skip_to(1, None).is_none());
+        assert_eq!(queue.previous(None).unwrap().title, "a");
+    }
+
+    #[test]
+    fn repeats_the_whole_queue() {
+        let mut queue = queue(&["b"]);
+        queue.repeat = RepeatMode::Playlist;
+        assert_eq!(queue.next(Some(song("a"))).unwrap().title, "b");
+        assert_eq!(queue.next(Some(song("b"))).unwrap().title, "a");
+        assert_eq!(names(queue.upcoming()), vec!["b"]);
+
+        queue.repeat = RepeatMode::None;
+        queue.next(Some(song("a")));
+        assert!(queue.next(Some(song("b"))).is_none());
+    }
+
+    #[test]
+    fn shuffle_picks_from_the_queue() {
+        let mut queue = queue(&["a", "b", "c"]);
+        queue.shuffle = true;
+        let next = queue.next(None).unwrap();
+        assert_eq!(queue.upcoming().len(), 2);
+        assert!(!queue.upcoming().iter().any(|song| song.title == next.title));
+    }
+
+    #[test]
+    fn relocates_moved_files() {
+        let mut queue = queue(&["a"]);
+        queue.next(Some(song("b")));
+        let moved = HashMap::from([
+            (PathBuf::from("/music/b.mp3"), PathBuf::from("/new/b.mp3")),
+        ]);
+        queue.relocate(&moved);
+        assert_eq!(queue.previous(None).unwrap().file_path, PathBuf::from("/new/b.mp3"));
+    }
+
+    #[test]
+    fn every_change_bumps_the_revision() {
+        let mut queue = PlayQueue::new();
+        let mut last = queue.revision();
+        let mut changed = |queue: &PlayQueue| {
+            let bumped = queue.revision() > last;
+            last = queue.revision();
+            bumped
+        };
+        queue.enqueue(vec![song("a")]);
+        assert!(changed(&queue));
+        queue.next(None);
+        assert!(changed(&queue));
+        queue.load(PlayQueue::new());
+        assert!(changed(&queue));
+        assert!(!changed(&queue));
+    }
+}
@@ -0,0 +1,121 @@
+use std::collections::BTreeMap;
+use serde_json::Value;
+
+use super::{Scrobble, SubmitError};
+use crate::config::ScrobbleSettings;
+
+// Limite da API do Last.fm para track.scrobble
+pub const BATCH_SIZE: usize = 50;
+
+// Erros de configuração ou do servidor; os demais (ex.: 6, parâmetros inválidos) não melhoram reenviando
+const RETRY_ERRORS: [i64; 8] = [8, 9, 10, 11, 13, 16, 26, 29];
+
+// api_sig = md5 dos parâmetros em ordem alfabética (nome + valor) seguidos do segredo
+fn sign(params: &BTreeMap<String, String>, secret: &str) -> String {
+    let mut text: String = params.iter().map(|(key, value)| format!("{}{}", key, value)).collect();
+    text.push_str(secret);
+    let mut checksum = glib::Checksum::new(glib::ChecksumType::Md5).expect("MD5 is always available");
+    checksum.update(text.as_bytes());
+    checksum.string().map(|sum| sum.to_string()).unwrap_or_default()
+}
+
+fn api_error(body: &str) -> Option<SubmitError> {
+    let value: Value = serde_json::from_str(body).ok()?;
+    let code = value.get("error")?.as_i64()?;
+    let message = format!(
+        "Last.fm error {}: {}",
+        code,
+        value.get("message").and_then(Value::as_str).unwrap_or("")
+    );
+    if RETRY_ERRORS.contains(&code) {
+        Some(SubmitError::Retry(message))
+    } else {
+        Some(SubmitError::Rejected(message))
+    }
+}
+
+fn call(agent: &ureq::Agent, settings: &ScrobbleSettings, mut params: BTreeMap<String, String>) -> Result<Value, SubmitError> {
+    params.insert("api_key".to_string(), settings.api_key.trim().to_string());
+    params.insert("sk".to_string(), settings.session_key.trim().to_string());
+    let signature = sign(&params, settings.api_secret.trim());
+    params.insert("api_sig".to_string(), signature);
+    // "format" fica fora da assinatura
+    params.insert("format".to_string(), "json".to_string());
+
+    let form: Vec<(&str, &str)> = params.iter().map(|(k, v)| (k.as_str(), v.as_str())).collect();
+    let body = match agent.post(settings.url()).send_form(&form) {
+        Ok(response) => response.into_string().map_err(|e| SubmitError::Retry(e.to_string()))?,
+        Err(ureq::Error::Status(code, response)) => {
+            let body = response.into_string().unwrap_or_default();
+            return Err(api_error(&body)
+                .unwrap_or_else(|| SubmitError::Retry(format!("HTTP {}: {}", code, body.trim()))));
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    if let Some(error) = api_error(&body) {
+        return Err(error);
+    }
+    serde_json::from_str(&body).map_err(|e| SubmitError::Retry(e.to_string()))
+}
+
+fn track_params(params: &mut BTreeMap<String, String>, scrobble: &Scrobble, suffix: &str) {
+    let mut set = |name: &str, value: String| {
+        params.insert(format!("{}{}", name, suffix), value);
+    };
+    set("artist", scrobble.artist.clone());
+    set("track", scrobble.title.clone());
+    if let Some(album) = &scrobble.album {
+        set("album", album.clone());
+    }
+    if let Some(album_artist) = &scrobble.album_artist {
+        set("albumArtist", album_artist.clone());
+    }
+    if let Some(track_number) = scrobble.track_number {
+        set("trackNumber", track_number.to_string());
+    }
+    if let Some(duration) = scrobble.duration {
+        set("duration", duration.to_string());
+    }
+}
+
+pub fn now_playing(agent: &ureq::Agent, settings: &ScrobbleSettings, scrobble: &Scrobble) -> Result<(), SubmitError> {
+    let mut params = BTreeMap::new();
+    params.insert("method".to_string(), "track.updateNowPlaying".to_string());
+    track_params(&mut params, scrobble, "");
+    call(agent, settings, params).map(|_| ())
+}
+
+pub fn submit(agent: &ureq::Agent, settings: &ScrobbleSettings, scrobbles: &[Scrobble]) -> Result<(), SubmitError> {
+    let mut params = BTreeMap::new();
+    params.insert("method".to_string(), "track.scrobble".to_string());
+    for (index, scrobble) in scrobbles.iter().enumerate() {
+        let suffix = format!("[{}]", index);
+        track_params(&mut params, scrobble, &suffix);
+        params.insert(format!("timestamp{}", suffix), scrobble.timestamp.to_string());
+    }
+
+    let response = call(agent, settings, params)?;
+    let ignored = response
+        .pointer("/scrobbles/@attr/ignored")
+        .and_then(|v| v.as_u64().or_else(|| v.as_str()?.parse().ok()))
+        .unwrap_or(0);
+    if ignored > 0 {
+        eprintln!("Last.fm ignored {} of {} scrobbles", ignored, scrobbles.len());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signs_sorted_params_with_the_secret() {
+        let mut params = BTreeMap::new();
+        params.insert("method".to_string(), "auth.getMobileSession".to_string());
+        params.insert("api_key".to_string(), "key".to_string());
+        // md5("api_keykeymethodauth.getMobileSessionsecret")
+        assert_eq!(sign(&params, "secret"), "018322def6bdaf0b7eba8f03ac376100");
+    }
+}
@@ -0,0 +1,60 @@
+use serde_json::{json, Value};
+
+use super::{Scrobble, SubmitError};
+use crate::config::ScrobbleSettings;
+
+// O servidor aceita até 1000 por requisição; lotes menores falham menos
+pub const BATCH_SIZE: usize = 100;
+
+fn track_metadata(scrobble: &Scrobble) -> Value {
+    let mut additional_info = json!({
+        "media_player": "Librefy",
+        "submission_client": "Librefy",
+        "submission_client_version": env!("CARGO_PKG_VERSION"),
+    });
+    if let Some(duration) = scrobble.duration {
+        additional_info["duration_ms"] = json!(duration * 1000);
+    }
+    if let Some(track_number) = scrobble.track_number {
+        additional_info["tracknumber"] = json!(track_number);
+    }
+
+    let mut metadata = json!({
+        "artist_name": scrobble.artist,
+        "track_name": scrobble.title,
+        "additional_info": additional_info,
+    });
+    if let Some(album) = &scrobble.album {
+        metadata["release_name"] = json!(album);
+    }
+    metadata
+}
+
+fn post(agent: &ureq::Agent, settings: &ScrobbleSettings, body: Value) -> Result<(), SubmitError> {
+    let url = format!("{}/1/submit-listens", settings.url().trim_end_matches('/'));
+    agent
+        .post(&url)
+        .set("Authorization", &format!("Token {}", settings.token.trim()))
+        .set("Content-Type", "application/json")
+        .send_string(&body.to_string())?;
+    Ok(())
+}
+
+pub fn now_playing(agent: &ureq::Agent, settings: &ScrobbleSettings, scrobble: &Scrobble) -> Result<(), SubmitError> {
+    post(agent, settings, json!({
+        "listen_type": "playing_now",
+        "payload": [{ "track_metadata": track_metadata(scrobble) }],
+    }))
+}
+
+pub fn submit(agent: &ureq::Agent, settings: &ScrobbleSettings, scrobbles: &[Scrobble]) -> Result<(), SubmitError> {
+    let payload: Vec<Value> = scrobbles
+        .iter()
+        .map(|scrobble| json!({
+            "listened_at": scrobble.timestamp,
+            "track_metadata": track_metadata(scrobble),
+        }))
+        .collect();
+    let listen_type = if payload.len() == 1 { "single" } else { "import" };
+    post(agent, settings, json!({ "listen_type": listen_type, "payload": payload }))
+}
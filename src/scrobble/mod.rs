@@ -0,0 +1,282 @@
+pub mod lastfm;
+pub mod listenbrainz;
+
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::config::{data_dir, ScrobbleService, ScrobbleSettings};
+use crate::library::Song;
+
+// Scrobbles que ainda não foram aceitos pelo servidor
+const QUEUE_FILE: &str = "scrobble_queue.json";
+const MIN_TRACK_SECONDS: u64 = 30;
+const MAX_REQUIRED_SECONDS: u64 = 240;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Scrobble {
+    pub artist: String,
+    pub title: String,
+    pub album: Option<String>,
+    pub album_artist: Option<String>,
+    pub track_number: Option<u32>,
+    pub duration: Option<u64>,
+    pub timestamp: u64, // início da reprodução
+}
+
+impl Scrobble {
+    pub fn new(song: &Song, timestamp: u64) -> Self {
+        Self {
+            artist: song.artist.clone(),
+            title: song.title.clone(),
+            album: Some(song.album.clone()).filter(|album| album != "Unknown Album"),
+            album_artist: song.album_artist.clone(),
+            track_number: song.track_number,
+            duration: song.duration,
+            timestamp,
+        }
+    }
+}
+
+pub enum SubmitError {
+    // Sem rede, servidor fora do ar, credenciais erradas: tenta de novo mais tarde
+    Retry(String),
+    // O servidor recusou os dados; reenviar não adianta
+    Rejected(String),
+}
+
+impl SubmitError {
+    pub fn message(&self) -> &str {
+        match self {
+            SubmitError::Retry(message) | SubmitError::Rejected(message) => message,
+        }
+    }
+}
+
+impl From<ureq::Error> for SubmitError {
+    fn from(error: ureq::Error) -> Self {
+        match error {
+            ureq::Error::Status(code, response) => {
+                let body = response.into_string().unwrap_or_default();
+                let message = format!("HTTP {}: {}", code, body.trim());
+                if code == 400 {
+                    SubmitError::Rejected(message)
+                } else {
+                    SubmitError::Retry(message)
+                }
+            }
+            ureq::Error::Transport(transport) => SubmitError::Retry(transport.to_string()),
+        }
+    }
+}
+
+// Regra padrão: faixa com mais de 30 s, tocada até a metade ou por 4 minutos
+pub fn should_scrobble(duration: Option<u64>, played_seconds: u64) -> bool {
+    match duration {
+        Some(duration) if duration <= MIN_TRACK_SECONDS => false,
+        Some(duration) => played_seconds >= (duration / 2).min(MAX_REQUIRED_SECONDS),
+        None => played_seconds >= MAX_REQUIRED_SECONDS,
+    }
+}
+
+fn agent() -> ureq::Agent {
+    ureq::AgentBuilder::new()
+        .timeout(Duration::from_secs(20))
+        .user_agent(concat!("Librefy/", env!("CARGO_PKG_VERSION")))
+        .build()
+}
+
+pub fn now_playing(settings: &ScrobbleSettings, scrobble: &Scrobble) -> Result<(), SubmitError> {
+    match settings.service {
+        ScrobbleService::ListenBrainz => listenbrainz::now_playing(&agent(), settings, scrobble),
+        ScrobbleService::LastFm => lastfm::now_playing(&agent(), settings, scrobble),
+    }
+}
+
+pub fn submit(settings: &ScrobbleSettings, scrobbles: &[Scrobble]) -> Result<(), SubmitError> {
+    match settings.service {
+        ScrobbleService::ListenBrainz => listenbrainz::submit(&agent(), settings, scrobbles),
+        ScrobbleService::LastFm => lastfm::submit(&agent(), settings, scrobbles),
+    }
+}
+
+fn queue_path() -> PathBuf {
+    data_dir().join(QUEUE_FILE)
+}
+
+pub struct Scrobbler {
+    pub settings: ScrobbleSettings,
+    pending: Vec<Scrobble>,
+    queue_path: PathBuf,
+    // Só um envio por vez, para não mandar o mesmo lote duas vezes
+    flushing: bool,
+    // Depois de um lote recusado, reenvia esses um a um para achar o culpado
+    retry_singly: usize,
+}
+
+impl Scrobbler {
+    pub fn new(settings: ScrobbleSettings) -> Self {
+        let queue_path = queue_path();
+        let pending = fs::read_to_string(&queue_path)
+            .ok()
+            .and_then(|contents| match serde_json::from_str(&contents) {
+                Ok(pending) => Some(pending),
+                Err(e) => {
+                    eprintln!("Ignoring invalid scrobble queue: {}", e);
+                    None
+                }
+            })
+            .unwrap_or_default();
+
+        Self {
+            settings,
+            pending,
+            queue_path,
+            flushing: false,
+            retry_singly: 0,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.settings.enabled
+    }
+
+    fn save(&self) -> Result<()> {
+        if let Some(dir) = self.queue_path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&self.queue_path, serde_json::to_string(&self.pending)?)?;
+        Ok(())
+    }
+
+    pub fn enqueue(&mut self, scrobble: Scrobble) {
+        self.pending.push(scrobble);
+        if let Err(e) = self.save() {
+            eprintln!("Failed to save scrobble queue: {}", e);
+        }
+    }
+
+    // Próximo lote a enviar, ou None se já há um envio em andamento
+    pub fn take_batch(&mut self) -> Option<(ScrobbleSettings, Vec<Scrobble>)> {
+        if !self.is_enabled() || self.flushing || self.pending.is_empty() {
+            return None;
+        }
+        self.flushing = true;
+        let size = match self.settings.service {
+            _ if self.retry_singly > 0 => 1,
+            ScrobbleService::ListenBrainz => listenbrainz::BATCH_SIZE,
+            ScrobbleService::LastFm => lastfm::BATCH_SIZE,
+        };
+        let batch = self.pending.iter().take(size).cloned().collect();
+        Some((self.settings.clone(), batch))
+    }
+
+    // Retorna true se ainda há scrobbles para enviar agora
+    pub fn batch_done(&mut self, sent: usize, result: Result<(), SubmitError>) -> bool {
+        self.flushing = false;
+        let done = match result {
+            Ok(_) => {
+                println!("Submitted {} scrobbles", sent);
+                true
+            }
+            Err(SubmitError::Rejected(message)) if sent > 1 => {
+                eprintln!("Scrobbles rejected, retrying {} one by one: {}", sent, message);
+                self.retry_singly = sent;
+                return true;
+            }
+            Err(SubmitError::Rejected(message)) => {
+                eprintln!("Scrobble rejected, dropping it: {}", message);
+                true
+            }
+            Err(SubmitError::Retry(message)) => {
+                eprintln!("Scrobbling failed, keeping {} queued: {}", self.pending.len(), message);
+                false
+            }
+        };
+        if !done {
+            return false;
+        }
+
+        self.retry_singly = self.retry_singly.saturating_sub(sent);
+        self.pending.drain(..sent.min(self.pending.len()));
+        if let Err(e) = self.save() {
+            eprintln!("Failed to save scrobble queue: {}", e);
+        }
+        !self.pending.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scrobbler(pending: usize) -> Scrobbler {
+        let settings = ScrobbleSettings { enabled: true, ..ScrobbleSettings::default() };
+        let pending = (0..pending)
+            .map(|i| Scrobble {
+                artist: "Artist".to_string(),
+                title: format!("Track {}", i),
+                album: None,
+                album_artist: None,
+                track_number: None,
+                duration: Some(200),
+                timestamp: i as u64,
+            })
+            .collect();
+        // Fila gravada fora da pasta de dados do usuário
+        let queue_path = std::env::temp_dir().join(format!("librefy-test-scrobbles-{}.json", std::process::id()));
+        Scrobbler { settings, pending, queue_path, flushing: false, retry_singly: 0 }
+    }
+
+    fn titles(scrobbles: &[Scrobble]) -> Vec<&str> {
+        scrobbles.iter().map(|s| s.title.as_str()).collect()
+    }
+
+    #[test]
+    fn scrobbles_after_half_or_four_minutes() {
+        assert!(!should_scrobble(Some(30), 30));
+        assert!(!should_scrobble(Some(31), 14));
+        assert!(should_scrobble(Some(31), 15));
+        assert!(!should_scrobble(Some(200), 99));
+        assert!(should_scrobble(Some(200), 100));
+        assert!(!should_scrobble(Some(3600), 239));
+        assert!(should_scrobble(Some(3600), 240));
+        assert!(!should_scrobble(None, 239));
+        assert!(should_scrobble(None, 240));
+    }
+
+    #[test]
+    fn rejected_batch_is_retried_one_by_one() {
+        let mut scrobbler = scrobbler(3);
+        let (_, batch) = scrobbler.take_batch().unwrap();
+        assert_eq!(batch.len(), 3);
+        assert!(scrobbler.take_batch().is_none());
+
+        assert!(scrobbler.batch_done(3, Err(SubmitError::Rejected("bad".to_string()))));
+        assert_eq!(scrobbler.pending.len(), 3);
+
+        // Só o item recusado sai da fila
+        let (_, batch) = scrobbler.take_batch().unwrap();
+        assert_eq!(titles(&batch), vec!["Track 0"]);
+        assert!(scrobbler.batch_done(1, Ok(())));
+        let (_, batch) = scrobbler.take_batch().unwrap();
+        assert_eq!(titles(&batch), vec!["Track 1"]);
+        assert!(scrobbler.batch_done(1, Err(SubmitError::Rejected("bad".to_string()))));
+        let (_, batch) = scrobbler.take_batch().unwrap();
+        assert_eq!(titles(&batch), vec!["Track 2"]);
+        assert!(!scrobbler.batch_done(1, Ok(())));
+        assert!(scrobbler.pending.is_empty());
+        assert_eq!(scrobbler.retry_singly, 0);
+    }
+
+    #[test]
+    fn failed_batch_stays_queued() {
+        let mut scrobbler = scrobbler(2);
+        let (_, batch) = scrobbler.take_batch().unwrap();
+        assert!(!scrobbler.batch_done(batch.len(), Err(SubmitError::Retry("offline".to_string()))));
+        assert_eq!(titles(&scrobbler.pending), vec!["Track 0", "Track 1"]);
+        assert_eq!(scrobbler.take_batch().unwrap().1.len(), 2);
+    }
+}
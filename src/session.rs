@@ -0,0 +1,188 @@
+use gtk4::prelude::*;
+use gtk4::{ApplicationWindow, Box};
+use std::cell::RefCell;
+use std::fs;
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+use crate::{AppState, LibraryView};
+use crate::config::data_dir;
+use crate::library::Song;
+use crate::player::queue::PlayQueue;
+
+// Estado da última execução, salvo ao sair e de tempos em tempos
+const SESSION_FILE: &str = "session.json";
+
+fn session_path() -> PathBuf {
+    data_dir().join(SESSION_FILE)
+}
+
+// Gravações em segundo plano podem terminar fora de ordem; uma mais antiga não sobrescreve a mais nova
+static NEXT_CAPTURE: AtomicU64 = AtomicU64::new(1);
+static LAST_WRITTEN: Mutex<u64> = Mutex::new(0);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Session {
+    pub queue: PlayQueue,
+    pub current_song: Option<Song>,
+    pub position_ms: u64,
+    pub playing: bool,
+    pub volume: f64,
+    pub view: LibraryView,
+    pub scroll: f64,
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Self {
+            queue: PlayQueue::new(),
+            current_song: None,
+            position_ms: 0,
+            playing: false,
+            volume: 1.0,
+            view: LibraryView::AllSongs,
+            scroll: 0.0,
+        }
+    }
+}
+
+impl Session {
+    pub fn load() -> Self {
+        let path = session_path();
+        let Ok(contents) = fs::read_to_string(&path) else {
+            return Self::default();
+        };
+        serde_json::from_str(&contents).unwrap_or_else(|e| {
+            eprintln!("Ignoring invalid session file {}: {}", path.display(), e);
+            Self::default()
+        })
+    }
+
+    fn save(&self, capture: u64) -> Result<()> {
+        let mut last_written = LAST_WRITTEN.lock().unwrap_or_else(|e| e.into_inner());
+        if capture < *last_written {
+            return Ok(());
+        }
+        let dir = data_dir();
+        fs::create_dir_all(&dir)?;
+        // Grava ao lado e renomeia, para um travamento no meio não deixar o arquivo pela metade
+        let temp = dir.join(format!("{}.tmp", SESSION_FILE));
+        fs::write(&temp, serde_json::to_string_pretty(self)?)?;
+        fs::rename(&temp, session_path())?;
+        *last_written = capture;
+        Ok(())
+    }
+
+    fn capture(app_state: &AppState, scroll: f64) -> Self {
+        let player = app_state.player.as_ref();
+        Self {
+            queue: app_state.queue.clone(),
+            current_song: app_state.current_song.clone(),
+            position_ms: player.and_then(|p| p.position()).map_or(0, |position| position.as_millis() as u64),
+            playing: player.is_some_and(|p| p.is_playing()),
+            volume: player.map_or(1.0, |p| p.volume()),
+            view: app_state.current_view.clone(),
+            scroll,
+        }
+    }
+}
+
+fn capture(state: &Rc<RefCell<AppState>>) -> Option<(Session, u64)> {
+    // Pula se a interface estiver no meio de uma alteração; o próximo ciclo salva
+    let app_state = state.try_borrow().ok()?;
+    let scroll = app_state.song_scroll.as_ref().map_or(0.0, |adjustment| adjustment.value());
+    Some((Session::capture(&app_state, scroll), NEXT_CAPTURE.fetch_add(1, Ordering::Relaxed)))
+}
+
+pub fn save(state: &Rc<RefCell<AppState>>) {
+    let Some((session, capture)) = capture(state) else {
+        return;
+    };
+    if let Err(e) = session.save(capture) {
+        eprintln!("Failed to save session: {}", e);
+    }
+}
+
+// Para o salvamento periódico: a fila inteira é serializada fora da thread da interface
+pub fn save_in_background(state: &Rc<RefCell<AppState>>) {
+    let Some((session, capture)) = capture(state) else {
+        return;
+    };
+    gio::spawn_blocking(move || {
+        if let Err(e) = session.save(capture) {
+            eprintln!("Failed to save session: {}", e);
+        }
+    });
+}
+
+// O que não existe mais na biblioteca volta para a lista de todas as músicas
+fn view_exists(app_state: &AppState, view: &LibraryView) -> bool {
+    let library = &app_state.library;
+    match view {
+        LibraryView::AllSongs => true,
+        LibraryView::Playlist(index) => *index < library.playlists.len(),
+        LibraryView::SmartPlaylist(index) => *index < library.smart_playlists.len(),
+        LibraryView::Artist(name) => library.artists.contains_key(name),
+        LibraryView::Album(name) => library.albums.contains_key(name),
+    }
+}
+
+pub fn restore(
+    state: &Rc<RefCell<AppState>>,
+    window: &ApplicationWindow,
+    content: &Box
+) {
+    let session = Session::load();
+
+    let resume = {
+        let mut app_state = state.borrow_mut();
+        let app_state = &mut *app_state;
+        // As músicas guardadas podem estar velhas; notas e contagens vêm da biblioteca
+        let mut queue = session.queue;
+        queue.refresh_songs(|songs| app_state.library.prefer_known(songs));
+        app_state.queue.load(queue);
+        if view_exists(app_state, &session.view) {
+            app_state.current_view = session.view;
+        }
+
+        let mut resume = false;
+        if let Some(ref mut player) = app_state.player {
+            player.set_volume(session.volume);
+            // A música fica carregada e pausada no mesmo ponto
+            let current_song = session.current_song.and_then(|song| app_state.library.prefer_known(vec![song]).pop());
+            if let Some(song) = current_song.filter(|song| song.file_path.exists()) {
+                let position = Duration::from_millis(session.position_ms);
+                match player.cue(&song.file_path, position) {
+                    Ok(()) => {
+                        println!("Restored {} at {}s", song.get_display_name(), position.as_secs());
+                        app_state.current_song = Some(song);
+                        resume = session.playing && app_state.settings.resume_playback;
+                    }
+                    Err(e) => eprintln!("Failed to restore {}: {}", song.file_path.display(), e),
+                }
+            }
+        }
+        resume
+    };
+
+    if state.borrow().current_view != LibraryView::AllSongs {
+        crate::refresh_view(content, window, state);
+    }
+
+    // Espera a lista ser desenhada para a rolagem ter para onde ir
+    if let Some(adjustment) = state.borrow().song_scroll.clone() {
+        glib::timeout_add_local_once(Duration::from_millis(300), move || {
+            adjustment.set_value(session.scroll);
+        });
+    }
+
+    if resume {
+        crate::resume_playback(state);
+    }
+}
@@ -0,0 +1,333 @@
+use gtk4::prelude::*;
+use gtk4::{
+    ApplicationWindow, Box, Button, CheckButton, Label, Orientation, ScrolledWindow, Spinner, Window
+};
+use std::cell::RefCell;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use crate::AppState;
+use crate::library::duplicates::{find_duplicates, DiskAction, DuplicateGroup, DuplicateMethod};
+use crate::library::Song;
+use crate::ui::song_list::{format_duration, remove_from_store};
+
+// Um botão "Keep" por cópia; só a marcada fica
+struct GroupChoice {
+    songs: Vec<Song>,
+    keep: Vec<CheckButton>,
+}
+
+impl GroupChoice {
+    fn to_remove(&self) -> Vec<PathBuf> {
+        self.songs
+            .iter()
+            .zip(&self.keep)
+            .filter(|(_, keep)| !keep.is_active())
+            .map(|(song, _)| song.file_path.clone())
+            .collect()
+    }
+}
+
+fn describe_copy(song: &Song) -> String {
+    let format = song
+        .file_path
+        .extension()
+        .map(|e| e.to_string_lossy().to_uppercase())
+        .unwrap_or_default();
+    let mut details = vec![format];
+    if let Some(bitrate) = song.bitrate {
+        details.push(format!("{} kbps", bitrate));
+    }
+    if let Some(duration) = song.duration {
+        details.push(format_duration(duration));
+    }
+    details.push(format!("{:.1} MB", song.file_size as f64 / 1_048_576.0));
+    format!("{} · {}", details.join(" · "), song.file_path.display())
+}
+
+fn create_group_view(group: &DuplicateGroup) -> (Box, GroupChoice) {
+    let container = Box::new(Orientation::Vertical, 5);
+    container.add_css_class("card");
+    container.set_margin_bottom(5);
+
+    let reasons: Vec<&str> = group.reasons.iter().map(|r| r.label()).collect();
+    let title = Label::new(Some(&format!(
+        "{} — {}  ({})",
+        group.songs[0].artist,
+        group.songs[0].title,
+        reasons.join(", ")
+    )));
+    title.set_halign(gtk4::Align::Start);
+    title.add_css_class("heading");
+    container.append(&title);
+
+    let mut keep: Vec<CheckButton> = Vec::new();
+    for song in &group.songs {
+        let check = CheckButton::with_label(&describe_copy(song));
+        if let Some(first) = keep.first() {
+            check.set_group(Some(first));
+        } else {
+            // A lista já vem com a melhor cópia primeiro
+            check.set_active(true);
+        }
+        container.append(&check);
+        keep.push(check);
+    }
+
+    let choice = GroupChoice {
+        songs: group.songs.clone(),
+        keep,
+    };
+    (container, choice)
+}
+
+// Tira as cópias da biblioteca e da tabela; devolve as que não puderam ser removidas
+fn remove_copies(state: &Rc<RefCell<AppState>>, paths: &[PathBuf], action: DiskAction) -> Vec<(PathBuf, anyhow::Error)> {
+    let (errors, store) = {
+        let mut app_state = state.borrow_mut();
+        let errors = app_state.library.remove_duplicates(paths, action);
+        (errors, app_state.song_store.clone())
+    };
+    let failed: Vec<&PathBuf> = errors.iter().map(|(path, _)| path).collect();
+    let removed: Vec<PathBuf> = paths.iter().filter(|p| !failed.contains(p)).cloned().collect();
+    remove_from_store(&store, &removed);
+    println!("Removed {} duplicate copies", removed.len());
+    errors
+}
+
+fn error_detail(errors: &[(PathBuf, anyhow::Error)]) -> String {
+    let detail: Vec<String> = errors.iter().map(|(path, e)| format!("{}: {}", path.display(), e)).collect();
+    detail.join("\n")
+}
+
+fn show_delete_errors(parent: &ApplicationWindow, errors: &[(PathBuf, anyhow::Error)]) {
+    let alert = gtk4::AlertDialog::builder()
+        .modal(true)
+        .message(format!("{} files could not be deleted", errors.len()))
+        .detail(error_detail(errors))
+        .build();
+    alert.show(Some(parent));
+}
+
+// O que não foi para a lixeira (ex.: sem lixeira naquele disco) só é apagado de vez com uma segunda confirmação
+fn confirm_permanent_delete(
+    parent: &ApplicationWindow,
+    state: &Rc<RefCell<AppState>>,
+    errors: Vec<(PathBuf, anyhow::Error)>,
+    on_removed: Rc<dyn Fn()>,
+) {
+    let alert = gtk4::AlertDialog::builder()
+        .modal(true)
+        .message(format!("{} files could not be moved to the trash", errors.len()))
+        .detail(format!("{}\n\nDelete them permanently?", error_detail(&errors)))
+        .buttons(["Keep", "Delete Permanently"])
+        .cancel_button(0)
+        .default_button(0)
+        .build();
+    let paths: Vec<PathBuf> = errors.into_iter().map(|(path, _)| path).collect();
+    let state = Rc::clone(state);
+    let parent_clone = parent.clone();
+    alert.choose(Some(parent), gio::Cancellable::NONE, move |result| {
+        if result != Ok(1) {
+            return;
+        }
+        let errors = remove_copies(&state, &paths, DiskAction::Delete);
+        on_removed();
+        if !errors.is_empty() {
+            show_delete_errors(&parent_clone, &errors);
+        }
+    });
+}
+
+pub fn show_duplicates_dialog<F>(parent: &ApplicationWindow, state: &Rc<RefCell<AppState>>, on_removed: F)
+where
+    F: Fn() + 'static,
+{
+    let dialog = Window::builder()
+        .title("Find Duplicates")
+        .transient_for(parent)
+        .modal(true)
+        .default_width(750)
+        .default_height(600)
+        .build();
+
+    let content_area = Box::new(Orientation::Vertical, 10);
+    content_area.set_margin_top(15);
+    content_area.set_margin_bottom(15);
+    content_area.set_margin_start(15);
+    content_area.set_margin_end(15);
+
+    let tags_check = CheckButton::with_label("Same tags and duration");
+    tags_check.set_active(true);
+    let hash_check = CheckButton::with_label("Identical audio content (ignores tags)");
+    hash_check.set_active(true);
+    let fingerprint_check = CheckButton::with_label("Acoustic fingerprint (slow, requires fpcalc)");
+
+    let methods_box = Box::new(Orientation::Vertical, 5);
+    methods_box.append(&tags_check);
+    methods_box.append(&hash_check);
+    methods_box.append(&fingerprint_check);
+
+    let find_btn = Button::with_label("Find Duplicates");
+    let spinner = Spinner::new();
+    let status_label = Label::new(None);
+    status_label.set_halign(gtk4::Align::Start);
+    status_label.set_hexpand(true);
+
+    let status_box = Box::new(Orientation::Horizontal, 10);
+    status_box.append(&find_btn);
+    status_box.append(&spinner);
+    status_box.append(&status_label);
+
+    let results_box = Box::new(Orientation::Vertical, 5);
+    let results_scroll = ScrolledWindow::new();
+    results_scroll.set_vexpand(true);
+    results_scroll.set_child(Some(&results_box));
+
+    let choices: Rc<RefCell<Vec<GroupChoice>>> = Rc::new(RefCell::new(Vec::new()));
+
+    let remove_btn = Button::with_label("Remove Others from Library");
+    let delete_btn = Button::with_label("Move Others to Trash");
+    delete_btn.add_css_class("destructive-action");
+    remove_btn.set_sensitive(false);
+    delete_btn.set_sensitive(false);
+
+    let state_clone = Rc::clone(state);
+    let choices_clone = Rc::clone(&choices);
+    let results_box_clone = results_box.clone();
+    let remove_btn_clone = remove_btn.clone();
+    let delete_btn_clone = delete_btn.clone();
+    find_btn.connect_clicked(move |btn| {
+        let mut methods = Vec::new();
+        if tags_check.is_active() {
+            methods.push(DuplicateMethod::Tags);
+        }
+        if hash_check.is_active() {
+            methods.push(DuplicateMethod::AudioHash);
+        }
+        if fingerprint_check.is_active() {
+            methods.push(DuplicateMethod::Fingerprint);
+        }
+        if methods.is_empty() {
+            status_label.set_text("Choose at least one method");
+            return;
+        }
+
+        btn.set_sensitive(false);
+        spinner.start();
+        status_label.set_text("Scanning library…");
+        while let Some(child) = results_box_clone.first_child() {
+            results_box_clone.remove(&child);
+        }
+        choices_clone.borrow_mut().clear();
+
+        // Ler e comparar os arquivos pode demorar, então roda em outra thread
+        let songs = state_clone.borrow().library.songs.clone();
+        let handle = gio::spawn_blocking(move || find_duplicates(&songs, &methods));
+
+        let btn = btn.clone();
+        let spinner = spinner.clone();
+        let status_label = status_label.clone();
+        let results_box = results_box_clone.clone();
+        let choices = Rc::clone(&choices_clone);
+        let remove_btn = remove_btn_clone.clone();
+        let delete_btn = delete_btn_clone.clone();
+        glib::MainContext::default().spawn_local(async move {
+            let result = handle.await;
+            btn.set_sensitive(true);
+            spinner.stop();
+            let report = match result {
+                Ok(report) => report,
+                Err(_) => {
+                    status_label.set_text("Duplicate scan failed");
+                    return;
+                }
+            };
+            for (path, error) in &report.errors {
+                eprintln!("Duplicate scan: {}: {}", path.display(), error);
+            }
+
+            let extra: usize = report.groups.iter().map(|g| g.songs.len() - 1).sum();
+            let mut status = format!("{} groups, {} extra copies", report.groups.len(), extra);
+            if !report.errors.is_empty() {
+                status.push_str(&format!(" ({} files could not be checked)", report.errors.len()));
+            }
+            status_label.set_text(&status);
+
+            for group in &report.groups {
+                let (view, choice) = create_group_view(group);
+                results_box.append(&view);
+                choices.borrow_mut().push(choice);
+            }
+            remove_btn.set_sensitive(!report.groups.is_empty());
+            delete_btn.set_sensitive(!report.groups.is_empty());
+        });
+    });
+
+    let remove_others: Rc<dyn Fn(DiskAction)> = {
+        let state = Rc::clone(state);
+        let dialog = dialog.clone();
+        let parent = parent.clone();
+        let choices = Rc::clone(&choices);
+        let on_removed: Rc<dyn Fn()> = Rc::new(on_removed);
+        Rc::new(move |action: DiskAction| {
+            let paths: Vec<PathBuf> = choices.borrow().iter().flat_map(|c| c.to_remove()).collect();
+            let errors = remove_copies(&state, &paths, action);
+            dialog.close();
+            on_removed();
+
+            if errors.is_empty() {
+                return;
+            }
+            if action == DiskAction::Trash {
+                confirm_permanent_delete(&parent, &state, errors, Rc::clone(&on_removed));
+            } else {
+                show_delete_errors(&parent, &errors);
+            }
+        })
+    };
+
+    let remove_clone = Rc::clone(&remove_others);
+    remove_btn.connect_clicked(move |_| remove_clone(DiskAction::Keep));
+
+    let remove_clone = Rc::clone(&remove_others);
+    let dialog_clone = dialog.clone();
+    delete_btn.connect_clicked(move |_| {
+        // Apagar do disco sempre pede confirmação
+        let alert = gtk4::AlertDialog::builder()
+            .modal(true)
+            .message("Move the unchecked copies to the trash?")
+            .detail("Files that cannot be trashed are only deleted after asking again.")
+            .buttons(["Cancel", "Move to Trash"])
+            .cancel_button(0)
+            .default_button(0)
+            .build();
+        let remove = Rc::clone(&remove_clone);
+        alert.choose(Some(&dialog_clone), gio::Cancellable::NONE, move |result| {
+            if result == Ok(1) {
+                remove(DiskAction::Trash);
+            }
+        });
+    });
+
+    let close_btn = Button::with_label("Close");
+    let dialog_clone = dialog.clone();
+    close_btn.connect_clicked(move |_| {
+        dialog_clone.close();
+    });
+
+    content_area.append(&Label::new(Some("Group songs by:")));
+    content_area.append(&methods_box);
+    content_area.append(&status_box);
+    content_area.append(&results_scroll);
+
+    let button_box = Box::new(Orientation::Horizontal, 10);
+    button_box.set_halign(gtk4::Align::End);
+    button_box.append(&close_btn);
+    button_box.append(&remove_btn);
+    button_box.append(&delete_btn);
+    content_area.append(&button_box);
+
+    dialog.set_child(Some(&content_area));
+    dialog.present();
+}
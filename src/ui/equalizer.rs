@@ -0,0 +1,306 @@
+use gtk4::prelude::*;
+use gtk4::{
+    ApplicationWindow, Box, Button, CheckButton, DropDown, Entry, Label, Orientation, Scale,
+    StringList, Switch, Window
+};
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+use crate::AppState;
+use crate::config::EQ_BANDS;
+use crate::player::equalizer::{is_builtin, BAND_LABELS, MAX_GAIN, MIN_GAIN};
+
+const CUSTOM: &str = "Custom";
+
+fn apply(state: &Rc<RefCell<AppState>>) {
+    let app_state = state.borrow();
+    if let Some(ref player) = app_state.player {
+        player.set_equalizer(&app_state.equalizer);
+    }
+}
+
+fn create_slider(label: &str) -> (Box, Scale) {
+    let column = Box::new(Orientation::Vertical, 5);
+    let scale = Scale::with_range(Orientation::Vertical, MIN_GAIN, MAX_GAIN, 0.5);
+    // Valores positivos para cima
+    scale.set_inverted(true);
+    scale.set_vexpand(true);
+    scale.set_draw_value(true);
+    scale.set_digits(1);
+    scale.set_value_pos(gtk4::PositionType::Bottom);
+    scale.add_mark(0.0, gtk4::PositionType::Left, None);
+    column.append(&scale);
+    column.append(&Label::new(Some(label)));
+    (column, scale)
+}
+
+// Lista de presets no dropdown: "Custom" primeiro, depois embutidos e do usuário
+fn preset_names(state: &Rc<RefCell<AppState>>) -> Vec<String> {
+    let mut names = vec![CUSTOM.to_string()];
+    names.extend(state.borrow().equalizer.all_presets().into_iter().map(|p| p.name));
+    names
+}
+
+pub fn show_equalizer_window(parent: &ApplicationWindow, state: &Rc<RefCell<AppState>>) {
+    let window = Window::builder()
+        .title("Equalizer")
+        .transient_for(parent)
+        .default_width(700)
+        .default_height(450)
+        .build();
+
+    let content_area = Box::new(Orientation::Vertical, 10);
+    content_area.set_margin_top(15);
+    content_area.set_margin_bottom(15);
+    content_area.set_margin_start(15);
+    content_area.set_margin_end(15);
+
+    // Evita tratar como edição manual os valores que o próprio código ajusta
+    let updating = Rc::new(Cell::new(false));
+
+    let enabled_switch = Switch::new();
+    enabled_switch.set_valign(gtk4::Align::Center);
+    enabled_switch.set_active(state.borrow().equalizer.enabled);
+
+    let names = Rc::new(RefCell::new(preset_names(state)));
+    let preset_model = StringList::new(&names.borrow().iter().map(String::as_str).collect::<Vec<_>>());
+    let preset_dropdown = DropDown::new(Some(preset_model.clone()), gtk4::Expression::NONE);
+
+    let header = Box::new(Orientation::Horizontal, 10);
+    header.append(&Label::new(Some("Enabled")));
+    header.append(&enabled_switch);
+    let spacer = Box::new(Orientation::Horizontal, 0);
+    spacer.set_hexpand(true);
+    header.append(&spacer);
+    header.append(&Label::new(Some("Preset:")));
+    header.append(&preset_dropdown);
+
+    let sliders_box = Box::new(Orientation::Horizontal, 10);
+    sliders_box.set_vexpand(true);
+    sliders_box.set_homogeneous(true);
+    let (preamp_column, preamp_scale) = create_slider("Preamp");
+    sliders_box.append(&preamp_column);
+    let mut band_scales = Vec::with_capacity(EQ_BANDS);
+    for label in BAND_LABELS {
+        let (column, scale) = create_slider(&format!("{} Hz", label));
+        sliders_box.append(&column);
+        band_scales.push(scale);
+    }
+    let band_scales = Rc::new(band_scales);
+
+    // Mostra as configurações atuais nos controles
+    let sync_controls = {
+        let state = Rc::clone(state);
+        let names = Rc::clone(&names);
+        let updating = Rc::clone(&updating);
+        let preset_dropdown = preset_dropdown.clone();
+        let preamp_scale = preamp_scale.clone();
+        let band_scales = Rc::clone(&band_scales);
+        Rc::new(move || {
+            updating.set(true);
+            let settings = state.borrow().equalizer.clone();
+            preamp_scale.set_value(settings.preamp);
+            for (scale, gain) in band_scales.iter().zip(settings.bands) {
+                scale.set_value(gain);
+            }
+            let selected = settings
+                .preset
+                .and_then(|name| names.borrow().iter().position(|n| *n == name))
+                .unwrap_or(0);
+            preset_dropdown.set_selected(selected as u32);
+            updating.set(false);
+        })
+    };
+    sync_controls();
+
+    let reload_presets = {
+        let state = Rc::clone(state);
+        let names = Rc::clone(&names);
+        let updating = Rc::clone(&updating);
+        let preset_model = preset_model.clone();
+        let sync_controls = Rc::clone(&sync_controls);
+        Rc::new(move || {
+            let new_names = preset_names(&state);
+            updating.set(true);
+            let refs: Vec<&str> = new_names.iter().map(String::as_str).collect();
+            preset_model.splice(0, preset_model.n_items(), &refs);
+            updating.set(false);
+            *names.borrow_mut() = new_names;
+            sync_controls();
+        })
+    };
+
+    let state_clone = Rc::clone(state);
+    enabled_switch.connect_active_notify(move |switch| {
+        state_clone.borrow_mut().equalizer.enabled = switch.is_active();
+        apply(&state_clone);
+    });
+
+    let state_clone = Rc::clone(state);
+    let names_clone = Rc::clone(&names);
+    let updating_clone = Rc::clone(&updating);
+    let sync_clone = Rc::clone(&sync_controls);
+    preset_dropdown.connect_selected_notify(move |dropdown| {
+        if updating_clone.get() {
+            return;
+        }
+        let Some(name) = names_clone.borrow().get(dropdown.selected() as usize).cloned() else {
+            return;
+        };
+        {
+            let mut app_state = state_clone.borrow_mut();
+            match app_state.equalizer.find_preset(&name) {
+                Some(preset) => app_state.equalizer.select_preset(&preset),
+                None => {
+                    app_state.equalizer.preset = None;
+                    app_state.equalizer.manual = None;
+                }
+            }
+        }
+        apply(&state_clone);
+        sync_clone();
+    });
+
+    // Mexer numa banda transforma o preset em "Custom"
+    let on_manual_change = {
+        let state = Rc::clone(state);
+        let updating = Rc::clone(&updating);
+        let preset_dropdown = preset_dropdown.clone();
+        let preamp_scale = preamp_scale.clone();
+        let band_scales = Rc::clone(&band_scales);
+        Rc::new(move || {
+            if updating.get() {
+                return;
+            }
+            {
+                let mut app_state = state.borrow_mut();
+                app_state.equalizer.preamp = preamp_scale.value();
+                for (index, scale) in band_scales.iter().enumerate() {
+                    app_state.equalizer.bands[index] = scale.value();
+                }
+                app_state.equalizer.preset = None;
+                app_state.equalizer.manual = None;
+            }
+            apply(&state);
+            updating.set(true);
+            preset_dropdown.set_selected(0);
+            updating.set(false);
+        })
+    };
+    for scale in band_scales.iter().chain(std::iter::once(&preamp_scale)) {
+        let on_change = Rc::clone(&on_manual_change);
+        scale.connect_value_changed(move |_| on_change());
+    }
+
+    // Presets do usuário
+    let name_entry = Entry::new();
+    name_entry.set_placeholder_text(Some("Preset name"));
+    name_entry.set_hexpand(true);
+    let save_btn = Button::with_label("Save Preset");
+    let delete_btn = Button::with_label("Delete Preset");
+
+    let state_clone = Rc::clone(state);
+    let reload_clone = Rc::clone(&reload_presets);
+    let name_entry_clone = name_entry.clone();
+    save_btn.connect_clicked(move |_| {
+        let name = name_entry_clone.text().trim().to_string();
+        if name.is_empty() || name == CUSTOM || is_builtin(&name) {
+            name_entry_clone.add_css_class("error");
+            return;
+        }
+        name_entry_clone.remove_css_class("error");
+        state_clone.borrow_mut().equalizer.save_preset(&name);
+        name_entry_clone.set_text("");
+        reload_clone();
+    });
+
+    let state_clone = Rc::clone(state);
+    let reload_clone = Rc::clone(&reload_presets);
+    delete_btn.connect_clicked(move |_| {
+        let name = state_clone.borrow().equalizer.preset.clone();
+        match name {
+            Some(name) if !is_builtin(&name) => {
+                state_clone.borrow_mut().equalizer.delete_preset(&name);
+                reload_clone();
+            }
+            _ => println!("Only saved presets can be deleted"),
+        }
+    });
+
+    let presets_box = Box::new(Orientation::Horizontal, 10);
+    presets_box.append(&name_entry);
+    presets_box.append(&save_btn);
+    presets_box.append(&delete_btn);
+
+    // Seleção automática por álbum ou gênero
+    let album_check = CheckButton::with_label("Choose preset by album");
+    album_check.set_active(state.borrow().equalizer.auto_by_album);
+    let genre_check = CheckButton::with_label("Choose preset by genre");
+    genre_check.set_active(state.borrow().equalizer.auto_by_genre);
+
+    let state_clone = Rc::clone(state);
+    album_check.connect_toggled(move |check| {
+        state_clone.borrow_mut().equalizer.auto_by_album = check.is_active();
+    });
+    let state_clone = Rc::clone(state);
+    genre_check.connect_toggled(move |check| {
+        state_clone.borrow_mut().equalizer.auto_by_genre = check.is_active();
+    });
+
+    let assign_album_btn = Button::with_label("Use for Current Album");
+    let assign_genre_btn = Button::with_label("Use for Current Genre");
+
+    let state_clone = Rc::clone(state);
+    assign_album_btn.connect_clicked(move |_| {
+        let mut app_state = state_clone.borrow_mut();
+        let album = app_state.current_song.as_ref().map(|song| song.album.clone());
+        match (album, app_state.equalizer.preset.clone()) {
+            (Some(album), Some(preset)) => {
+                println!("Equalizer preset for album {}: {}", album, preset);
+                app_state.equalizer.album_presets.insert(album, preset);
+            }
+            _ => println!("Play a song and choose a preset first"),
+        }
+    });
+
+    let state_clone = Rc::clone(state);
+    assign_genre_btn.connect_clicked(move |_| {
+        let mut app_state = state_clone.borrow_mut();
+        let genre = app_state
+            .current_song
+            .as_ref()
+            .and_then(|song| song.genre.as_deref())
+            .map(|genre| genre.trim().to_lowercase())
+            .filter(|genre| !genre.is_empty());
+        match (genre, app_state.equalizer.preset.clone()) {
+            (Some(genre), Some(preset)) => {
+                println!("Equalizer preset for genre {}: {}", genre, preset);
+                app_state.equalizer.genre_presets.insert(genre, preset);
+            }
+            _ => println!("Play a song with a genre and choose a preset first"),
+        }
+    });
+
+    let auto_box = Box::new(Orientation::Horizontal, 10);
+    auto_box.append(&album_check);
+    auto_box.append(&genre_check);
+    let auto_spacer = Box::new(Orientation::Horizontal, 0);
+    auto_spacer.set_hexpand(true);
+    auto_box.append(&auto_spacer);
+    auto_box.append(&assign_album_btn);
+    auto_box.append(&assign_genre_btn);
+
+    content_area.append(&header);
+    content_area.append(&sliders_box);
+    content_area.append(&presets_box);
+    content_area.append(&auto_box);
+
+    let state_clone = Rc::clone(state);
+    window.connect_close_request(move |_| {
+        state_clone.borrow().equalizer.save();
+        glib::Propagation::Proceed
+    });
+
+    window.set_child(Some(&content_area));
+    window.present();
+}
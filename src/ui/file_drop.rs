@@ -0,0 +1,23 @@
+use gtk4::prelude::*;
+use gtk4::{gdk, DropTarget};
+use std::path::PathBuf;
+
+// Aceita arquivos e pastas arrastados do gerenciador de arquivos
+pub fn file_drop_target<F>(on_drop: F) -> DropTarget
+where
+    F: Fn(Vec<PathBuf>) + 'static,
+{
+    let target = DropTarget::new(gdk::FileList::static_type(), gdk::DragAction::COPY);
+    target.connect_drop(move |_, value, _, _| {
+        let Ok(files) = value.get::<gdk::FileList>() else {
+            return false;
+        };
+        let paths: Vec<PathBuf> = files.files().iter().filter_map(|file| file.path()).collect();
+        if paths.is_empty() {
+            return false;
+        }
+        on_drop(paths);
+        true
+    });
+    target
+}
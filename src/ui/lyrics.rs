@@ -0,0 +1,312 @@
+use gtk4::prelude::*;
+use gtk4::{
+    ApplicationWindow, Box, Button, Label, ListBox, ListBoxRow, Orientation, Revealer,
+    ScrolledWindow, TextView, Window
+};
+use std::cell::RefCell;
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::time::Duration;
+
+use crate::AppState;
+use crate::library::lyrics::{self, format_timestamp, Lyrics};
+
+const TICK: Duration = Duration::from_millis(250);
+
+struct LyricsView {
+    list: ListBox,
+    scroll: ScrolledWindow,
+    status: Label,
+    edit_btn: Button,
+    // None força recarregar no próximo tick
+    loaded: Option<PathBuf>,
+    lyrics: Option<Lyrics>,
+    current: Option<usize>,
+}
+
+impl LyricsView {
+    fn show(&mut self, path: Option<PathBuf>) {
+        while let Some(child) = self.list.first_child() {
+            self.list.remove(&child);
+        }
+        self.current = None;
+        self.lyrics = path.as_deref().and_then(lyrics::load);
+        self.edit_btn.set_sensitive(path.is_some());
+
+        match (&path, &self.lyrics) {
+            (None, _) => self.status.set_text("Nothing playing"),
+            (Some(_), None) => self.status.set_text("No lyrics found"),
+            (Some(_), Some(lyrics)) => {
+                let kind = if lyrics.is_synced() { "Synced" } else { "Unsynced" };
+                self.status.set_text(&format!("{} · {}", kind, lyrics.source.label()));
+                for line in &lyrics.lines {
+                    let label = Label::new(Some(&line.text));
+                    label.set_wrap(true);
+                    label.set_xalign(0.0);
+                    label.add_css_class("lyrics-line");
+                    let row = ListBoxRow::new();
+                    row.set_child(Some(&label));
+                    row.set_activatable(line.time.is_some());
+                    self.list.append(&row);
+                }
+            }
+        }
+        self.scroll.vadjustment().set_value(0.0);
+        self.loaded = path;
+    }
+
+    fn highlight(&mut self, position: Duration) {
+        let Some(lyrics) = &self.lyrics else {
+            return;
+        };
+        let current = lyrics.current_line(position);
+        if current == self.current {
+            return;
+        }
+
+        let label_at = |index: usize| self.list.row_at_index(index as i32).and_then(|row| row.child());
+        if let Some(label) = self.current.and_then(label_at) {
+            label.remove_css_class("current-lyric");
+        }
+        if let Some(row) = current.and_then(|index| self.list.row_at_index(index as i32)) {
+            if let Some(label) = row.child() {
+                label.add_css_class("current-lyric");
+            }
+            // Mantém a linha atual no meio do painel
+            if let Some(bounds) = row.compute_bounds(&self.list) {
+                let adjustment = self.scroll.vadjustment();
+                let target = f64::from(bounds.y()) - (adjustment.page_size() - f64::from(bounds.height())) / 2.0;
+                adjustment.set_value(target.max(0.0));
+            }
+        }
+        self.current = current;
+    }
+}
+
+pub fn create_lyrics_panel(window: &ApplicationWindow, state: &Rc<RefCell<AppState>>) -> Revealer {
+    let panel = Box::new(Orientation::Vertical, 10);
+    panel.add_css_class("lyrics-panel");
+    panel.set_size_request(320, -1);
+    panel.set_margin_top(10);
+    panel.set_margin_start(10);
+    panel.set_margin_end(10);
+
+    let title = Label::new(Some("Lyrics"));
+    title.add_css_class("title-4");
+    title.set_halign(gtk4::Align::Start);
+    title.set_hexpand(true);
+    let edit_btn = Button::with_label("✏ Edit");
+    edit_btn.set_sensitive(false);
+    let header = Box::new(Orientation::Horizontal, 10);
+    header.append(&title);
+    header.append(&edit_btn);
+
+    let status = Label::new(Some("Nothing playing"));
+    status.add_css_class("dim-label");
+    status.set_halign(gtk4::Align::Start);
+
+    let list = ListBox::new();
+    list.set_selection_mode(gtk4::SelectionMode::None);
+    let scroll = ScrolledWindow::new();
+    scroll.set_vexpand(true);
+    scroll.set_child(Some(&list));
+
+    panel.append(&header);
+    panel.append(&status);
+    panel.append(&scroll);
+
+    let revealer = Revealer::new();
+    revealer.set_transition_type(gtk4::RevealerTransitionType::SlideLeft);
+    revealer.set_child(Some(&panel));
+
+    let view = Rc::new(RefCell::new(LyricsView {
+        list: list.clone(),
+        scroll,
+        status,
+        edit_btn: edit_btn.clone(),
+        loaded: None,
+        lyrics: None,
+        current: None,
+    }));
+
+    // Clicar numa linha sincronizada pula para ela
+    let state_clone = Rc::clone(state);
+    let view_clone = Rc::clone(&view);
+    list.connect_row_activated(move |_, row| {
+        let time = view_clone
+            .borrow()
+            .lyrics
+            .as_ref()
+            .and_then(|lyrics| lyrics.lines.get(row.index() as usize))
+            .and_then(|line| line.time);
+        let Some(time) = time else {
+            return;
+        };
+        if let Some(ref mut player) = state_clone.borrow_mut().player {
+            if let Err(e) = player.seek(time) {
+                eprintln!("Failed to seek: {}", e);
+            }
+        }
+    });
+
+    let window_clone = window.clone();
+    let state_clone = Rc::clone(state);
+    let view_clone = Rc::clone(&view);
+    edit_btn.connect_clicked(move |_| {
+        let Some(path) = view_clone.borrow().loaded.clone() else {
+            return;
+        };
+        let text = view_clone.borrow().lyrics.as_ref().map(|l| l.to_lrc()).unwrap_or_default();
+        let view = Rc::clone(&view_clone);
+        show_lyrics_editor(&window_clone, &state_clone, path, &text, move || {
+            view.borrow_mut().loaded = None;
+        });
+    });
+
+    // Acompanha a música e a posição do player enquanto o painel está aberto
+    let revealer_weak = revealer.downgrade();
+    let state_clone = Rc::clone(state);
+    glib::timeout_add_local(TICK, move || {
+        let Some(revealer) = revealer_weak.upgrade() else {
+            return glib::ControlFlow::Break;
+        };
+        if !revealer.reveals_child() {
+            return glib::ControlFlow::Continue;
+        }
+        let Ok(app_state) = state_clone.try_borrow() else {
+            return glib::ControlFlow::Continue;
+        };
+        let path = app_state.current_song.as_ref().map(|song| song.file_path.clone());
+        let position = app_state.player.as_ref().and_then(|player| player.position());
+        drop(app_state);
+
+        let mut view = view.borrow_mut();
+        if view.loaded != path {
+            view.show(path);
+        }
+        if let Some(position) = position {
+            view.highlight(position);
+        }
+        glib::ControlFlow::Continue
+    });
+
+    revealer
+}
+
+// Coloca [mm:ss.xx] no início da linha do cursor e desce para a próxima
+fn insert_timestamp(text_view: &TextView, position: Duration) {
+    let buffer = text_view.buffer();
+    let mut start = buffer.iter_at_mark(&buffer.get_insert());
+    start.set_line_offset(0);
+    let mut end = start;
+    if !end.ends_line() {
+        end.forward_to_line_end();
+    }
+
+    let line = buffer.text(&start, &end, false).to_string();
+    let text = lyrics::parse_lrc(&line)
+        .first()
+        .filter(|parsed| parsed.time.is_some())
+        .map(|parsed| parsed.text.clone())
+        .unwrap_or(line);
+
+    buffer.delete(&mut start, &mut end);
+    buffer.insert(&mut start, &format!("{}{}", format_timestamp(position), text));
+    if !start.forward_line() {
+        start = buffer.end_iter();
+    }
+    buffer.place_cursor(&start);
+    text_view.scroll_to_mark(&buffer.get_insert(), 0.1, false, 0.0, 0.0);
+}
+
+pub fn show_lyrics_editor<F>(parent: &ApplicationWindow, state: &Rc<RefCell<AppState>>, audio: PathBuf, text: &str, on_saved: F)
+where
+    F: Fn() + 'static,
+{
+    let dialog = Window::builder()
+        .title("Edit Lyrics")
+        .transient_for(parent)
+        .default_width(500)
+        .default_height(600)
+        .build();
+
+    let content_area = Box::new(Orientation::Vertical, 10);
+    content_area.set_margin_top(15);
+    content_area.set_margin_bottom(15);
+    content_area.set_margin_start(15);
+    content_area.set_margin_end(15);
+
+    let hint = Label::new(Some(
+        "One line per lyric. Play the song and press \"Insert Timestamp\" as each line starts."
+    ));
+    hint.set_wrap(true);
+    hint.set_xalign(0.0);
+
+    let text_view = TextView::new();
+    text_view.set_monospace(true);
+    text_view.buffer().set_text(text);
+    text_view.buffer().place_cursor(&text_view.buffer().start_iter());
+    let scroll = ScrolledWindow::new();
+    scroll.set_vexpand(true);
+    scroll.set_child(Some(&text_view));
+
+    let timestamp_btn = Button::with_label("⏱ Insert Timestamp");
+    let state_clone = Rc::clone(state);
+    let text_view_clone = text_view.clone();
+    let audio_clone = audio.clone();
+    timestamp_btn.connect_clicked(move |_| {
+        let app_state = state_clone.borrow();
+        // Só vale o tempo da música que está sendo editada
+        let playing = app_state.current_song.as_ref().is_some_and(|song| song.file_path == audio_clone);
+        let position = app_state.player.as_ref().and_then(|p| p.position()).filter(|_| playing);
+        drop(app_state);
+        match position {
+            Some(position) => insert_timestamp(&text_view_clone, position),
+            None => println!("Play the song to insert timestamps"),
+        }
+    });
+
+    let cancel_btn = Button::with_label("Cancel");
+    let dialog_clone = dialog.clone();
+    cancel_btn.connect_clicked(move |_| {
+        dialog_clone.close();
+    });
+
+    let save_btn = Button::with_label("Save");
+    save_btn.add_css_class("suggested-action");
+    let dialog_clone = dialog.clone();
+    save_btn.connect_clicked(move |_| {
+        let buffer = text_view.buffer();
+        let text = buffer.text(&buffer.start_iter(), &buffer.end_iter(), false);
+        match lyrics::save_lrc(&audio, &text) {
+            Ok(path) => {
+                println!("Lyrics saved to {}", path.display());
+                on_saved();
+                dialog_clone.close();
+            }
+            Err(e) => {
+                let alert = gtk4::AlertDialog::builder()
+                    .modal(true)
+                    .message("Could not save lyrics")
+                    .detail(e.to_string())
+                    .build();
+                alert.show(Some(&dialog_clone));
+            }
+        }
+    });
+
+    let button_box = Box::new(Orientation::Horizontal, 10);
+    let spacer = Box::new(Orientation::Horizontal, 0);
+    spacer.set_hexpand(true);
+    button_box.append(&timestamp_btn);
+    button_box.append(&spacer);
+    button_box.append(&cancel_btn);
+    button_box.append(&save_btn);
+
+    content_area.append(&hint);
+    content_area.append(&scroll);
+    content_area.append(&button_box);
+
+    dialog.set_child(Some(&content_area));
+    dialog.present();
+}
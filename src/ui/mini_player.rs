@@ -0,0 +1,251 @@
+use glib::translate::ToGlibPtr;
+use gtk4::prelude::*;
+use gtk4::{ApplicationWindow, Box, Button, Image, Label, Orientation, Scale, Window};
+use std::cell::RefCell;
+use std::os::raw::{c_int, c_ulong, c_void};
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::time::Duration;
+
+use crate::AppState;
+use crate::ui::notification::load_cover_icon;
+use crate::ui::song_list::format_duration;
+
+// O GTK 4 não deixa o app posicionar janelas; no X11 dá para fazer pela Xlib.
+// No Wayland quem decide é o compositor, e a posição não é salva
+#[link(name = "X11")]
+extern "C" {
+    fn XMoveWindow(display: *mut c_void, window: c_ulong, x: c_int, y: c_int) -> c_int;
+    fn XDefaultRootWindow(display: *mut c_void) -> c_ulong;
+    fn XTranslateCoordinates(
+        display: *mut c_void,
+        source: c_ulong,
+        target: c_ulong,
+        source_x: c_int,
+        source_y: c_int,
+        target_x: *mut c_int,
+        target_y: *mut c_int,
+        child: *mut c_ulong,
+    ) -> c_int;
+}
+
+// Parte do backend X11 da libgtk-4, sem binding no gdk4 0.7
+extern "C" {
+    fn gdk_x11_display_get_xdisplay(display: *mut gtk4::gdk::ffi::GdkDisplay) -> *mut c_void;
+    fn gdk_x11_surface_get_xid(surface: *mut gtk4::gdk::ffi::GdkSurface) -> c_ulong;
+}
+
+fn x11_window(window: &Window) -> Option<(*mut c_void, c_ulong)> {
+    if !window.is_realized() {
+        return None;
+    }
+    let surface = window.surface();
+    let display = surface.display();
+    if display.type_().name() != "GdkX11Display" {
+        return None;
+    }
+    unsafe {
+        let xdisplay = gdk_x11_display_get_xdisplay(display.to_glib_none().0);
+        let xid = gdk_x11_surface_get_xid(surface.to_glib_none().0);
+        (!xdisplay.is_null() && xid != 0).then_some((xdisplay, xid))
+    }
+}
+
+fn window_position(window: &Window) -> Option<(i32, i32)> {
+    let (display, xid) = x11_window(window)?;
+    let (mut x, mut y, mut child) = (0, 0, 0);
+    let found = unsafe {
+        XTranslateCoordinates(display, xid, XDefaultRootWindow(display), 0, 0, &mut x, &mut y, &mut child)
+    };
+    (found != 0).then_some((x, y))
+}
+
+fn move_window(window: &Window, (x, y): (i32, i32)) {
+    if let Some((display, xid)) = x11_window(window) {
+        unsafe {
+            XMoveWindow(display, xid, x, y);
+        }
+    }
+}
+
+// Troca a janela principal por uma janela compacta; fechar a compacta traz a principal de volta.
+// A principal só fica escondida, então continua segurando o app aberto
+pub fn show_mini_player(main_window: &ApplicationWindow, state: &Rc<RefCell<AppState>>) {
+    let (width, height, position) = {
+        let settings = &state.borrow().settings;
+        (settings.mini_width, settings.mini_height, settings.mini_position)
+    };
+    let window = Window::builder()
+        .title("Librefy")
+        .default_width(width)
+        .default_height(height)
+        .build();
+    window.set_application(main_window.application().as_ref());
+
+    let content = Box::new(Orientation::Horizontal, 12);
+    content.add_css_class("player-controls");
+    content.set_margin_top(10);
+    content.set_margin_bottom(10);
+    content.set_margin_start(10);
+    content.set_margin_end(10);
+
+    let cover = Image::from_icon_name("audio-x-generic-symbolic");
+    cover.set_pixel_size(72);
+    cover.add_css_class("album-art");
+    cover.set_valign(gtk4::Align::Center);
+    content.append(&cover);
+
+    let right = Box::new(Orientation::Vertical, 4);
+    right.set_hexpand(true);
+    right.set_valign(gtk4::Align::Center);
+
+    let title = Label::new(Some("No song playing"));
+    title.add_css_class("song-title");
+    title.set_halign(gtk4::Align::Start);
+    title.set_ellipsize(gtk4::pango::EllipsizeMode::End);
+    let artist = Label::new(None);
+    artist.add_css_class("song-artist");
+    artist.set_halign(gtk4::Align::Start);
+    artist.set_ellipsize(gtk4::pango::EllipsizeMode::End);
+    right.append(&title);
+    right.append(&artist);
+
+    let seek_bar = Scale::with_range(Orientation::Horizontal, 0.0, 1.0, 1.0);
+    seek_bar.set_draw_value(false);
+    seek_bar.set_hexpand(true);
+    let time_label = Label::new(Some("0:00 / 0:00"));
+    time_label.add_css_class("time-label");
+    let seek_box = Box::new(Orientation::Horizontal, 6);
+    seek_box.append(&seek_bar);
+    seek_box.append(&time_label);
+    right.append(&seek_box);
+
+    let buttons = Box::new(Orientation::Horizontal, 6);
+    let prev_btn = Button::with_label("⏮");
+    let play_btn = Button::with_label("⏯");
+    play_btn.add_css_class("suggested-action");
+    let next_btn = Button::with_label("⏭");
+    let expand_btn = Button::with_label("⤢");
+    expand_btn.set_tooltip_text(Some("Back to the full window"));
+    let spacer = Box::new(Orientation::Horizontal, 0);
+    spacer.set_hexpand(true);
+    buttons.append(&prev_btn);
+    buttons.append(&play_btn);
+    buttons.append(&next_btn);
+    buttons.append(&spacer);
+    buttons.append(&expand_btn);
+    right.append(&buttons);
+    content.append(&right);
+
+    let state_clone = Rc::clone(state);
+    prev_btn.connect_clicked(move |_| {
+        if !crate::play_previous_track(&state_clone) {
+            println!("No previous track");
+        }
+    });
+    let state_clone = Rc::clone(state);
+    play_btn.connect_clicked(move |_| {
+        crate::toggle_playback(&state_clone);
+    });
+    let state_clone = Rc::clone(state);
+    next_btn.connect_clicked(move |_| {
+        if !crate::play_next_track(&state_clone) {
+            println!("Queue is empty");
+        }
+    });
+    let window_clone = window.clone();
+    expand_btn.connect_clicked(move |_| {
+        window_clone.close();
+    });
+
+    // Só o arraste do usuário; os valores do timer não passam por aqui
+    let state_clone = Rc::clone(state);
+    seek_bar.connect_change_value(move |_, _, value| {
+        if let Some(ref mut player) = state_clone.borrow_mut().player {
+            if let Err(e) = player.seek(Duration::from_secs_f64(value.max(0.0))) {
+                eprintln!("Failed to seek: {}", e);
+            }
+        }
+        glib::Propagation::Proceed
+    });
+
+    // Acompanha a música atual, que também muda pela janela principal e pelo MPRIS
+    let shown: Rc<RefCell<Option<PathBuf>>> = Rc::new(RefCell::new(None));
+    let update = {
+        let state = Rc::clone(state);
+        let window = window.downgrade();
+        move || {
+            if window.upgrade().is_none() {
+                return glib::ControlFlow::Break;
+            }
+            let Ok(app_state) = state.try_borrow() else {
+                return glib::ControlFlow::Continue;
+            };
+            let player = app_state.player.as_ref();
+            let song = app_state.current_song.as_ref();
+
+            if shown.borrow().as_ref() != song.map(|song| &song.file_path) {
+                *shown.borrow_mut() = song.map(|song| song.file_path.clone());
+                match song {
+                    Some(song) => {
+                        title.set_text(&song.title);
+                        artist.set_text(&song.artist);
+                        cover.set_icon_name(Some("audio-x-generic-symbolic"));
+                        let cover = cover.clone();
+                        let shown = Rc::clone(&shown);
+                        let path = song.file_path.clone();
+                        load_cover_icon(song, move |icon| {
+                            // Só se ainda for a mesma música
+                            if let Some(icon) = icon.filter(|_| shown.borrow().as_ref() == Some(&path)) {
+                                cover.set_from_gicon(&icon);
+                            }
+                        });
+                    }
+                    None => {
+                        title.set_text("No song playing");
+                        artist.set_text("");
+                        cover.set_icon_name(Some("audio-x-generic-symbolic"));
+                    }
+                }
+            }
+
+            let position = player.and_then(|p| p.position()).unwrap_or_default().as_secs();
+            let length = song.and_then(|song| song.duration).unwrap_or(0);
+            seek_bar.set_range(0.0, length.max(1) as f64);
+            seek_bar.set_value(position as f64);
+            seek_bar.set_sensitive(length > 0);
+            time_label.set_text(&format!("{} / {}", format_duration(position), format_duration(length)));
+
+            let label = if player.is_some_and(|p| p.is_playing()) { "⏸" } else { "⏯" };
+            if play_btn.label().as_deref() != Some(label) {
+                play_btn.set_label(label);
+            }
+            glib::ControlFlow::Continue
+        }
+    };
+    update();
+    glib::timeout_add_local(Duration::from_millis(500), update);
+
+    let state_clone = Rc::clone(state);
+    let main_window_clone = main_window.clone();
+    window.connect_close_request(move |window| {
+        {
+            let mut app_state = state_clone.borrow_mut();
+            app_state.settings.mini_width = window.default_width();
+            app_state.settings.mini_height = window.default_height();
+            if let Some(position) = window_position(window) {
+                app_state.settings.mini_position = Some(position);
+            }
+        }
+        main_window_clone.set_visible(true);
+        main_window_clone.present();
+        glib::Propagation::Proceed
+    });
+
+    window.set_child(Some(&content));
+    window.present();
+    if let Some(position) = position {
+        move_window(&window, position);
+    }
+    main_window.set_visible(false);
+}
@@ -0,0 +1,16 @@
+pub mod duplicates;
+pub mod equalizer;
+pub mod file_drop;
+pub mod lyrics;
+pub mod mini_player;
+pub mod notification;
+pub mod organizer;
+pub mod preferences;
+pub mod search;
+pub mod smart_playlist;
+pub mod song_actions;
+pub mod song_list;
+pub mod song_object;
+pub mod stats;
+pub mod tag_editor;
+pub mod theme;
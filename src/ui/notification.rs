@@ -0,0 +1,88 @@
+use gio::prelude::*;
+use gtk4::prelude::*;
+use gtk4::Application;
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+
+use crate::AppState;
+use crate::library::{tags, Song};
+
+const NOTIFICATION_ID: &str = "now-playing";
+
+// Ações dos botões da notificação ("app.next" e "app.pause")
+pub fn install_actions(app: &Application, state: &Rc<RefCell<AppState>>) {
+    let next = gio::SimpleAction::new("next", None);
+    let state_clone = Rc::clone(state);
+    next.connect_activate(move |_, _| {
+        if !crate::play_next_track(&state_clone) {
+            println!("Queue is empty");
+        }
+    });
+    app.add_action(&next);
+
+    let pause = gio::SimpleAction::new("pause", None);
+    let state_clone = Rc::clone(state);
+    pause.connect_activate(move |_, _| {
+        crate::pause_playback(&state_clone);
+    });
+    app.add_action(&pause);
+}
+
+thread_local! {
+    // Última notificação pedida; capas que chegam atrasadas de músicas anteriores são descartadas
+    static LATEST: Cell<u64> = const { Cell::new(0) };
+}
+
+// A capa embutida é lida fora da thread da interface; `done` recebe o ícone depois
+pub fn load_cover_icon<F: FnOnce(Option<gio::Icon>) + 'static>(song: &Song, done: F) {
+    if let Some(path) = song.get_cover_path().filter(|path| path.exists()) {
+        done(Some(gio::FileIcon::new(&gio::File::for_path(path)).upcast()));
+        return;
+    }
+    let path = song.file_path.clone();
+    let handle = gio::spawn_blocking(move || tags::read_cover(&path));
+    glib::MainContext::default().spawn_local(async move {
+        let data = handle.await.ok().flatten();
+        done(data.map(|data| gio::BytesIcon::new(&glib::Bytes::from_owned(data)).upcast()));
+    });
+}
+
+pub fn notify_track_change(song: &Song) {
+    let Some(app) = gio::Application::default() else {
+        return;
+    };
+    // Com a janela em foco a música já aparece na barra do player
+    let focused = app
+        .downcast_ref::<Application>()
+        .and_then(|app| app.active_window())
+        .is_some_and(|window| window.is_active());
+    if focused {
+        app.withdraw_notification(NOTIFICATION_ID);
+        return;
+    }
+
+    let notification = gio::Notification::new(&song.title);
+    let body = if song.album.is_empty() || song.album == "Unknown Album" {
+        song.artist.clone()
+    } else {
+        format!("{}\n{}", song.artist, song.album)
+    };
+    notification.set_body(Some(&body));
+    notification.add_button("Pause", "app.pause");
+    notification.add_button("Next", "app.next");
+
+    let request = LATEST.with(|latest| {
+        latest.set(latest.get() + 1);
+        latest.get()
+    });
+    load_cover_icon(song, move |icon| {
+        if LATEST.with(Cell::get) != request {
+            return;
+        }
+        if let Some(icon) = icon {
+            notification.set_icon(&icon);
+        }
+        // A mesma id substitui a notificação da música anterior
+        app.send_notification(Some(NOTIFICATION_ID), &notification);
+    });
+}
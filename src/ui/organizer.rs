@@ -0,0 +1,288 @@
+use gtk4::prelude::*;
+use gtk4::{
+    ApplicationWindow, Box, Button, DropDown, Entry, FileDialog, Label, Orientation,
+    ScrolledWindow, TextView, Window
+};
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::Duration;
+
+use crate::AppState;
+use crate::config::default_music_dir;
+use crate::library::organize::{ConflictPolicy, MoveKind, OrganizePlan, DEFAULT_PATTERN};
+use crate::ui::song_object::SongObject;
+
+fn describe_plan(plan: &OrganizePlan) -> String {
+    let relative = |path: &Path| {
+        path.strip_prefix(&plan.root)
+            .map(|p| p.display().to_string())
+            .unwrap_or_else(|_| path.display().to_string())
+    };
+
+    let mut lines = Vec::new();
+    for planned in &plan.moves {
+        let marker = match (planned.kind, planned.conflict, planned.copy) {
+            (MoveKind::Cover, _, true) => "🖼 copy",
+            (MoveKind::Cover, _, false) => "🖼",
+            (MoveKind::Song, true, _) => "⚠",
+            (MoveKind::Song, false, _) => "🎵",
+        };
+        lines.push(format!("{} {}\n    → {}", marker, relative(&planned.from), relative(&planned.to)));
+    }
+    for (path, reason) in &plan.skipped {
+        lines.push(format!("⏭ {}\n    skipped: {}", relative(path), reason));
+    }
+    if lines.is_empty() {
+        lines.push("Everything is already in place.".to_string());
+    }
+    lines.join("\n")
+}
+
+// Organizador: prévia de todas as mudanças antes de mexer em qualquer arquivo
+pub fn show_organizer_dialog<F>(parent: &ApplicationWindow, state: &Rc<RefCell<AppState>>, on_done: F)
+where
+    F: Fn() + 'static,
+{
+    let dialog = Window::builder()
+        .title("Organize Files")
+        .transient_for(parent)
+        .modal(true)
+        .default_width(700)
+        .default_height(550)
+        .build();
+
+    let content_area = Box::new(Orientation::Vertical, 10);
+    content_area.set_margin_top(15);
+    content_area.set_margin_bottom(15);
+    content_area.set_margin_start(15);
+    content_area.set_margin_end(15);
+
+    let default_root = state.borrow().settings.library_folders.first().cloned().unwrap_or_else(default_music_dir);
+    let root = Rc::new(RefCell::new(default_root));
+    let plan: Rc<RefCell<Option<OrganizePlan>>> = Rc::new(RefCell::new(None));
+
+    let root_box = Box::new(Orientation::Horizontal, 10);
+    let root_label = Label::new(Some(&root.borrow().display().to_string()));
+    root_label.set_hexpand(true);
+    root_label.set_halign(gtk4::Align::Start);
+    root_label.set_ellipsize(gtk4::pango::EllipsizeMode::Middle);
+    let root_btn = Button::with_label("Choose Folder…");
+    root_box.append(&Label::new(Some("Destination:")));
+    root_box.append(&root_label);
+    root_box.append(&root_btn);
+
+    let pattern_entry = Entry::new();
+    pattern_entry.set_text(DEFAULT_PATTERN);
+    pattern_entry.set_tooltip_text(Some(
+        "Fields: {title} {artist} {album} {albumartist} {genre} {year} {track} {disc}\nUse {track:02} to pad numbers, / for folders"
+    ));
+
+    let labels: Vec<&str> = ConflictPolicy::ALL.iter().map(|p| p.label()).collect();
+    let conflict_dropdown = DropDown::from_strings(&labels);
+    conflict_dropdown.set_selected(1);
+
+    let preview_view = TextView::new();
+    preview_view.set_editable(false);
+    preview_view.set_monospace(true);
+    let preview_scroll = ScrolledWindow::new();
+    preview_scroll.set_vexpand(true);
+    preview_scroll.set_child(Some(&preview_view));
+
+    let summary_label = Label::new(Some("Preview the changes before organizing."));
+    summary_label.set_halign(gtk4::Align::Start);
+
+    let preview_btn = Button::with_label("Preview");
+    let organize_btn = Button::with_label("Organize");
+    organize_btn.add_css_class("suggested-action");
+    organize_btn.set_sensitive(false);
+
+    // Refaz a prévia com as opções atuais; devolve se ela mudou
+    let refresh_preview = {
+        let state = Rc::clone(state);
+        let plan = Rc::clone(&plan);
+        let root = Rc::clone(&root);
+        let pattern_entry = pattern_entry.clone();
+        let conflict_dropdown = conflict_dropdown.clone();
+        let preview_view = preview_view.clone();
+        let summary_label = summary_label.clone();
+        let organize_btn = organize_btn.clone();
+        Rc::new(move || -> bool {
+            let policy = ConflictPolicy::ALL[conflict_dropdown.selected() as usize];
+            let pattern = pattern_entry.text().to_string();
+            let result = state
+                .borrow()
+                .library
+                .plan_organize(&root.borrow(), &pattern, policy);
+
+            match result {
+                Ok(new_plan) => {
+                    pattern_entry.remove_css_class("error");
+                    organize_btn.set_sensitive(!new_plan.moves.is_empty());
+                    if plan.borrow().as_ref() == Some(&new_plan) {
+                        return false;
+                    }
+                    let songs = new_plan.moves.iter().filter(|m| m.kind == MoveKind::Song).count();
+                    let conflicts = new_plan.moves.iter().filter(|m| m.conflict).count();
+                    summary_label.set_text(&format!(
+                        "{} songs to move, {} cover images, {} conflicts, {} skipped",
+                        songs,
+                        new_plan.moves.len() - songs,
+                        conflicts,
+                        new_plan.skipped.len()
+                    ));
+                    preview_view.buffer().set_text(&describe_plan(&new_plan));
+                    *plan.borrow_mut() = Some(new_plan);
+                }
+                Err(e) => {
+                    pattern_entry.add_css_class("error");
+                    summary_label.set_text(&format!("⚠ {}", e));
+                    preview_view.buffer().set_text("");
+                    organize_btn.set_sensitive(false);
+                    plan.borrow_mut().take();
+                }
+            }
+            true
+        })
+    };
+
+    let refresh_clone = Rc::clone(&refresh_preview);
+    preview_btn.connect_clicked(move |_| {
+        refresh_clone();
+    });
+
+    // Qualquer mudança nas opções refaz a prévia, depois de uma pausa na digitação
+    let schedule_preview = {
+        let refresh = Rc::clone(&refresh_preview);
+        let pending: Rc<RefCell<Option<glib::SourceId>>> = Rc::new(RefCell::new(None));
+        let organize_btn = organize_btn.clone();
+        Rc::new(move || {
+            organize_btn.set_sensitive(false);
+            if let Some(source) = pending.borrow_mut().take() {
+                source.remove();
+            }
+            let refresh = Rc::clone(&refresh);
+            let pending_clone = Rc::clone(&pending);
+            let source = glib::timeout_add_local_once(Duration::from_millis(400), move || {
+                pending_clone.borrow_mut().take();
+                refresh();
+            });
+            pending.borrow_mut().replace(source);
+        })
+    };
+    let schedule_clone = Rc::clone(&schedule_preview);
+    pattern_entry.connect_changed(move |_| schedule_clone());
+    let schedule_clone = Rc::clone(&schedule_preview);
+    conflict_dropdown.connect_selected_notify(move |_| schedule_clone());
+
+    let dialog_clone = dialog.clone();
+    let root_clone = Rc::clone(&root);
+    let root_label_clone = root_label.clone();
+    let schedule_clone = Rc::clone(&schedule_preview);
+    root_btn.connect_clicked(move |_| {
+        let chooser = FileDialog::builder()
+            .title("Select Destination Folder")
+            .modal(true)
+            .build();
+
+        let root = Rc::clone(&root_clone);
+        let root_label = root_label_clone.clone();
+        let schedule = Rc::clone(&schedule_clone);
+        chooser.select_folder(Some(&dialog_clone), gio::Cancellable::NONE, move |result| {
+            match result {
+                Ok(folder) => {
+                    if let Some(path) = folder.path() {
+                        root_label.set_text(&path.display().to_string());
+                        *root.borrow_mut() = path;
+                        schedule();
+                    }
+                }
+                Err(e) => {
+                    eprintln!("Error selecting folder: {}", e);
+                }
+            }
+        });
+    });
+
+    let state_clone = Rc::clone(state);
+    let dialog_clone = dialog.clone();
+    let parent_clone = parent.clone();
+    let summary_label_clone = summary_label.clone();
+    organize_btn.connect_clicked(move |_| {
+        // A biblioteca ou o disco podem ter mudado desde a prévia; só aplica o que foi mostrado
+        if refresh_preview() {
+            summary_label_clone.set_text("The files changed since the preview. Check it and organize again.");
+            return;
+        }
+        let Some(plan) = plan.borrow_mut().take() else {
+            return;
+        };
+        dialog_clone.close();
+
+        let (errors, songs, store) = {
+            let mut app_state = state_clone.borrow_mut();
+            let app_state = &mut *app_state;
+            let errors = app_state.library.apply_organize(&plan);
+
+            // A fila e a música atual guardam cópias das músicas, com o caminho antigo
+            let failed: HashSet<&PathBuf> = errors.iter().map(|(path, _)| path).collect();
+            let moved: HashMap<PathBuf, PathBuf> = plan
+                .moves
+                .iter()
+                .filter(|m| m.kind == MoveKind::Song && !failed.contains(&m.from))
+                .map(|m| (m.from.clone(), m.to.clone()))
+                .collect();
+            app_state.queue.relocate(&moved);
+            if let Some(song) = app_state.current_song.as_mut() {
+                if let Some(to) = moved.get(&song.file_path) {
+                    song.file_path = to.clone();
+                }
+            }
+
+            let songs: Vec<SongObject> = app_state.library.songs.iter().cloned().map(SongObject::new).collect();
+            (errors, songs, app_state.song_store.clone())
+        };
+        // Os caminhos mudaram, então o modelo é refeito por inteiro
+        store.splice(0, store.n_items(), &songs);
+        println!("Organized {} files", plan.moves.len() - errors.len());
+        on_done();
+
+        if !errors.is_empty() {
+            let detail: Vec<String> = errors
+                .iter()
+                .map(|(path, e)| format!("{}: {}", path.display(), e))
+                .collect();
+            let alert = gtk4::AlertDialog::builder()
+                .modal(true)
+                .message(format!("{} files could not be moved", errors.len()))
+                .detail(detail.join("\n"))
+                .build();
+            alert.show(Some(&parent_clone));
+        }
+    });
+
+    let cancel_btn = Button::with_label("Cancel");
+    let dialog_clone = dialog.clone();
+    cancel_btn.connect_clicked(move |_| {
+        dialog_clone.close();
+    });
+
+    content_area.append(&root_box);
+    content_area.append(&Label::new(Some("Pattern:")));
+    content_area.append(&pattern_entry);
+    content_area.append(&Label::new(Some("When the destination already exists:")));
+    content_area.append(&conflict_dropdown);
+    content_area.append(&preview_scroll);
+    content_area.append(&summary_label);
+
+    let button_box = Box::new(Orientation::Horizontal, 10);
+    button_box.set_halign(gtk4::Align::End);
+    button_box.append(&cancel_btn);
+    button_box.append(&preview_btn);
+    button_box.append(&organize_btn);
+    content_area.append(&button_box);
+
+    dialog.set_child(Some(&content_area));
+    dialog.present();
+}
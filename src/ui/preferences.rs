@@ -0,0 +1,572 @@
+use adw::prelude::*;
+use gtk4::gdk::RGBA;
+use gtk4::{
+    ApplicationWindow, Box, Button, ColorDialog, ColorDialogButton, Entry, FileDialog, PasswordEntry, SpinButton,
+    StringList, Switch
+};
+use libadwaita as adw;
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use std::time::Duration;
+
+use crate::AppState;
+use crate::config::{AudioOutput, ColorScheme, OpenAction, ScrobbleService};
+use crate::ui::theme;
+
+// Verde folha do tema embutido, mostrado quando não há cor escolhida
+const DEFAULT_ACCENT: &str = "#2E8B57";
+
+thread_local! {
+    // Gravação agendada; campos de texto mudam a cada tecla
+    static PENDING_SAVE: RefCell<Option<glib::SourceId>> = const { RefCell::new(None) };
+}
+
+fn save_all(state: &Rc<RefCell<AppState>>) {
+    if let Some(source) = PENDING_SAVE.with(|pending| pending.borrow_mut().take()) {
+        source.remove();
+    }
+    let app_state = state.borrow();
+    app_state.settings.save();
+    app_state.listening.save();
+    app_state.opening.save();
+    app_state.notifications.save();
+    app_state.scrobbler.settings.save();
+}
+
+fn save_soon(state: &Rc<RefCell<AppState>>) {
+    if let Some(source) = PENDING_SAVE.with(|pending| pending.borrow_mut().take()) {
+        source.remove();
+    }
+    let state = Rc::clone(state);
+    let source = glib::timeout_add_local_once(Duration::from_millis(500), move || {
+        PENDING_SAVE.with(|pending| pending.borrow_mut().take());
+        save_all(&state);
+    });
+    PENDING_SAVE.with(|pending| *pending.borrow_mut() = Some(source));
+}
+
+// Aplica a mudança e grava as preferências logo em seguida
+fn saved<T>(state: &Rc<RefCell<AppState>>, on_change: impl Fn(T) + 'static) -> impl Fn(T) + 'static {
+    let state = Rc::clone(state);
+    move |value| {
+        on_change(value);
+        save_soon(&state);
+    }
+}
+
+fn switch_row(title: &str, subtitle: &str, active: bool, on_change: impl Fn(bool) + 'static) -> adw::ActionRow {
+    let switch = Switch::new();
+    switch.set_valign(gtk4::Align::Center);
+    switch.set_active(active);
+    switch.connect_active_notify(move |switch| on_change(switch.is_active()));
+
+    let row = adw::ActionRow::builder().title(title).subtitle(subtitle).build();
+    row.add_suffix(&switch);
+    row.set_activatable_widget(Some(&switch));
+    row
+}
+
+fn spin_row(title: &str, subtitle: &str, range: (f64, f64), value: f64, on_change: impl Fn(f64) + 'static) -> adw::ActionRow {
+    let spin = SpinButton::with_range(range.0, range.1, 1.0);
+    spin.set_valign(gtk4::Align::Center);
+    spin.set_value(value);
+    spin.connect_value_changed(move |spin| on_change(spin.value()));
+
+    let row = adw::ActionRow::builder().title(title).subtitle(subtitle).build();
+    row.add_suffix(&spin);
+    row
+}
+
+fn entry_row(title: &str, entry: &impl IsA<gtk4::Widget>) -> adw::ActionRow {
+    entry.set_valign(gtk4::Align::Center);
+    entry.set_hexpand(true);
+    let row = adw::ActionRow::builder().title(title).build();
+    row.add_suffix(entry);
+    row
+}
+
+fn text_row(title: &str, text: &str, placeholder: &str, on_change: impl Fn(String) + 'static) -> adw::ActionRow {
+    let entry = Entry::new();
+    entry.set_text(text);
+    entry.set_placeholder_text(Some(placeholder));
+    entry.connect_changed(move |entry| on_change(entry.text().trim().to_string()));
+    entry_row(title, &entry)
+}
+
+fn secret_row(title: &str, text: &str, on_change: impl Fn(String) + 'static) -> adw::ActionRow {
+    let entry = PasswordEntry::new();
+    entry.set_show_peek_icon(true);
+    entry.set_text(text);
+    entry.connect_changed(move |entry| on_change(entry.text().trim().to_string()));
+    entry_row(title, &entry)
+}
+
+fn combo_row(title: &str, labels: &[&str], selected: usize, on_change: impl Fn(usize) + 'static) -> adw::ComboRow {
+    let row = adw::ComboRow::builder()
+        .title(title)
+        .model(&StringList::new(labels))
+        .build();
+    row.set_selected(selected as u32);
+    row.connect_selected_notify(move |row| on_change(row.selected() as usize));
+    row
+}
+
+// Uma linha por pasta; a lista é refeita quando uma pasta entra ou sai
+fn refresh_folder_rows(
+    group: &adw::PreferencesGroup,
+    rows: &Rc<RefCell<Vec<adw::ActionRow>>>,
+    state: &Rc<RefCell<AppState>>
+) {
+    for row in rows.borrow_mut().drain(..) {
+        group.remove(&row);
+    }
+    let folders = state.borrow().settings.library_folders.clone();
+    for folder in folders {
+        let row = adw::ActionRow::builder().title(folder.display().to_string()).build();
+        let remove_btn = Button::from_icon_name("list-remove-symbolic");
+        remove_btn.set_valign(gtk4::Align::Center);
+        remove_btn.set_tooltip_text(Some("Remove from library folders"));
+        row.add_suffix(&remove_btn);
+
+        let group_clone = group.clone();
+        let rows_clone = Rc::clone(rows);
+        let state_clone = Rc::clone(state);
+        remove_btn.connect_clicked(move |_| {
+            state_clone.borrow_mut().settings.library_folders.retain(|path| *path != folder);
+            refresh_folder_rows(&group_clone, &rows_clone, &state_clone);
+            save_soon(&state_clone);
+        });
+
+        group.add(&row);
+        rows.borrow_mut().push(row);
+    }
+}
+
+fn create_library_page(
+    window: &adw::PreferencesWindow,
+    state: &Rc<RefCell<AppState>>,
+    content: &Box
+) -> adw::PreferencesPage {
+    let page = adw::PreferencesPage::builder()
+        .title("Library")
+        .icon_name("folder-music-symbolic")
+        .build();
+
+    let add_btn = Button::with_label("Add Folder…");
+    let scan_btn = Button::with_label("Scan Now");
+    let buttons = Box::new(gtk4::Orientation::Horizontal, 6);
+    buttons.set_valign(gtk4::Align::Center);
+    buttons.append(&add_btn);
+    buttons.append(&scan_btn);
+    let buttons_row = adw::ActionRow::builder().title("Manage folders").build();
+    buttons_row.add_suffix(&buttons);
+
+    let folders_group = adw::PreferencesGroup::builder()
+        .title("Library Folders")
+        .description("Folders scanned for music")
+        .build();
+    let folder_rows = Rc::new(RefCell::new(Vec::new()));
+    refresh_folder_rows(&folders_group, &folder_rows, state);
+
+    let window_clone = window.clone();
+    let group_clone = folders_group.clone();
+    let rows_clone = Rc::clone(&folder_rows);
+    let state_clone = Rc::clone(state);
+    add_btn.connect_clicked(move |_| {
+        let chooser = FileDialog::builder()
+            .title("Select Library Folder")
+            .modal(true)
+            .build();
+
+        let group = group_clone.clone();
+        let rows = Rc::clone(&rows_clone);
+        let state = Rc::clone(&state_clone);
+        chooser.select_folder(Some(&window_clone), gio::Cancellable::NONE, move |result| {
+            match result {
+                Ok(folder) => {
+                    let Some(path) = folder.path() else {
+                        return;
+                    };
+                    {
+                        let folders = &mut state.borrow_mut().settings.library_folders;
+                        if !folders.contains(&path) {
+                            folders.push(path);
+                        }
+                    }
+                    refresh_folder_rows(&group, &rows, &state);
+                    save_soon(&state);
+                }
+                Err(e) => {
+                    eprintln!("Error selecting folder: {}", e);
+                }
+            }
+        });
+    });
+
+    let state_clone = Rc::clone(state);
+    let content_clone = content.clone();
+    scan_btn.connect_clicked(move |_| {
+        crate::scan_and_refresh(&state_clone, &content_clone);
+    });
+
+    let scanning_group = adw::PreferencesGroup::builder().title("Scanning").build();
+    scanning_group.add(&buttons_row);
+    let state_clone = Rc::clone(state);
+    scanning_group.add(&switch_row(
+        "Scan on startup",
+        "Look for new files in the library folders when Librefy opens",
+        state.borrow().settings.scan_on_startup,
+        saved(state, move |active| state_clone.borrow_mut().settings.scan_on_startup = active),
+    ));
+    let state_clone = Rc::clone(state);
+    scanning_group.add(&switch_row(
+        "Add opened files to the library",
+        "Files opened from the file manager or dropped on the window",
+        state.borrow().opening.add_to_library,
+        saved(state, move |active| state_clone.borrow_mut().opening.add_to_library = active),
+    ));
+    let state_clone = Rc::clone(state);
+    scanning_group.add(&switch_row(
+        "Write ratings to tags",
+        "Store ratings and play counts in the audio files",
+        state.borrow().listening.sync_rating_tags,
+        saved(state, move |active| {
+            let mut app_state = state_clone.borrow_mut();
+            app_state.listening.sync_rating_tags = active;
+            app_state.library.sync_rating_tags = active;
+        }),
+    ));
+
+    let listening_group = adw::PreferencesGroup::builder()
+        .title("Play Counts")
+        .description("A track counts as played after whichever comes first")
+        .build();
+    let state_clone = Rc::clone(state);
+    listening_group.add(&spin_row(
+        "Percentage played",
+        "Of the track length",
+        (1.0, 100.0),
+        f64::from(state.borrow().listening.count_after_percent),
+        saved(state, move |value| state_clone.borrow_mut().listening.count_after_percent = value as u8),
+    ));
+    let state_clone = Rc::clone(state);
+    listening_group.add(&spin_row(
+        "Seconds played",
+        "For long tracks",
+        (1.0, 3600.0),
+        state.borrow().listening.count_after_seconds as f64,
+        saved(state, move |value| state_clone.borrow_mut().listening.count_after_seconds = value as u64),
+    ));
+
+    page.add(&folders_group);
+    page.add(&scanning_group);
+    page.add(&listening_group);
+    page
+}
+
+fn create_playback_page(parent: &ApplicationWindow, state: &Rc<RefCell<AppState>>) -> adw::PreferencesPage {
+    let page = adw::PreferencesPage::builder()
+        .title("Playback")
+        .icon_name("media-playback-start-symbolic")
+        .build();
+
+    let playback_group = adw::PreferencesGroup::builder().title("Playback").build();
+    let actions = [OpenAction::Play, OpenAction::Enqueue];
+    let selected = actions.iter().position(|a| *a == state.borrow().opening.action).unwrap_or(0);
+    let state_clone = Rc::clone(state);
+    playback_group.add(&combo_row(
+        "Opening files",
+        &["Play immediately", "Add to queue"],
+        selected,
+        saved(state, move |index| state_clone.borrow_mut().opening.action = actions[index]),
+    ));
+
+    let state_clone = Rc::clone(state);
+    playback_group.add(&switch_row(
+        "Resume playback on startup",
+        "Continue the track that was playing when Librefy was closed",
+        state.borrow().settings.resume_playback,
+        saved(state, move |active| state_clone.borrow_mut().settings.resume_playback = active),
+    ));
+
+    let equalizer_btn = Button::with_label("Open…");
+    equalizer_btn.set_valign(gtk4::Align::Center);
+    let parent_clone = parent.clone();
+    let state_clone = Rc::clone(state);
+    equalizer_btn.connect_clicked(move |_| {
+        crate::ui::equalizer::show_equalizer_window(&parent_clone, &state_clone);
+    });
+    let equalizer_row = adw::ActionRow::builder().title("Equalizer").build();
+    equalizer_row.add_suffix(&equalizer_btn);
+    equalizer_row.set_activatable_widget(Some(&equalizer_btn));
+    playback_group.add(&equalizer_row);
+
+    let output_group = adw::PreferencesGroup::builder().title("Audio Output").build();
+    let labels: Vec<&str> = AudioOutput::ALL.iter().map(AudioOutput::label).collect();
+    let selected = AudioOutput::ALL
+        .iter()
+        .position(|o| *o == state.borrow().settings.audio_output)
+        .unwrap_or(0);
+    let state_clone = Rc::clone(state);
+    output_group.add(&combo_row("Output device", &labels, selected, saved(state, move |index| {
+        let output = AudioOutput::ALL[index];
+        let mut app_state = state_clone.borrow_mut();
+        app_state.settings.audio_output = output;
+        if let Some(ref mut player) = app_state.player {
+            if let Err(e) = player.set_audio_output(output) {
+                eprintln!("Failed to use audio output {}: {}", output.label(), e);
+            }
+        }
+    })));
+
+    page.add(&playback_group);
+    page.add(&output_group);
+    page
+}
+
+fn theme_labels(themes: &[String]) -> Vec<&str> {
+    let mut labels = vec!["Leaf Green (built-in)"];
+    labels.extend(themes.iter().map(String::as_str));
+    labels
+}
+
+fn selected_theme(state: &Rc<RefCell<AppState>>, themes: &[String]) -> usize {
+    state
+        .borrow()
+        .settings
+        .theme
+        .as_ref()
+        .and_then(|name| themes.iter().position(|t| t == name))
+        .map_or(0, |index| index + 1)
+}
+
+fn create_appearance_page(state: &Rc<RefCell<AppState>>) -> adw::PreferencesPage {
+    let page = adw::PreferencesPage::builder()
+        .title("Appearance")
+        .icon_name("applications-graphics-symbolic")
+        .build();
+
+    let style_group = adw::PreferencesGroup::builder().title("Style").build();
+    let labels: Vec<&str> = ColorScheme::ALL.iter().map(ColorScheme::label).collect();
+    let selected = ColorScheme::ALL
+        .iter()
+        .position(|c| *c == state.borrow().settings.color_scheme)
+        .unwrap_or(0);
+    let state_clone = Rc::clone(state);
+    style_group.add(&combo_row("Color scheme", &labels, selected, saved(state, move |index| {
+        crate::set_color_scheme(&state_clone, ColorScheme::ALL[index]);
+    })));
+
+    // Temas do usuário, lidos da pasta de temas
+    let themes = Rc::new(RefCell::new(theme::available_themes()));
+    let updating = Rc::new(Cell::new(false));
+    let state_clone = Rc::clone(state);
+    let themes_clone = Rc::clone(&themes);
+    let updating_clone = Rc::clone(&updating);
+    let theme_row = combo_row(
+        "Theme",
+        &theme_labels(&themes.borrow()),
+        selected_theme(state, &themes.borrow()),
+        saved(state, move |index: usize| {
+            if updating_clone.get() {
+                return;
+            }
+            let name = index.checked_sub(1).and_then(|index| themes_clone.borrow().get(index).cloned());
+            let manager = Rc::clone(&state_clone.borrow().theme);
+            manager.set_theme(name.as_deref());
+            state_clone.borrow_mut().settings.theme = name;
+        }),
+    );
+    style_group.add(&theme_row);
+
+    // A lista acompanha os arquivos que entram e saem da pasta
+    match gio::File::for_path(theme::themes_dir()).monitor_directory(gio::FileMonitorFlags::NONE, gio::Cancellable::NONE) {
+        Ok(monitor) => {
+            let row = theme_row.downgrade();
+            let state_clone = Rc::clone(state);
+            monitor.connect_changed(move |_, _, _, event| {
+                if !matches!(event, gio::FileMonitorEvent::Created | gio::FileMonitorEvent::Deleted) {
+                    return;
+                }
+                let Some(row) = row.upgrade() else {
+                    return;
+                };
+                themes.replace(theme::available_themes());
+                updating.set(true);
+                row.set_model(Some(&StringList::new(&theme_labels(&themes.borrow()))));
+                row.set_selected(selected_theme(&state_clone, &themes.borrow()) as u32);
+                updating.set(false);
+            });
+            theme_row.connect_destroy(move |_| {
+                monitor.cancel();
+            });
+        }
+        Err(e) => eprintln!("Failed to watch {}: {}", theme::themes_dir().display(), e),
+    }
+
+    let folder_btn = Button::with_label("Open Folder");
+    folder_btn.set_valign(gtk4::Align::Center);
+    folder_btn.connect_clicked(|_| {
+        let dir = theme::themes_dir();
+        if let Err(e) = std::fs::create_dir_all(&dir) {
+            eprintln!("Failed to create {}: {}", dir.display(), e);
+            return;
+        }
+        let uri = gio::File::for_path(&dir).uri();
+        if let Err(e) = gio::AppInfo::launch_default_for_uri(&uri, gio::AppLaunchContext::NONE) {
+            eprintln!("Failed to open {}: {}", dir.display(), e);
+        }
+    });
+    let folder_row = adw::ActionRow::builder()
+        .title("Custom themes")
+        .subtitle(theme::themes_dir().display().to_string())
+        .build();
+    folder_row.add_suffix(&folder_btn);
+    style_group.add(&folder_row);
+
+    // Cor de destaque escolhida, ou a da capa do álbum tocando
+    let accent_group = adw::PreferencesGroup::builder().title("Accent Color").build();
+    let color_btn = ColorDialogButton::new(Some(ColorDialog::builder().with_alpha(false).build()));
+    color_btn.set_valign(gtk4::Align::Center);
+    let chosen = state.borrow().settings.accent_color.as_deref().and_then(|color| RGBA::parse(color).ok());
+    color_btn.set_rgba(&chosen.unwrap_or_else(|| RGBA::parse(DEFAULT_ACCENT).unwrap_or(RGBA::BLACK)));
+
+    let updating = Rc::new(Cell::new(false));
+    let state_clone = Rc::clone(state);
+    let updating_clone = Rc::clone(&updating);
+    color_btn.connect_rgba_notify(move |button| {
+        if updating_clone.get() {
+            return;
+        }
+        state_clone.borrow_mut().settings.accent_color = Some(button.rgba().to_str().to_string());
+        theme::refresh_accent(&state_clone);
+        save_soon(&state_clone);
+    });
+
+    let reset_btn = Button::from_icon_name("edit-undo-symbolic");
+    reset_btn.set_valign(gtk4::Align::Center);
+    reset_btn.set_tooltip_text(Some("Use the theme's color"));
+    let state_clone = Rc::clone(state);
+    let color_btn_clone = color_btn.clone();
+    reset_btn.connect_clicked(move |_| {
+        state_clone.borrow_mut().settings.accent_color = None;
+        theme::refresh_accent(&state_clone);
+        save_soon(&state_clone);
+        updating.set(true);
+        color_btn_clone.set_rgba(&RGBA::parse(DEFAULT_ACCENT).unwrap_or(RGBA::BLACK));
+        updating.set(false);
+    });
+
+    let color_row = adw::ActionRow::builder().title("Accent color").build();
+    color_row.add_suffix(&color_btn);
+    color_row.add_suffix(&reset_btn);
+    accent_group.add(&color_row);
+
+    let state_clone = Rc::clone(state);
+    accent_group.add(&switch_row(
+        "Match album cover",
+        "Take the accent color from the cover of the playing track",
+        state.borrow().settings.accent_from_cover,
+        saved(state, move |active| {
+            state_clone.borrow_mut().settings.accent_from_cover = active;
+            theme::refresh_accent(&state_clone);
+        }),
+    ));
+
+    let group = adw::PreferencesGroup::builder().title("Window").build();
+    let state_clone = Rc::clone(state);
+    group.add(&switch_row(
+        "Remember window size",
+        "Reopen with the size and maximized state from last time",
+        state.borrow().settings.remember_window_size,
+        saved(state, move |active| state_clone.borrow_mut().settings.remember_window_size = active),
+    ));
+
+    page.add(&style_group);
+    page.add(&accent_group);
+    page.add(&group);
+    page
+}
+
+fn create_integrations_page(state: &Rc<RefCell<AppState>>) -> adw::PreferencesPage {
+    let page = adw::PreferencesPage::builder()
+        .title("Integrations")
+        .icon_name("network-server-symbolic")
+        .build();
+
+    let desktop_group = adw::PreferencesGroup::builder().title("Desktop").build();
+    let state_clone = Rc::clone(state);
+    desktop_group.add(&switch_row(
+        "Track change notifications",
+        "Only shown while the window is not focused",
+        state.borrow().notifications.track_change,
+        saved(state, move |active| state_clone.borrow_mut().notifications.track_change = active),
+    ));
+
+    let scrobble_group = adw::PreferencesGroup::builder()
+        .title("Scrobbling")
+        .description("Send listened tracks to ListenBrainz or a Last.fm-compatible service")
+        .build();
+    let settings = state.borrow().scrobbler.settings.clone();
+
+    let state_clone = Rc::clone(state);
+    scrobble_group.add(&switch_row("Enabled", "", settings.enabled, saved(state, move |active| {
+        state_clone.borrow_mut().scrobbler.settings.enabled = active;
+    })));
+
+    let services = [ScrobbleService::ListenBrainz, ScrobbleService::LastFm];
+    let selected = services.iter().position(|s| *s == settings.service).unwrap_or(0);
+    let state_clone = Rc::clone(state);
+    scrobble_group.add(&combo_row("Service", &["ListenBrainz", "Last.fm"], selected, saved(state, move |index| {
+        state_clone.borrow_mut().scrobbler.settings.service = services[index];
+    })));
+
+    let state_clone = Rc::clone(state);
+    scrobble_group.add(&text_row("Server URL", &settings.api_url, "Service default", saved(state, move |text| {
+        state_clone.borrow_mut().scrobbler.settings.api_url = text;
+    })));
+    let state_clone = Rc::clone(state);
+    scrobble_group.add(&secret_row("ListenBrainz token", &settings.token, saved(state, move |text| {
+        state_clone.borrow_mut().scrobbler.settings.token = text;
+    })));
+    let state_clone = Rc::clone(state);
+    scrobble_group.add(&text_row("Last.fm API key", &settings.api_key, "", saved(state, move |text| {
+        state_clone.borrow_mut().scrobbler.settings.api_key = text;
+    })));
+    let state_clone = Rc::clone(state);
+    scrobble_group.add(&secret_row("Last.fm API secret", &settings.api_secret, saved(state, move |text| {
+        state_clone.borrow_mut().scrobbler.settings.api_secret = text;
+    })));
+    let state_clone = Rc::clone(state);
+    scrobble_group.add(&secret_row("Last.fm session key", &settings.session_key, saved(state, move |text| {
+        state_clone.borrow_mut().scrobbler.settings.session_key = text;
+    })));
+
+    page.add(&desktop_group);
+    page.add(&scrobble_group);
+    page
+}
+
+// As mudanças valem na hora e são gravadas logo depois; o que faltar é gravado ao fechar a janela
+pub fn show_preferences_window(parent: &ApplicationWindow, state: &Rc<RefCell<AppState>>, content: &Box) {
+    let window = adw::PreferencesWindow::builder()
+        .title("Preferences")
+        .transient_for(parent)
+        .default_width(640)
+        .default_height(600)
+        .search_enabled(false)
+        .build();
+
+    window.add(&create_library_page(&window, state, content));
+    window.add(&create_playback_page(parent, state));
+    window.add(&create_appearance_page(state));
+    window.add(&create_integrations_page(state));
+
+    let state_clone = Rc::clone(state);
+    window.connect_close_request(move |_| {
+        save_all(&state_clone);
+        glib::Propagation::Proceed
+    });
+
+    window.present();
+}
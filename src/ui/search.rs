@@ -0,0 +1,254 @@
+use gtk4::prelude::*;
+use gtk4::{
+    ApplicationWindow, Box, Button, FlowBox, Label, SearchBar, SearchEntry, SelectionMode
+};
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use crate::{AppState, LibraryView};
+use crate::library::query::{Highlight, Query, QueryField};
+use crate::library::search::{match_score, normalize};
+use crate::library::Song;
+use crate::ui::song_list::{create_song_list_view, song_store};
+
+const MAX_GROUP_RESULTS: usize = 12;
+
+pub fn create_search_bar(window: &ApplicationWindow, state: &Rc<RefCell<AppState>>, content: &Box) -> SearchBar {
+    let entry = SearchEntry::new();
+    entry.set_placeholder_text(Some("Search… e.g. artist:\"Miles Davis\" year:1955..1960 -live"));
+    entry.set_hexpand(true);
+    // Debounce: só dispara "search-changed" depois de uma pausa na digitação
+    entry.set_search_delay(250);
+
+    let search_bar = SearchBar::new();
+    search_bar.set_child(Some(&entry));
+    search_bar.connect_entry(&entry);
+    search_bar.set_show_close_button(true);
+    // Começar a digitar em qualquer lugar da janela abre a busca
+    search_bar.set_key_capture_widget(Some(window));
+
+    // Ctrl+F alterna a barra de busca
+    let shortcuts = gtk4::ShortcutController::new();
+    let search_bar_clone = search_bar.clone();
+    shortcuts.add_shortcut(gtk4::Shortcut::new(
+        gtk4::ShortcutTrigger::parse_string("<Control>f"),
+        Some(gtk4::CallbackAction::new(move |_, _| {
+            search_bar_clone.set_search_mode(!search_bar_clone.is_search_mode());
+            true
+        })),
+    ));
+    window.add_controller(shortcuts);
+
+    let state_clone = Rc::clone(state);
+    let window_clone = window.clone();
+    let content_clone = content.clone();
+    entry.connect_search_changed(move |entry| {
+        let text = entry.text().to_string();
+        if text.trim().is_empty() {
+            clear_error(entry);
+            crate::refresh_view(&content_clone, &window_clone, &state_clone);
+        } else {
+            show_search_results(&content_clone, &state_clone, entry, &text);
+        }
+    });
+
+    let state_clone = Rc::clone(state);
+    let window_clone = window.clone();
+    let content_clone = content.clone();
+    search_bar.connect_search_mode_enabled_notify(move |search_bar| {
+        if !search_bar.is_search_mode() {
+            crate::refresh_view(&content_clone, &window_clone, &state_clone);
+        }
+    });
+
+    search_bar
+}
+
+fn clear_error(entry: &SearchEntry) {
+    entry.remove_css_class("error");
+    entry.set_tooltip_text(None);
+}
+
+// Caminhos das músicas da visão atual, ou None quando é a biblioteca inteira
+fn current_view_paths(state: &AppState) -> Option<HashSet<PathBuf>> {
+    let library = &state.library;
+    let songs: Vec<&Song> = match &state.current_view {
+        LibraryView::AllSongs => return None,
+        LibraryView::Playlist(index) => library
+            .playlists
+            .get(*index)
+            .map(|p| library.playlist_songs(p))
+            .unwrap_or_default(),
+        LibraryView::SmartPlaylist(index) => library
+            .smart_playlists
+            .get(*index)
+            .map(|p| library.evaluate_smart_playlist(p))
+            .unwrap_or_default(),
+        LibraryView::Artist(name) => library.songs.iter().filter(|s| &s.artist == name).collect(),
+        LibraryView::Album(name) => library.songs.iter().filter(|s| &s.album == name).collect(),
+    };
+    Some(songs.into_iter().map(|s| s.file_path.clone()).collect())
+}
+
+fn name_matches(name: &str, terms: &[String]) -> bool {
+    let name = normalize(name);
+    !terms.is_empty() && terms.iter().all(|term| match_score(term, &name, true).is_some())
+}
+
+fn show_search_results(content: &Box, state: &Rc<RefCell<AppState>>, entry: &SearchEntry, text: &str) {
+    let terms = match Query::parse(text) {
+        Ok(query) => query.highlight_terms(),
+        Err(_) => Vec::new(),
+    };
+
+    let results: Vec<Song> = {
+        let app_state = state.borrow();
+        match app_state.library.search_songs(text) {
+            Ok(songs) => {
+                clear_error(entry);
+                let view_paths = current_view_paths(&app_state);
+                songs
+                    .into_iter()
+                    .filter(|song| {
+                        view_paths
+                            .as_ref()
+                            .map(|paths| paths.contains(&song.file_path))
+                            .unwrap_or(true)
+                    })
+                    .cloned()
+                    .collect()
+            }
+            Err(e) => {
+                // Mostra o erro na própria entrada em vez de simplesmente não achar nada
+                entry.add_css_class("error");
+                entry.set_tooltip_text(Some(&e.to_string()));
+
+                while let Some(child) = content.first_child() {
+                    content.remove(&child);
+                }
+                let error_label = Label::new(Some(&format!("⚠ {}", e)));
+                error_label.add_css_class("title-4");
+                content.append(&error_label);
+                return;
+            }
+        }
+    };
+
+    while let Some(child) = content.first_child() {
+        content.remove(&child);
+    }
+
+    let header = Label::new(Some("Search Results"));
+    header.add_css_class("song-list-header");
+    header.set_halign(gtk4::Align::Start);
+    content.append(&header);
+
+    if results.is_empty() {
+        let empty_label = Label::new(Some(&format!("No results for \"{}\"", text)));
+        empty_label.add_css_class("title-4");
+        content.append(&empty_label);
+        return;
+    }
+
+    // Agrupa artistas e álbuns cujo próprio nome corresponde à busca
+    let artist_terms = Highlight::texts_for(&terms, QueryField::Artist);
+    let album_terms = Highlight::texts_for(&terms, QueryField::Album);
+    let mut artists: Vec<String> = Vec::new();
+    let mut albums: Vec<String> = Vec::new();
+    for song in &results {
+        if !artists.contains(&song.artist) && name_matches(&song.artist, &artist_terms) {
+            artists.push(song.artist.clone());
+        }
+        if !albums.contains(&song.album) && name_matches(&song.album, &album_terms) {
+            albums.push(song.album.clone());
+        }
+    }
+
+    if !artists.is_empty() {
+        append_group(content, entry, "🎤 Artists", "artist", &artists, &artist_terms);
+    }
+    if !albums.is_empty() {
+        append_group(content, entry, "💿 Albums", "album", &albums, &album_terms);
+    }
+
+    let songs_label = Label::new(Some(&format!("🎵 Songs ({})", results.len())));
+    songs_label.set_halign(gtk4::Align::Start);
+    content.append(&songs_label);
+
+    let model = song_store(&results);
+    let song_list = create_song_list_view(state, &model, &terms);
+    content.append(&song_list);
+}
+
+fn append_group(content: &Box, entry: &SearchEntry, title: &str, field: &str, names: &[String], terms: &[String]) {
+    let group_label = Label::new(Some(title));
+    group_label.set_halign(gtk4::Align::Start);
+    content.append(&group_label);
+
+    let flow = FlowBox::new();
+    flow.set_selection_mode(SelectionMode::None);
+    flow.set_max_children_per_line(6);
+
+    for name in names.iter().take(MAX_GROUP_RESULTS) {
+        let label = Label::new(None);
+        label.set_markup(&highlight_markup(name, terms));
+
+        let button = Button::new();
+        button.set_child(Some(&label));
+
+        // Clicar refina a busca para aquele artista/álbum
+        let entry_clone = entry.clone();
+        let query = format!("{}:\"{}\"", field, name.replace('"', ""));
+        button.connect_clicked(move |_| {
+            entry_clone.set_text(&query);
+        });
+
+        flow.insert(&button, -1);
+    }
+
+    content.append(&flow);
+}
+
+// Marca em negrito os trechos que correspondem aos termos, comparando o texto já normalizado
+pub fn highlight_markup(text: &str, terms: &[String]) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut normalized = String::new();
+    let mut spans = Vec::with_capacity(chars.len());
+    for c in &chars {
+        let start = normalized.len();
+        normalized.push_str(&normalize(&c.to_string()));
+        spans.push(start..normalized.len());
+    }
+
+    let mut marked = vec![false; chars.len()];
+    for term in terms.iter().filter(|t| !t.is_empty()) {
+        for (pos, matched) in normalized.match_indices(term.as_str()) {
+            let range = pos..pos + matched.len();
+            for (i, span) in spans.iter().enumerate() {
+                if span.start < range.end && range.start < span.end {
+                    marked[i] = true;
+                }
+            }
+        }
+    }
+
+    let mut markup = String::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let bold = marked[i];
+        let start = i;
+        while i < chars.len() && marked[i] == bold {
+            i += 1;
+        }
+        let chunk: String = chars[start..i].iter().collect();
+        let escaped = glib::markup_escape_text(&chunk);
+        if bold {
+            markup.push_str(&format!("<b>{}</b>", escaped));
+        } else {
+            markup.push_str(&escaped);
+        }
+    }
+    markup
+}
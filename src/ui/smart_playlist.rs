@@ -0,0 +1,401 @@
+use gtk4::prelude::*;
+use gtk4::{
+    ApplicationWindow, Box, Button, CheckButton, DropDown, Entry, Label, Orientation,
+    ScrolledWindow, SpinButton, Window
+};
+use std::cell::RefCell;
+use std::rc::{Rc, Weak};
+
+use crate::AppState;
+use crate::library::smart::{
+    Limit, MatchMode, Rule, RuleField, RuleGroup, RuleNode, RuleOperator, SmartPlaylist, SortField, SortOrder, MAX_DAYS
+};
+
+struct RuleRow {
+    container: Box,
+    field: DropDown,
+    operator: DropDown,
+    value: Entry,
+}
+
+enum EditorNode {
+    Rule(RuleRow),
+    Group(GroupEditor),
+}
+
+struct GroupEditor {
+    container: Box,
+    header: Box,
+    mode: DropDown,
+    rules_box: Box,
+    children: Rc<RefCell<Vec<EditorNode>>>,
+}
+
+fn operator_labels(field: RuleField) -> Vec<&'static str> {
+    field.operators().iter().map(|op| op.label()).collect()
+}
+
+impl RuleRow {
+    fn new() -> Self {
+        let container = Box::new(Orientation::Horizontal, 5);
+
+        let field_labels: Vec<&str> = RuleField::ALL.iter().map(|f| f.label()).collect();
+        let field = DropDown::from_strings(&field_labels);
+        let operator = DropDown::from_strings(&operator_labels(RuleField::ALL[0]));
+        let value = Entry::new();
+        value.set_hexpand(true);
+
+        // Os operadores disponíveis dependem do tipo do campo
+        let operator_clone = operator.clone();
+        field.connect_selected_notify(move |dropdown| {
+            let field = RuleField::ALL[dropdown.selected() as usize];
+            let labels = operator_labels(field);
+            operator_clone.set_model(Some(&gtk4::StringList::new(&labels)));
+            operator_clone.set_selected(0);
+        });
+
+        container.append(&field);
+        container.append(&operator);
+        container.append(&value);
+
+        Self {
+            container,
+            field,
+            operator,
+            value,
+        }
+    }
+
+    fn from_rule(rule: &Rule) -> Self {
+        let row = Self::new();
+        // Trocar o campo recria a lista de operadores, então o operador vem depois
+        if let Some(index) = RuleField::ALL.iter().position(|field| *field == rule.field) {
+            row.field.set_selected(index as u32);
+        }
+        if let Some(index) = rule.field.operators().iter().position(|op| *op == rule.operator) {
+            row.operator.set_selected(index as u32);
+        }
+        row.value.set_text(&rule.value);
+        row
+    }
+
+    fn to_rule(&self) -> Rule {
+        let field = RuleField::ALL[self.field.selected() as usize];
+        let operators = field.operators();
+        let operator = operators
+            .get(self.operator.selected() as usize)
+            .copied()
+            .unwrap_or(operators[0]);
+
+        let mut value = self.value.text().to_string();
+        if matches!(operator, RuleOperator::InLastDays | RuleOperator::NotInLastDays) {
+            if let Ok(days) = value.trim().parse::<u64>() {
+                value = days.min(MAX_DAYS).to_string();
+            }
+        }
+        Rule { field, operator, value }
+    }
+}
+
+impl EditorNode {
+    fn container(&self) -> &Box {
+        match self {
+            EditorNode::Rule(row) => &row.container,
+            EditorNode::Group(group) => &group.container,
+        }
+    }
+
+    // Onde fica o botão de remover
+    fn controls(&self) -> &Box {
+        match self {
+            EditorNode::Rule(row) => &row.container,
+            EditorNode::Group(group) => &group.header,
+        }
+    }
+}
+
+fn add_node(rules_box: &Box, children: &Rc<RefCell<Vec<EditorNode>>>, node: EditorNode) {
+    let remove_btn = Button::with_label("✕");
+    node.controls().append(&remove_btn);
+
+    let weak_children: Weak<RefCell<Vec<EditorNode>>> = Rc::downgrade(children);
+    let weak_rules_box = rules_box.downgrade();
+    let container = node.container().downgrade();
+    remove_btn.connect_clicked(move |_| {
+        let (Some(children), Some(rules_box), Some(container)) =
+            (weak_children.upgrade(), weak_rules_box.upgrade(), container.upgrade())
+        else {
+            return;
+        };
+        children.borrow_mut().retain(|n| n.container() != &container);
+        rules_box.remove(&container);
+    });
+
+    rules_box.append(node.container());
+    children.borrow_mut().push(node);
+}
+
+impl GroupEditor {
+    fn new() -> Self {
+        let container = Box::new(Orientation::Vertical, 5);
+        container.add_css_class("card");
+        container.set_margin_start(10);
+
+        let header = Box::new(Orientation::Horizontal, 5);
+        let mode = DropDown::from_strings(&["Match all of the following", "Match any of the following"]);
+        mode.set_hexpand(true);
+        mode.set_halign(gtk4::Align::Start);
+        header.append(&mode);
+
+        let rules_box = Box::new(Orientation::Vertical, 5);
+
+        let buttons = Box::new(Orientation::Horizontal, 5);
+        let add_rule_btn = Button::with_label("➕ Rule");
+        let add_group_btn = Button::with_label("➕ Group");
+        buttons.append(&add_rule_btn);
+        buttons.append(&add_group_btn);
+
+        container.append(&header);
+        container.append(&rules_box);
+        container.append(&buttons);
+
+        let editor = Self {
+            container,
+            header,
+            mode,
+            rules_box,
+            children: Rc::new(RefCell::new(Vec::new())),
+        };
+
+        // Os botões ficam dentro do próprio editor; referências fracas evitam o ciclo
+        let rules_box = editor.rules_box.downgrade();
+        let children = Rc::downgrade(&editor.children);
+        add_rule_btn.connect_clicked(move |_| {
+            if let (Some(rules_box), Some(children)) = (rules_box.upgrade(), children.upgrade()) {
+                add_node(&rules_box, &children, EditorNode::Rule(RuleRow::new()));
+            }
+        });
+
+        let rules_box = editor.rules_box.downgrade();
+        let children = Rc::downgrade(&editor.children);
+        add_group_btn.connect_clicked(move |_| {
+            if let (Some(rules_box), Some(children)) = (rules_box.upgrade(), children.upgrade()) {
+                let group = GroupEditor::new();
+                group.add_node(EditorNode::Rule(RuleRow::new()));
+                add_node(&rules_box, &children, EditorNode::Group(group));
+            }
+        });
+
+        editor
+    }
+
+    fn from_group(group: &RuleGroup) -> Self {
+        let editor = Self::new();
+        editor.load(group);
+        editor
+    }
+
+    fn load(&self, group: &RuleGroup) {
+        self.mode.set_selected(match group.mode {
+            MatchMode::All => 0,
+            MatchMode::Any => 1,
+        });
+        for node in &group.rules {
+            self.add_node(match node {
+                RuleNode::Rule(rule) => EditorNode::Rule(RuleRow::from_rule(rule)),
+                RuleNode::Group(group) => EditorNode::Group(GroupEditor::from_group(group)),
+            });
+        }
+    }
+
+    fn add_node(&self, node: EditorNode) {
+        add_node(&self.rules_box, &self.children, node);
+    }
+
+    fn to_group(&self) -> RuleGroup {
+        let mode = if self.mode.selected() == 0 {
+            MatchMode::All
+        } else {
+            MatchMode::Any
+        };
+
+        let rules = self
+            .children
+            .borrow()
+            .iter()
+            .map(|node| match node {
+                EditorNode::Rule(row) => RuleNode::Rule(row.to_rule()),
+                EditorNode::Group(group) => RuleNode::Group(group.to_group()),
+            })
+            .collect();
+
+        RuleGroup { mode, rules }
+    }
+}
+
+// Sem índice cria uma playlist nova; com índice edita a existente no lugar
+pub fn show_smart_playlist_dialog<F>(
+    parent: &ApplicationWindow,
+    state: &Rc<RefCell<AppState>>,
+    existing: Option<usize>,
+    on_saved: F
+) where
+    F: Fn(usize) + 'static,
+{
+    let existing = existing.and_then(|index| {
+        let playlist = state.borrow().library.smart_playlists.get(index).cloned()?;
+        Some((index, playlist))
+    });
+
+    let dialog = Window::builder()
+        .title(if existing.is_some() { "Edit Smart Playlist" } else { "New Smart Playlist" })
+        .transient_for(parent)
+        .modal(true)
+        .default_width(650)
+        .default_height(500)
+        .build();
+
+    let content_area = Box::new(Orientation::Vertical, 10);
+    content_area.set_margin_top(15);
+    content_area.set_margin_bottom(15);
+    content_area.set_margin_start(15);
+    content_area.set_margin_end(15);
+
+    let name_entry = Entry::new();
+    name_entry.set_placeholder_text(Some("Playlist Name"));
+
+    // Regras
+    let root = GroupEditor::new();
+    root.container.remove_css_class("card");
+    root.container.set_margin_start(0);
+    match &existing {
+        Some((_, playlist)) => root.load(&playlist.rules),
+        None => root.add_node(EditorNode::Rule(RuleRow::new())),
+    }
+
+    let rules_scroll = ScrolledWindow::new();
+    rules_scroll.set_vexpand(true);
+    rules_scroll.set_child(Some(&root.container));
+
+    // Ordenação
+    let sort_box = Box::new(Orientation::Horizontal, 10);
+    let mut sort_labels = vec!["No sorting"];
+    sort_labels.extend(SortField::ALL.iter().map(|f| f.label()));
+    let sort_dropdown = DropDown::from_strings(&sort_labels);
+    let descending_check = CheckButton::with_label("Descending");
+    sort_box.append(&Label::new(Some("Sort by:")));
+    sort_box.append(&sort_dropdown);
+    sort_box.append(&descending_check);
+
+    // Limite
+    let limit_box = Box::new(Orientation::Horizontal, 10);
+    let limit_check = CheckButton::with_label("Limit to");
+    let limit_spin = SpinButton::with_range(1.0, 100_000.0, 1.0);
+    limit_spin.set_value(25.0);
+    let limit_unit = DropDown::from_strings(&["songs", "minutes", "MB"]);
+    limit_box.append(&limit_check);
+    limit_box.append(&limit_spin);
+    limit_box.append(&limit_unit);
+
+    if let Some((_, playlist)) = &existing {
+        name_entry.set_text(&playlist.name);
+        if let Some(sort) = playlist.sort {
+            if let Some(index) = SortField::ALL.iter().position(|field| *field == sort.field) {
+                sort_dropdown.set_selected(index as u32 + 1);
+            }
+            descending_check.set_active(sort.descending);
+        }
+        if let Some(limit) = playlist.limit {
+            let (value, unit) = match limit {
+                Limit::Songs(value) => (value as f64, 0),
+                Limit::Minutes(value) => (value as f64, 1),
+                Limit::Megabytes(value) => (value as f64, 2),
+            };
+            limit_check.set_active(true);
+            limit_spin.set_value(value);
+            limit_unit.set_selected(unit);
+        }
+    }
+
+    let save_btn = Button::with_label(if existing.is_some() { "Save" } else { "Create" });
+    save_btn.add_css_class("suggested-action");
+
+    let state_clone = Rc::clone(state);
+    let dialog_clone = dialog.clone();
+    let name_entry_clone = name_entry.clone();
+    let sort_dropdown_clone = sort_dropdown.clone();
+    let descending_check_clone = descending_check.clone();
+    let limit_check_clone = limit_check.clone();
+    let limit_spin_clone = limit_spin.clone();
+    let limit_unit_clone = limit_unit.clone();
+    let existing_index = existing.map(|(index, _)| index);
+
+    save_btn.connect_clicked(move |_| {
+        let name = name_entry_clone.text().to_string();
+        if name.is_empty() {
+            println!("Please fill the playlist name");
+            return;
+        }
+
+        let mut playlist = SmartPlaylist::new(&name, root.to_group());
+
+        let sort_index = sort_dropdown_clone.selected() as usize;
+        if sort_index > 0 {
+            playlist.sort = Some(SortOrder {
+                field: SortField::ALL[sort_index - 1],
+                descending: descending_check_clone.is_active(),
+            });
+        }
+
+        if limit_check_clone.is_active() {
+            let value = limit_spin_clone.value_as_int().max(1) as u64;
+            playlist.limit = Some(match limit_unit_clone.selected() {
+                0 => Limit::Songs(value as usize),
+                1 => Limit::Minutes(value),
+                _ => Limit::Megabytes(value),
+            });
+        }
+
+        let index = {
+            let mut app_state = state_clone.borrow_mut();
+            let playlists = &mut app_state.library.smart_playlists;
+            let index = match existing_index.filter(|index| *index < playlists.len()) {
+                Some(index) => {
+                    playlists[index] = playlist;
+                    index
+                }
+                None => {
+                    playlists.push(playlist);
+                    playlists.len() - 1
+                }
+            };
+            app_state.save_library();
+            index
+        };
+
+        dialog_clone.close();
+        on_saved(index);
+    });
+
+    let cancel_btn = Button::with_label("Cancel");
+    let dialog_clone2 = dialog.clone();
+    cancel_btn.connect_clicked(move |_| {
+        dialog_clone2.close();
+    });
+
+    content_area.append(&Label::new(Some("Name:")));
+    content_area.append(&name_entry);
+    content_area.append(&Label::new(Some("Rules:")));
+    content_area.append(&rules_scroll);
+    content_area.append(&sort_box);
+    content_area.append(&limit_box);
+
+    let button_box = Box::new(Orientation::Horizontal, 10);
+    button_box.set_halign(gtk4::Align::End);
+    button_box.append(&cancel_btn);
+    button_box.append(&save_btn);
+    content_area.append(&button_box);
+
+    dialog.set_child(Some(&content_area));
+    dialog.present();
+}
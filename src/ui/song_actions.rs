@@ -0,0 +1,250 @@
+use gtk4::prelude::*;
+use gtk4::{ApplicationWindow, Box, MultiSelection};
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use crate::AppState;
+use crate::library::playlist::Playlist;
+use crate::library::Song;
+use crate::ui::song_list::remove_from_store;
+use crate::ui::song_object::SongObject;
+
+// Atalhos de teclado das ações; o Delete fica na própria tabela
+const ACCELS: [(&str, &[&str]); 13] = [
+    ("song.play", &["<Control>p"]),
+    ("song.play-next", &["<Control><Shift>n"]),
+    ("song.add-to-queue", &["<Control>e"]),
+    ("song.go-to-artist", &["<Alt>a"]),
+    ("song.go-to-album", &["<Alt>l"]),
+    ("song.edit-tags", &["<Control>i"]),
+    ("song.show-in-file-manager", &["<Control><Shift>o"]),
+    ("song.rate(0)", &["<Control>0"]),
+    ("song.rate(1)", &["<Control>1"]),
+    ("song.rate(2)", &["<Control>2"]),
+    ("song.rate(3)", &["<Control>3"]),
+    ("song.rate(4)", &["<Control>4"]),
+    ("song.rate(5)", &["<Control>5"]),
+];
+
+pub fn selected_songs(selection: &MultiSelection) -> Vec<Song> {
+    let bitset = selection.selection();
+    (0..bitset.size())
+        .filter_map(|i| selection.item(bitset.nth(i as u32)).and_downcast::<SongObject>())
+        .map(|object| object.song())
+        .collect()
+}
+
+fn current_selection(state: &Rc<RefCell<AppState>>) -> Vec<Song> {
+    let selection = state.borrow().song_selection.clone();
+    selection.map(|s| selected_songs(&s)).unwrap_or_default()
+}
+
+fn add_action<F>(group: &gio::SimpleActionGroup, state: &Rc<RefCell<AppState>>, name: &str, handler: F)
+where
+    F: Fn(Vec<Song>) + 'static,
+{
+    let action = gio::SimpleAction::new(name, None);
+    let state_clone = Rc::clone(state);
+    action.connect_activate(move |_, _| {
+        let songs = current_selection(&state_clone);
+        if !songs.is_empty() {
+            handler(songs);
+        }
+    });
+    group.add_action(&action);
+}
+
+// Ações "song.*" que agem sobre as músicas selecionadas na tabela visível
+pub fn install_song_actions(
+    window: &ApplicationWindow,
+    state: &Rc<RefCell<AppState>>,
+    content: &Box,
+    playlists_box: &Box
+) {
+    let group = gio::SimpleActionGroup::new();
+
+    let state_clone = Rc::clone(state);
+    add_action(&group, state, "play", move |mut songs| {
+        // As demais selecionadas tocam em seguida, sem apagar o resto da fila
+        let first = songs.remove(0);
+        state_clone.borrow_mut().queue.play_next(songs);
+        crate::play_song(&state_clone, &first);
+    });
+
+    let state_clone = Rc::clone(state);
+    add_action(&group, state, "play-next", move |songs| {
+        println!("Playing next: {} songs", songs.len());
+        state_clone.borrow_mut().queue.play_next(songs);
+    });
+
+    let state_clone = Rc::clone(state);
+    add_action(&group, state, "add-to-queue", move |songs| {
+        println!("Added to queue: {} songs", songs.len());
+        state_clone.borrow_mut().queue.enqueue(songs);
+    });
+
+    let add_to_playlist = gio::SimpleAction::new("add-to-playlist", Some(glib::VariantTy::INT32));
+    let state_clone = Rc::clone(state);
+    add_to_playlist.connect_activate(move |_, parameter| {
+        let Some(index) = parameter.and_then(|p| p.get::<i32>()) else {
+            return;
+        };
+        let paths: Vec<PathBuf> = current_selection(&state_clone)
+            .into_iter()
+            .map(|song| song.file_path)
+            .collect();
+        if let Some(playlist) = state_clone.borrow_mut().library.playlists.get_mut(index as usize) {
+            playlist.songs.extend(paths);
+        }
+    });
+    group.add_action(&add_to_playlist);
+
+    let rate = gio::SimpleAction::new("rate", Some(glib::VariantTy::INT32));
+    let state_clone = Rc::clone(state);
+    rate.connect_activate(move |_, parameter| {
+        let Some(stars) = parameter.and_then(|p| p.get::<i32>()) else {
+            return;
+        };
+        let paths: Vec<PathBuf> = current_selection(&state_clone)
+            .into_iter()
+            .map(|song| song.file_path)
+            .collect();
+        if paths.is_empty() {
+            return;
+        }
+        let mut app_state = state_clone.borrow_mut();
+        let changed = app_state.library.set_rating(&paths, stars.clamp(0, 5) as u8);
+        crate::ui::song_list::replace_songs(&app_state.song_store, &changed);
+        app_state.save_library();
+    });
+    group.add_action(&rate);
+
+    let state_clone = Rc::clone(state);
+    let window_clone = window.clone();
+    let content_clone = content.clone();
+    let playlists_box_clone = playlists_box.clone();
+    add_action(&group, state, "add-to-new-playlist", move |songs| {
+        let index = {
+            let mut app_state = state_clone.borrow_mut();
+            let playlists = &mut app_state.library.playlists;
+            let mut playlist = Playlist::new(&format!("New Playlist {}", playlists.len() + 1));
+            playlist.songs = songs.into_iter().map(|song| song.file_path).collect();
+            playlists.push(playlist);
+            playlists.len() - 1
+        };
+        crate::update_playlist_list(&playlists_box_clone, &window_clone, &state_clone, &content_clone);
+        crate::show_playlist(&content_clone, &window_clone, &state_clone, index);
+    });
+
+    let state_clone = Rc::clone(state);
+    let content_clone = content.clone();
+    add_action(&group, state, "go-to-artist", move |songs| {
+        crate::show_artist(&content_clone, &state_clone, &songs[0].artist);
+    });
+
+    let state_clone = Rc::clone(state);
+    let content_clone = content.clone();
+    add_action(&group, state, "go-to-album", move |songs| {
+        crate::show_album(&content_clone, &state_clone, &songs[0].album);
+    });
+
+    let state_clone = Rc::clone(state);
+    let window_clone = window.clone();
+    let content_clone = content.clone();
+    add_action(&group, state, "edit-tags", move |songs| {
+        let state_for_update = Rc::clone(&state_clone);
+        let window_for_update = window_clone.clone();
+        let content_for_update = content_clone.clone();
+        crate::ui::tag_editor::show_tag_editor(&window_clone, &state_clone, songs, move || {
+            crate::refresh_view(&content_for_update, &window_for_update, &state_for_update);
+        });
+    });
+
+    let window_clone = window.clone();
+    add_action(&group, state, "show-in-file-manager", move |songs| {
+        let file = gio::File::for_path(&songs[0].file_path);
+        gtk4::FileLauncher::new(Some(&file)).open_containing_folder(
+            Some(&window_clone),
+            gio::Cancellable::NONE,
+            |result| {
+                if let Err(e) = result {
+                    eprintln!("Failed to open file manager: {}", e);
+                }
+            },
+        );
+    });
+
+    let state_clone = Rc::clone(state);
+    let window_clone = window.clone();
+    let content_clone = content.clone();
+    add_action(&group, state, "remove-from-library", move |songs| {
+        let paths: Vec<PathBuf> = songs.into_iter().map(|song| song.file_path).collect();
+        {
+            let mut app_state = state_clone.borrow_mut();
+            let removed = app_state.library.remove_songs(&paths);
+            remove_from_store(&app_state.song_store, &paths);
+            // Também saem da fila, que guarda cópias das músicas
+            let gone: HashSet<&PathBuf> = paths.iter().collect();
+            app_state
+                .queue
+                .refresh_songs(|songs| songs.into_iter().filter(|song| !gone.contains(&song.file_path)).collect());
+            println!("Removed {} songs from library", removed);
+        }
+        crate::refresh_view(&content_clone, &window_clone, &state_clone);
+    });
+
+    window.insert_action_group("song", Some(&group));
+
+    if let Some(app) = window.application() {
+        for (action, accels) in ACCELS {
+            app.set_accels_for_action(action, accels);
+        }
+    }
+}
+
+// Montado na hora de abrir, para refletir as playlists atuais
+pub fn build_song_menu(state: &Rc<RefCell<AppState>>) -> gio::Menu {
+    let menu = gio::Menu::new();
+
+    let playback = gio::Menu::new();
+    playback.append(Some("Play"), Some("song.play"));
+    playback.append(Some("Play Next"), Some("song.play-next"));
+    playback.append(Some("Add to Queue"), Some("song.add-to-queue"));
+    menu.append_section(None, &playback);
+
+    let playlists = gio::Menu::new();
+    for (index, playlist) in state.borrow().library.playlists.iter().enumerate() {
+        let item = gio::MenuItem::new(Some(&playlist.name), None);
+        item.set_action_and_target_value(Some("song.add-to-playlist"), Some(&(index as i32).to_variant()));
+        playlists.append_item(&item);
+    }
+    playlists.append(Some("New Playlist…"), Some("song.add-to-new-playlist"));
+
+    let playlist_section = gio::Menu::new();
+    playlist_section.append_submenu(Some("Add to Playlist"), &playlists);
+
+    let ratings = gio::Menu::new();
+    for stars in 0..=5 {
+        let label = if stars == 0 { "No Rating".to_string() } else { "★".repeat(stars as usize) };
+        let item = gio::MenuItem::new(Some(&label), None);
+        item.set_action_and_target_value(Some("song.rate"), Some(&stars.to_variant()));
+        ratings.append_item(&item);
+    }
+    playlist_section.append_submenu(Some("Rating"), &ratings);
+    menu.append_section(None, &playlist_section);
+
+    let navigation = gio::Menu::new();
+    navigation.append(Some("Go to Artist"), Some("song.go-to-artist"));
+    navigation.append(Some("Go to Album"), Some("song.go-to-album"));
+    menu.append_section(None, &navigation);
+
+    let file = gio::Menu::new();
+    file.append(Some("Edit Tags…"), Some("song.edit-tags"));
+    file.append(Some("Show in File Manager"), Some("song.show-in-file-manager"));
+    file.append(Some("Remove from Library"), Some("song.remove-from-library"));
+    menu.append_section(None, &file);
+
+    menu
+}
@@ -0,0 +1,515 @@
+use gtk4::prelude::*;
+use gtk4::{
+    Box, CheckButton, ColumnView, ColumnViewColumn, ColumnViewSorter, CustomSorter, Label,
+    ListItem, MenuButton, MultiSelection, Orientation, Popover, PopoverMenu, ScrolledWindow,
+    SignalListItemFactory, SortListModel, SortType
+};
+use std::cell::RefCell;
+use std::cmp::Ordering;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::time::Duration;
+
+use crate::AppState;
+use crate::config::{ColumnState, TrackTableLayout};
+use crate::library::query::{Highlight, QueryField};
+use crate::library::Song;
+use crate::ui::search::highlight_markup;
+use crate::ui::song_actions::build_song_menu;
+use crate::ui::song_object::SongObject;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrackColumn {
+    Track,
+    Title,
+    Artist,
+    Album,
+    AlbumArtist,
+    Year,
+    Genre,
+    Duration,
+    PlayCount,
+    SkipCount,
+    LastPlayed,
+    Rating,
+    DateAdded,
+    Bitrate,
+}
+
+impl TrackColumn {
+    pub const ALL: [TrackColumn; 14] = [
+        TrackColumn::Track,
+        TrackColumn::Title,
+        TrackColumn::Artist,
+        TrackColumn::Album,
+        TrackColumn::AlbumArtist,
+        TrackColumn::Year,
+        TrackColumn::Genre,
+        TrackColumn::Duration,
+        TrackColumn::PlayCount,
+        TrackColumn::SkipCount,
+        TrackColumn::LastPlayed,
+        TrackColumn::Rating,
+        TrackColumn::DateAdded,
+        TrackColumn::Bitrate,
+    ];
+
+    pub fn id(&self) -> &'static str {
+        match self {
+            TrackColumn::Track => "track",
+            TrackColumn::Title => "title",
+            TrackColumn::Artist => "artist",
+            TrackColumn::Album => "album",
+            TrackColumn::AlbumArtist => "album-artist",
+            TrackColumn::Year => "year",
+            TrackColumn::Genre => "genre",
+            TrackColumn::Duration => "duration",
+            TrackColumn::PlayCount => "play-count",
+            TrackColumn::SkipCount => "skip-count",
+            TrackColumn::LastPlayed => "last-played",
+            TrackColumn::Rating => "rating",
+            TrackColumn::DateAdded => "date-added",
+            TrackColumn::Bitrate => "bitrate",
+        }
+    }
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            TrackColumn::Track => "#",
+            TrackColumn::Title => "Title",
+            TrackColumn::Artist => "Artist",
+            TrackColumn::Album => "Album",
+            TrackColumn::AlbumArtist => "Album Artist",
+            TrackColumn::Year => "Year",
+            TrackColumn::Genre => "Genre",
+            TrackColumn::Duration => "Duration",
+            TrackColumn::PlayCount => "Plays",
+            TrackColumn::SkipCount => "Skips",
+            TrackColumn::LastPlayed => "Last Played",
+            TrackColumn::Rating => "Rating",
+            TrackColumn::DateAdded => "Date Added",
+            TrackColumn::Bitrate => "Bitrate",
+        }
+    }
+
+    fn from_id(id: &str) -> Option<Self> {
+        Self::ALL.iter().copied().find(|column| column.id() == id)
+    }
+
+    fn default_visible(&self) -> bool {
+        !matches!(
+            self,
+            TrackColumn::AlbumArtist
+                | TrackColumn::SkipCount
+                | TrackColumn::LastPlayed
+                | TrackColumn::DateAdded
+                | TrackColumn::Bitrate
+        )
+    }
+
+    // Campo da busca mostrado na coluna, para destacar só os termos dele
+    fn query_field(&self) -> Option<QueryField> {
+        match self {
+            TrackColumn::Title => Some(QueryField::Title),
+            TrackColumn::Artist => Some(QueryField::Artist),
+            TrackColumn::Album => Some(QueryField::Album),
+            TrackColumn::AlbumArtist => Some(QueryField::AlbumArtist),
+            TrackColumn::Genre => Some(QueryField::Genre),
+            _ => None,
+        }
+    }
+
+    pub fn text(&self, song: &Song) -> String {
+        match self {
+            TrackColumn::Track => song.track_number.map(|n| n.to_string()).unwrap_or_default(),
+            TrackColumn::Title => song.title.clone(),
+            TrackColumn::Artist => song.artist.clone(),
+            TrackColumn::Album => song.album.clone(),
+            TrackColumn::AlbumArtist => song.album_artist.clone().unwrap_or_default(),
+            TrackColumn::Year => song.year.map(|y| y.to_string()).unwrap_or_default(),
+            TrackColumn::Genre => song.genre.clone().unwrap_or_default(),
+            TrackColumn::Duration => song.duration.map(format_duration).unwrap_or_default(),
+            TrackColumn::PlayCount => song.play_count.to_string(),
+            TrackColumn::SkipCount => song.skip_count.to_string(),
+            TrackColumn::LastPlayed => song.last_played.map(format_date).unwrap_or_default(),
+            TrackColumn::Rating => {
+                let stars = song.rating.min(5) as usize;
+                format!("{}{}", "★".repeat(stars), "☆".repeat(5 - stars))
+            }
+            TrackColumn::DateAdded => format_date(song.date_added),
+            TrackColumn::Bitrate => song.bitrate.map(|b| format!("{} kbps", b)).unwrap_or_default(),
+        }
+    }
+
+    pub fn compare(&self, a: &SongObject, b: &SongObject) -> Ordering {
+        let (keys_a, keys_b) = (a.sort_keys(), b.sort_keys());
+        match self {
+            TrackColumn::Title => return keys_a.title.cmp(&keys_b.title),
+            TrackColumn::Artist => return keys_a.artist.cmp(&keys_b.artist),
+            TrackColumn::Album => return keys_a.album.cmp(&keys_b.album),
+            TrackColumn::AlbumArtist => return keys_a.album_artist.cmp(&keys_b.album_artist),
+            TrackColumn::Genre => return keys_a.genre.cmp(&keys_b.genre),
+            _ => {}
+        }
+        a.with_song(|a| {
+            b.with_song(|b| match self {
+                TrackColumn::Track => (a.disc_number, a.track_number).cmp(&(b.disc_number, b.track_number)),
+                TrackColumn::Year => a.year.cmp(&b.year),
+                TrackColumn::Duration => a.duration.cmp(&b.duration),
+                TrackColumn::PlayCount => a.play_count.cmp(&b.play_count),
+                TrackColumn::SkipCount => a.skip_count.cmp(&b.skip_count),
+                TrackColumn::LastPlayed => a.last_played.cmp(&b.last_played),
+                TrackColumn::Rating => a.rating.cmp(&b.rating),
+                TrackColumn::DateAdded => a.date_added.cmp(&b.date_added),
+                TrackColumn::Bitrate => a.bitrate.cmp(&b.bitrate),
+                _ => Ordering::Equal,
+            })
+        })
+    }
+}
+
+pub fn format_duration(seconds: u64) -> String {
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+fn format_date(timestamp: u64) -> String {
+    glib::DateTime::from_unix_local(timestamp as i64)
+        .and_then(|date| date.format("%Y-%m-%d"))
+        .map(|text| text.to_string())
+        .unwrap_or_default()
+}
+
+pub fn song_store(songs: &[Song]) -> gio::ListStore {
+    let store = gio::ListStore::new::<SongObject>();
+    let objects: Vec<SongObject> = songs.iter().cloned().map(SongObject::new).collect();
+    store.extend_from_slice(&objects);
+    store
+}
+
+// Abre o menu de contexto sobre a linha; se ela não estava selecionada, passa a ser a única
+fn show_context_menu(label: &Label, item: &ListItem, menu: &PopoverMenu, state: &Rc<RefCell<AppState>>, x: f64, y: f64) {
+    let Some(column_view) = menu.parent() else {
+        return;
+    };
+    if !item.is_selected() {
+        if let Some(selection) = column_view
+            .downcast_ref::<ColumnView>()
+            .and_then(|view| view.model())
+            .and_downcast::<MultiSelection>()
+        {
+            selection.select_item(item.position(), true);
+        }
+    }
+
+    let Some(point) = label.compute_point(&column_view, &gtk4::graphene::Point::new(x as f32, y as f32)) else {
+        return;
+    };
+    menu.set_menu_model(Some(&build_song_menu(state)));
+    menu.set_pointing_to(Some(&gtk4::gdk::Rectangle::new(point.x() as i32, point.y() as i32, 1, 1)));
+    menu.popup();
+}
+
+// Clique direito no mouse ou toque longo na tela
+fn attach_context_menu(label: &Label, item: &ListItem, menu: &PopoverMenu, state: &Rc<RefCell<AppState>>) {
+    let click = gtk4::GestureClick::new();
+    click.set_button(gtk4::gdk::BUTTON_SECONDARY);
+    let item_weak = item.downgrade();
+    let label_weak = label.downgrade();
+    let menu_clone = menu.clone();
+    let state_clone = Rc::clone(state);
+    click.connect_pressed(move |gesture, _, x, y| {
+        if let (Some(item), Some(label)) = (item_weak.upgrade(), label_weak.upgrade()) {
+            gesture.set_state(gtk4::EventSequenceState::Claimed);
+            show_context_menu(&label, &item, &menu_clone, &state_clone, x, y);
+        }
+    });
+    label.add_controller(click);
+
+    let long_press = gtk4::GestureLongPress::new();
+    long_press.set_touch_only(true);
+    let item_weak = item.downgrade();
+    let label_weak = label.downgrade();
+    let menu_clone = menu.clone();
+    let state_clone = Rc::clone(state);
+    long_press.connect_pressed(move |gesture, x, y| {
+        if let (Some(item), Some(label)) = (item_weak.upgrade(), label_weak.upgrade()) {
+            gesture.set_state(gtk4::EventSequenceState::Claimed);
+            show_context_menu(&label, &item, &menu_clone, &state_clone, x, y);
+        }
+    });
+    label.add_controller(long_press);
+}
+
+// Troca os objetos das músicas alteradas para que as linhas sejam redesenhadas
+pub fn replace_songs(store: &gio::ListStore, songs: &[Song]) {
+    let by_path: HashMap<_, _> = songs.iter().map(|song| (&song.file_path, song)).collect();
+    for position in 0..store.n_items() {
+        let Some(object) = store.item(position).and_downcast::<SongObject>() else {
+            continue;
+        };
+        let updated = object.with_song(|song| by_path.get(&song.file_path).map(|s| (*s).clone()));
+        if let Some(updated) = updated {
+            store.splice(position, 1, &[SongObject::new(updated)]);
+        }
+    }
+}
+
+pub fn remove_from_store(store: &gio::ListStore, paths: &[PathBuf]) {
+    let paths: HashSet<&PathBuf> = paths.iter().collect();
+    store.retain(|object| {
+        object
+            .downcast_ref::<SongObject>()
+            .map(|song| song.with_song(|song| !paths.contains(&song.file_path)))
+            .unwrap_or(true)
+    });
+}
+
+fn create_column(
+    column: TrackColumn,
+    highlight: &[Highlight],
+    menu: &PopoverMenu,
+    state: &Rc<RefCell<AppState>>
+) -> ColumnViewColumn {
+    let factory = SignalListItemFactory::new();
+    let menu = menu.clone();
+    let state = Rc::clone(state);
+    factory.connect_setup(move |_, item| {
+        let Some(item) = item.downcast_ref::<ListItem>() else {
+            return;
+        };
+        let label = Label::new(None);
+        label.set_xalign(0.0);
+        label.set_hexpand(true);
+        label.set_ellipsize(gtk4::pango::EllipsizeMode::End);
+        attach_context_menu(&label, item, &menu, &state);
+        item.set_child(Some(&label));
+    });
+
+    let highlight = column
+        .query_field()
+        .map(|field| Highlight::texts_for(highlight, field))
+        .unwrap_or_default();
+    factory.connect_bind(move |_, item| {
+        let Some(item) = item.downcast_ref::<ListItem>() else {
+            return;
+        };
+        let Some(label) = item.child().and_downcast::<Label>() else {
+            return;
+        };
+        let Some(song_object) = item.item().and_downcast::<SongObject>() else {
+            return;
+        };
+
+        let text = song_object.with_song(|song| column.text(song));
+        if !highlight.is_empty() {
+            // Destaca os termos da busca
+            label.set_markup(&highlight_markup(&text, &highlight));
+        } else {
+            label.set_text(&text);
+        }
+    });
+
+    let view_column = ColumnViewColumn::new(Some(column.title()), Some(factory));
+    view_column.set_id(Some(column.id()));
+    view_column.set_resizable(true);
+    view_column.set_expand(matches!(
+        column,
+        TrackColumn::Title | TrackColumn::Artist | TrackColumn::Album
+    ));
+
+    let sorter = CustomSorter::new(move |a, b| {
+        let (Some(a), Some(b)) = (a.downcast_ref::<SongObject>(), b.downcast_ref::<SongObject>()) else {
+            return gtk4::Ordering::Equal;
+        };
+        column.compare(a, b).into()
+    });
+    view_column.set_sorter(Some(&sorter));
+
+    view_column
+}
+
+fn columns_of(column_view: &ColumnView) -> Vec<ColumnViewColumn> {
+    let columns = column_view.columns();
+    (0..columns.n_items())
+        .filter_map(|i| columns.item(i).and_downcast::<ColumnViewColumn>())
+        .collect()
+}
+
+// Guarda ordem, visibilidade, largura e ordenação das colunas no estado
+fn store_layout(column_view: &ColumnView, state: &Rc<RefCell<AppState>>) {
+    let columns = columns_of(column_view)
+        .iter()
+        .filter_map(|column| {
+            Some(ColumnState {
+                id: column.id()?.to_string(),
+                visible: column.is_visible(),
+                width: column.fixed_width(),
+            })
+        })
+        .collect();
+
+    let sorter = column_view.sorter().and_downcast::<ColumnViewSorter>();
+    let sort_column = sorter
+        .as_ref()
+        .and_then(|s| s.primary_sort_column())
+        .and_then(|column| column.id())
+        .map(|id| id.to_string());
+    let sort_descending = sorter
+        .map(|s| s.primary_sort_order() == SortType::Descending)
+        .unwrap_or(false);
+
+    if let Ok(mut app_state) = state.try_borrow_mut() {
+        app_state.track_layout = TrackTableLayout {
+            columns,
+            sort_column,
+            sort_descending,
+        };
+    }
+}
+
+fn create_column_chooser(column_view: &ColumnView) -> MenuButton {
+    let columns_box = Box::new(Orientation::Vertical, 5);
+    for column in columns_of(column_view) {
+        let title = column.title().map(|t| t.to_string()).unwrap_or_default();
+        let check = CheckButton::with_label(&title);
+        column
+            .bind_property("visible", &check, "active")
+            .bidirectional()
+            .sync_create()
+            .build();
+        columns_box.append(&check);
+    }
+
+    let popover = Popover::new();
+    popover.set_child(Some(&columns_box));
+
+    let button = MenuButton::new();
+    button.set_label("Columns");
+    button.set_popover(Some(&popover));
+    button.set_halign(gtk4::Align::End);
+    button
+}
+
+pub fn create_song_list_view(
+    state: &Rc<RefCell<AppState>>,
+    model: &gio::ListStore,
+    highlight: &[Highlight]
+) -> Box {
+    let column_view = ColumnView::new(None::<MultiSelection>);
+    column_view.set_reorderable(true);
+    column_view.set_show_column_separators(true);
+    column_view.add_css_class("song-list");
+
+    let layout = state.borrow().track_layout.clone();
+
+    // Um só menu por tabela, compartilhado pelas células
+    let menu = PopoverMenu::from_model(None::<&gio::MenuModel>);
+    menu.set_has_arrow(false);
+    menu.set_halign(gtk4::Align::Start);
+    menu.set_parent(&column_view);
+    let menu_clone = menu.clone();
+    column_view.connect_destroy(move |_| menu_clone.unparent());
+
+    // Primeiro as colunas na ordem salva, depois as que ainda não estavam no layout
+    let mut order: Vec<(TrackColumn, Option<&ColumnState>)> = layout
+        .columns
+        .iter()
+        .filter_map(|saved| TrackColumn::from_id(&saved.id).map(|column| (column, Some(saved))))
+        .collect();
+    for column in TrackColumn::ALL {
+        if !order.iter().any(|(c, _)| *c == column) {
+            order.push((column, None));
+        }
+    }
+
+    for (column, saved) in order {
+        let view_column = create_column(column, highlight, &menu, state);
+        view_column.set_visible(saved.map(|s| s.visible).unwrap_or(column.default_visible()));
+        if let Some(width) = saved.map(|s| s.width).filter(|w| *w > 0) {
+            view_column.set_fixed_width(width);
+        }
+        column_view.append_column(&view_column);
+    }
+
+    let sort_model = SortListModel::new(Some(model.clone()), column_view.sorter());
+    // Shift/Ctrl + clique e Ctrl+A já vêm do ColumnView com MultiSelection
+    let selection = MultiSelection::new(Some(sort_model.clone()));
+    column_view.set_model(Some(&selection));
+    state.borrow_mut().song_selection = Some(selection);
+
+    // Delete só vale com a tabela em foco, para não apagar músicas ao editar texto
+    let shortcuts = gtk4::ShortcutController::new();
+    shortcuts.add_shortcut(gtk4::Shortcut::new(
+        gtk4::ShortcutTrigger::parse_string("Delete"),
+        Some(gtk4::NamedAction::new("song.remove-from-library")),
+    ));
+    column_view.add_controller(shortcuts);
+
+    if let Some(id) = &layout.sort_column {
+        if let Some(column) = columns_of(&column_view).iter().find(|c| c.id().as_deref() == Some(id.as_str())) {
+            let order = if layout.sort_descending {
+                SortType::Descending
+            } else {
+                SortType::Ascending
+            };
+            column_view.sort_by_column(Some(column), order);
+        }
+    }
+
+    // Enter ou duplo clique tocam a música
+    let state_clone = Rc::clone(state);
+    let sort_model_clone = sort_model.clone();
+    column_view.connect_activate(move |_, position| {
+        if let Some(song_object) = sort_model_clone.item(position).and_downcast::<SongObject>() {
+            crate::play_song(&state_clone, &song_object.song());
+        }
+    });
+
+    // Qualquer mudança no layout é lembrada e gravada logo depois; arrastar a largura
+    // de uma coluna dispara várias notificações, que viram uma gravação só
+    let view_weak = column_view.downgrade();
+    let state_clone = Rc::clone(state);
+    let pending_save: Rc<RefCell<Option<glib::SourceId>>> = Rc::new(RefCell::new(None));
+    let on_layout_changed = Rc::new(move || {
+        if let Some(column_view) = view_weak.upgrade() {
+            store_layout(&column_view, &state_clone);
+        }
+        if let Some(source) = pending_save.borrow_mut().take() {
+            source.remove();
+        }
+        let state = Rc::clone(&state_clone);
+        let pending = Rc::clone(&pending_save);
+        let source = glib::timeout_add_local_once(Duration::from_secs(1), move || {
+            pending.borrow_mut().take();
+            if let Ok(app_state) = state.try_borrow() {
+                app_state.track_layout.save();
+            }
+        });
+        pending_save.borrow_mut().replace(source);
+    });
+
+    for column in columns_of(&column_view) {
+        let callback = Rc::clone(&on_layout_changed);
+        column.connect_visible_notify(move |_| callback());
+        let callback = Rc::clone(&on_layout_changed);
+        column.connect_fixed_width_notify(move |_| callback());
+    }
+    let callback = Rc::clone(&on_layout_changed);
+    column_view.columns().connect_items_changed(move |_, _, _, _| callback());
+    if let Some(sorter) = column_view.sorter() {
+        let callback = Rc::clone(&on_layout_changed);
+        sorter.connect_changed(move |_, _| callback());
+    }
+
+    let scroll = ScrolledWindow::new();
+    scroll.set_vexpand(true);
+    scroll.set_min_content_height(300);
+    scroll.set_child(Some(&column_view));
+    state.borrow_mut().song_scroll = Some(scroll.vadjustment());
+
+    let container = Box::new(Orientation::Vertical, 5);
+    container.append(&create_column_chooser(&column_view));
+    container.append(&scroll);
+    container
+}
@@ -0,0 +1,79 @@
+use gtk4::glib;
+use gtk4::subclass::prelude::*;
+
+use crate::library::{sort_name, Song};
+
+// Nomes já preparados para ordenar, calculados uma vez em vez de a cada comparação
+pub struct SortKeys {
+    pub title: String,
+    pub artist: String,
+    pub album: String,
+    pub album_artist: String,
+    pub genre: String,
+}
+
+impl SortKeys {
+    fn new(song: &Song) -> Self {
+        Self {
+            title: sort_name(&song.title),
+            artist: sort_name(&song.artist),
+            album: sort_name(&song.album),
+            album_artist: sort_name(song.album_artist.as_deref().unwrap_or(&song.artist)),
+            genre: sort_name(song.genre.as_deref().unwrap_or("")),
+        }
+    }
+}
+
+mod imp {
+    use super::*;
+    use std::cell::{OnceCell, RefCell};
+
+    #[derive(Default)]
+    pub struct SongObject {
+        pub song: RefCell<Option<Song>>,
+        pub sort_keys: OnceCell<SortKeys>,
+    }
+
+    #[glib::object_subclass]
+    impl ObjectSubclass for SongObject {
+        const NAME: &'static str = "LibrefySongObject";
+        type Type = super::SongObject;
+    }
+
+    impl ObjectImpl for SongObject {}
+}
+
+// Objeto GLib que embrulha uma `Song` para usar em modelos `gio::ListStore`
+glib::wrapper! {
+    pub struct SongObject(ObjectSubclass<imp::SongObject>);
+}
+
+impl SongObject {
+    pub fn new(song: Song) -> Self {
+        let object: Self = glib::Object::new();
+        object.imp().song.replace(Some(song));
+        object
+    }
+
+    pub fn song(&self) -> Song {
+        self.imp()
+            .song
+            .borrow()
+            .clone()
+            .expect("SongObject always holds a song")
+    }
+
+    // Acesso sem clonar, para ordenação e filtros
+    pub fn with_song<R>(&self, f: impl FnOnce(&Song) -> R) -> R {
+        f(self
+            .imp()
+            .song
+            .borrow()
+            .as_ref()
+            .expect("SongObject always holds a song"))
+    }
+
+    pub fn sort_keys(&self) -> &SortKeys {
+        self.imp().sort_keys.get_or_init(|| self.with_song(SortKeys::new))
+    }
+}
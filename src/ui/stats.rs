@@ -0,0 +1,233 @@
+use gtk4::prelude::*;
+use gtk4::{
+    ApplicationWindow, Box, Button, DropDown, FileDialog, Label, Orientation, ScrolledWindow,
+    SpinButton, Window
+};
+use std::cell::RefCell;
+use std::fs;
+use std::rc::Rc;
+
+use crate::AppState;
+use crate::library::history::{self, PlayEvent};
+use crate::library::stats::{
+    current_year, library_stats, listening_stats, year_in_review, CategoryCount, StatsPeriod, TopEntry
+};
+use crate::library::unix_now;
+
+const TOP_LIMIT: usize = 10;
+
+fn format_listening_time(seconds: u64) -> String {
+    let hours = seconds / 3600;
+    let minutes = (seconds % 3600) / 60;
+    if hours > 0 {
+        format!("{} h {} min", hours, minutes)
+    } else {
+        format!("{} min", minutes)
+    }
+}
+
+fn section(title: &str) -> (Box, Box) {
+    let container = Box::new(Orientation::Vertical, 5);
+    container.set_hexpand(true);
+    let heading = Label::new(Some(title));
+    heading.add_css_class("heading");
+    heading.set_halign(gtk4::Align::Start);
+    container.append(&heading);
+
+    let rows = Box::new(Orientation::Vertical, 2);
+    container.append(&rows);
+    (container, rows)
+}
+
+fn add_row(rows: &Box, text: &str) {
+    let label = Label::new(Some(text));
+    label.set_halign(gtk4::Align::Start);
+    label.set_ellipsize(gtk4::pango::EllipsizeMode::End);
+    rows.append(&label);
+}
+
+fn fill_top(rows: &Box, entries: &[TopEntry]) {
+    while let Some(child) = rows.first_child() {
+        rows.remove(&child);
+    }
+    if entries.is_empty() {
+        add_row(rows, "Nothing played yet");
+    }
+    for (rank, entry) in entries.iter().enumerate() {
+        add_row(rows, &format!("{}. {} ({} plays)", rank + 1, entry.name, entry.plays));
+    }
+}
+
+fn fill_categories(rows: &Box, categories: &[CategoryCount]) {
+    for category in categories.iter().take(TOP_LIMIT) {
+        add_row(rows, &format!(
+            "{}: {} songs, {:.1} MB",
+            category.name,
+            category.songs,
+            category.bytes as f64 / 1_048_576.0
+        ));
+    }
+}
+
+// Formatos de exportação: nome na lista e extensão
+const EXPORT_FORMATS: [(&str, &str); 2] = [("JSON", "json"), ("CSV", "csv")];
+
+fn export_year_in_review(
+    parent: &Window,
+    events: Rc<Vec<PlayEvent>>,
+    state: &Rc<RefCell<AppState>>,
+    year: i32,
+    format: usize,
+) {
+    let (name, extension) = EXPORT_FORMATS[format.min(EXPORT_FORMATS.len() - 1)];
+    let is_csv = extension == "csv";
+    let filter = gtk4::FileFilter::new();
+    filter.set_name(Some(name));
+    filter.add_pattern(&format!("*.{}", extension));
+    let filters = gio::ListStore::new::<gtk4::FileFilter>();
+    filters.append(&filter);
+
+    let dialog = FileDialog::builder()
+        .title("Export Year in Review")
+        .modal(true)
+        .initial_name(format!("librefy-{}-review.{}", year, extension))
+        .filters(&filters)
+        .default_filter(&filter)
+        .build();
+
+    let songs = state.borrow().library.songs.clone();
+    dialog.save(Some(parent), gio::Cancellable::NONE, move |result| {
+        let Some(path) = result.ok().and_then(|file| file.path()) else {
+            return;
+        };
+        let review = year_in_review(&events, &songs, year);
+        // O formato é o escolhido na janela, não a extensão digitada
+        let contents = if is_csv { Ok(review.to_csv()) } else { review.to_json() };
+
+        match contents.and_then(|contents| Ok(fs::write(&path, contents)?)) {
+            Ok(_) => println!("Exported year in review to {}", path.display()),
+            Err(e) => eprintln!("Failed to export year in review: {}", e),
+        }
+    });
+}
+
+pub fn show_stats_window(parent: &ApplicationWindow, state: &Rc<RefCell<AppState>>) {
+    let window = Window::builder()
+        .title("Listening Statistics")
+        .transient_for(parent)
+        .default_width(900)
+        .default_height(650)
+        .build();
+
+    let events = Rc::new(history::load());
+
+    let content_area = Box::new(Orientation::Vertical, 15);
+    content_area.set_margin_top(15);
+    content_area.set_margin_bottom(15);
+    content_area.set_margin_start(15);
+    content_area.set_margin_end(15);
+
+    // Estatísticas de escuta do período escolhido
+    let labels: Vec<&str> = StatsPeriod::ALL.iter().map(|p| p.label()).collect();
+    let period_dropdown = DropDown::from_strings(&labels);
+    let summary_label = Label::new(None);
+    summary_label.set_halign(gtk4::Align::Start);
+    summary_label.set_hexpand(true);
+
+    let period_box = Box::new(Orientation::Horizontal, 10);
+    period_box.append(&period_dropdown);
+    period_box.append(&summary_label);
+
+    let (artists_section, artists_rows) = section("Top Artists");
+    let (albums_section, albums_rows) = section("Top Albums");
+    let (tracks_section, tracks_rows) = section("Top Tracks");
+    let top_box = Box::new(Orientation::Horizontal, 20);
+    top_box.set_homogeneous(true);
+    top_box.append(&artists_section);
+    top_box.append(&albums_section);
+    top_box.append(&tracks_section);
+
+    let update = {
+        let events = Rc::clone(&events);
+        move |period: StatsPeriod| {
+            let stats = listening_stats(&events, period.since(unix_now()), u64::MAX, TOP_LIMIT);
+            summary_label.set_text(&format!(
+                "{} plays · {} skips · {} listened",
+                stats.plays,
+                stats.skips,
+                format_listening_time(stats.total_seconds)
+            ));
+            fill_top(&artists_rows, &stats.top_artists);
+            fill_top(&albums_rows, &stats.top_albums);
+            fill_top(&tracks_rows, &stats.top_tracks);
+        }
+    };
+    update(StatsPeriod::ALL[0]);
+    period_dropdown.connect_selected_notify(move |dropdown| {
+        update(StatsPeriod::ALL[dropdown.selected() as usize]);
+    });
+
+    // Tamanho da biblioteca
+    let library = library_stats(&state.borrow().library.songs);
+    let library_label = Label::new(Some(&format!(
+        "{} songs · {} of music · {:.1} GB",
+        library.songs,
+        format_listening_time(library.total_seconds),
+        library.total_bytes as f64 / 1_073_741_824.0
+    )));
+    library_label.set_halign(gtk4::Align::Start);
+
+    let (formats_section, formats_rows) = section("By Format");
+    fill_categories(&formats_rows, &library.by_format);
+    let (genres_section, genres_rows) = section("By Genre");
+    fill_categories(&genres_rows, &library.by_genre);
+    let library_box = Box::new(Orientation::Horizontal, 20);
+    library_box.set_homogeneous(true);
+    library_box.append(&formats_section);
+    library_box.append(&genres_section);
+
+    // Retrospectiva do ano
+    let year_spin = SpinButton::with_range(2000.0, f64::from(current_year()), 1.0);
+    year_spin.set_value(f64::from(current_year()));
+    let format_dropdown = DropDown::from_strings(&EXPORT_FORMATS.map(|(name, _)| name));
+    let export_btn = Button::with_label("Export Year in Review…");
+    let window_clone = window.clone();
+    let state_clone = Rc::clone(state);
+    let year_spin_clone = year_spin.clone();
+    let format_clone = format_dropdown.clone();
+    export_btn.connect_clicked(move |_| {
+        export_year_in_review(
+            &window_clone,
+            Rc::clone(&events),
+            &state_clone,
+            year_spin_clone.value_as_int(),
+            format_clone.selected() as usize,
+        );
+    });
+
+    let review_box = Box::new(Orientation::Horizontal, 10);
+    review_box.append(&Label::new(Some("Year in review:")));
+    review_box.append(&year_spin);
+    review_box.append(&format_dropdown);
+    review_box.append(&export_btn);
+
+    let library_heading = Label::new(Some("Library"));
+    library_heading.add_css_class("title-4");
+    library_heading.set_halign(gtk4::Align::Start);
+    let listening_heading = Label::new(Some("Listening"));
+    listening_heading.add_css_class("title-4");
+    listening_heading.set_halign(gtk4::Align::Start);
+
+    content_area.append(&listening_heading);
+    content_area.append(&period_box);
+    content_area.append(&top_box);
+    content_area.append(&library_heading);
+    content_area.append(&library_label);
+    content_area.append(&library_box);
+    content_area.append(&review_box);
+
+    let scroll = ScrolledWindow::new();
+    scroll.set_child(Some(&content_area));
+    window.set_child(Some(&scroll));
+    window.present();
+}
@@ -0,0 +1,313 @@
+use gtk4::prelude::*;
+use gtk4::{ApplicationWindow, Box, Button, Entry, FileDialog, Grid, Label, Orientation, Window};
+use std::cell::RefCell;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use crate::AppState;
+use crate::library::tags::{CoverEdit, TagEdit};
+use crate::library::Song;
+use crate::ui::song_list::replace_songs;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum TagField {
+    Title,
+    Artist,
+    Album,
+    AlbumArtist,
+    Genre,
+    Year,
+    Track,
+    Disc,
+}
+
+impl TagField {
+    const ALL: [TagField; 8] = [
+        TagField::Title,
+        TagField::Artist,
+        TagField::Album,
+        TagField::AlbumArtist,
+        TagField::Genre,
+        TagField::Year,
+        TagField::Track,
+        TagField::Disc,
+    ];
+
+    fn label(&self) -> &'static str {
+        match self {
+            TagField::Title => "Title",
+            TagField::Artist => "Artist",
+            TagField::Album => "Album",
+            TagField::AlbumArtist => "Album Artist",
+            TagField::Genre => "Genre",
+            TagField::Year => "Year",
+            TagField::Track => "Track",
+            TagField::Disc => "Disc",
+        }
+    }
+
+    fn value(&self, song: &Song) -> String {
+        let number = |n: Option<u32>| n.map(|n| n.to_string()).unwrap_or_default();
+        match self {
+            TagField::Title => song.title.clone(),
+            TagField::Artist => song.artist.clone(),
+            TagField::Album => song.album.clone(),
+            TagField::AlbumArtist => song.album_artist.clone().unwrap_or_default(),
+            TagField::Genre => song.genre.clone().unwrap_or_default(),
+            TagField::Year => number(song.year),
+            TagField::Track => number(song.track_number),
+            TagField::Disc => number(song.disc_number),
+        }
+    }
+}
+
+struct FieldEntry {
+    field: TagField,
+    entry: Entry,
+    original: Option<String>, // None = os arquivos têm valores diferentes
+}
+
+impl FieldEntry {
+    // Só o que o usuário mudou entra na edição
+    fn changed_text(&self) -> Option<String> {
+        let text = self.entry.text().to_string();
+        match &self.original {
+            Some(original) if *original == text => None,
+            None if text.is_empty() => None,
+            _ => Some(text),
+        }
+    }
+}
+
+fn parse_number(text: &str) -> Option<Option<u32>> {
+    let text = text.trim();
+    if text.is_empty() {
+        Some(None)
+    } else {
+        text.parse().ok().map(Some)
+    }
+}
+
+fn build_edit(fields: &[FieldEntry], cover: CoverEdit) -> Result<TagEdit, TagField> {
+    let mut edit = TagEdit {
+        cover,
+        ..TagEdit::default()
+    };
+
+    for field in fields {
+        let Some(text) = field.changed_text() else {
+            continue;
+        };
+        match field.field {
+            TagField::Title => edit.title = Some(text),
+            TagField::Artist => edit.artist = Some(text),
+            TagField::Album => edit.album = Some(text),
+            TagField::AlbumArtist => edit.album_artist = Some(text),
+            TagField::Genre => edit.genre = Some(text),
+            TagField::Year => edit.year = Some(parse_number(&text).ok_or(field.field)?),
+            TagField::Track => edit.track_number = Some(parse_number(&text).ok_or(field.field)?),
+            TagField::Disc => edit.disc_number = Some(parse_number(&text).ok_or(field.field)?),
+        }
+    }
+
+    Ok(edit)
+}
+
+fn cover_description(cover: &CoverEdit) -> String {
+    match cover {
+        CoverEdit::Keep => "Keep current cover".to_string(),
+        CoverEdit::Set(path) => format!("Embed {}", path.display()),
+        CoverEdit::Remove => "Remove embedded cover".to_string(),
+    }
+}
+
+// Edita uma ou várias músicas; campos com valores diferentes ficam vazios e são mantidos
+pub fn show_tag_editor<F>(parent: &ApplicationWindow, state: &Rc<RefCell<AppState>>, songs: Vec<Song>, on_saved: F)
+where
+    F: Fn() + 'static,
+{
+    if songs.is_empty() {
+        return;
+    }
+
+    let title = if songs.len() == 1 {
+        "Edit Tags".to_string()
+    } else {
+        format!("Edit Tags ({} songs)", songs.len())
+    };
+    let dialog = Window::builder()
+        .title(title)
+        .transient_for(parent)
+        .modal(true)
+        .default_width(450)
+        .build();
+
+    let content_area = Box::new(Orientation::Vertical, 10);
+    content_area.set_margin_top(15);
+    content_area.set_margin_bottom(15);
+    content_area.set_margin_start(15);
+    content_area.set_margin_end(15);
+
+    let grid = Grid::new();
+    grid.set_row_spacing(5);
+    grid.set_column_spacing(10);
+
+    let mut fields = Vec::new();
+    for (row, field) in TagField::ALL.iter().enumerate() {
+        let first = field.value(&songs[0]);
+        let original = songs
+            .iter()
+            .all(|song| field.value(song) == first)
+            .then_some(first);
+
+        let entry = Entry::new();
+        entry.set_hexpand(true);
+        match &original {
+            Some(value) => entry.set_text(value),
+            None => entry.set_placeholder_text(Some("Keep (multiple values)")),
+        }
+
+        let label = Label::new(Some(field.label()));
+        label.set_halign(gtk4::Align::End);
+        grid.attach(&label, 0, row as i32, 1, 1);
+        grid.attach(&entry, 1, row as i32, 1, 1);
+
+        fields.push(FieldEntry {
+            field: *field,
+            entry,
+            original,
+        });
+    }
+
+    // Capa
+    let cover = Rc::new(RefCell::new(CoverEdit::Keep));
+    let cover_label = Label::new(Some(&cover_description(&CoverEdit::Keep)));
+    cover_label.set_halign(gtk4::Align::Start);
+    cover_label.set_ellipsize(gtk4::pango::EllipsizeMode::Middle);
+
+    let cover_buttons = Box::new(Orientation::Horizontal, 10);
+    let choose_cover_btn = Button::with_label("Choose Image…");
+    let remove_cover_btn = Button::with_label("Remove Cover");
+    let keep_cover_btn = Button::with_label("Keep");
+    cover_buttons.append(&choose_cover_btn);
+    cover_buttons.append(&remove_cover_btn);
+    cover_buttons.append(&keep_cover_btn);
+
+    let set_cover = {
+        let cover = Rc::clone(&cover);
+        let cover_label = cover_label.clone();
+        Rc::new(move |edit: CoverEdit| {
+            cover_label.set_text(&cover_description(&edit));
+            *cover.borrow_mut() = edit;
+        })
+    };
+
+    let dialog_clone = dialog.clone();
+    let set_cover_clone = Rc::clone(&set_cover);
+    choose_cover_btn.connect_clicked(move |_| {
+        let filter = gtk4::FileFilter::new();
+        filter.set_name(Some("Images"));
+        filter.add_mime_type("image/jpeg");
+        filter.add_mime_type("image/png");
+        let filters = gio::ListStore::new::<gtk4::FileFilter>();
+        filters.append(&filter);
+
+        let chooser = FileDialog::builder()
+            .title("Select Cover Image")
+            .modal(true)
+            .filters(&filters)
+            .build();
+
+        let set_cover = Rc::clone(&set_cover_clone);
+        chooser.open(Some(&dialog_clone), gio::Cancellable::NONE, move |result| {
+            match result {
+                Ok(file) => {
+                    if let Some(path) = file.path() {
+                        set_cover(CoverEdit::Set(path));
+                    }
+                }
+                Err(e) => {
+                    eprintln!("Error selecting file: {}", e);
+                }
+            }
+        });
+    });
+
+    let set_cover_clone = Rc::clone(&set_cover);
+    remove_cover_btn.connect_clicked(move |_| set_cover_clone(CoverEdit::Remove));
+    let set_cover_clone = Rc::clone(&set_cover);
+    keep_cover_btn.connect_clicked(move |_| set_cover_clone(CoverEdit::Keep));
+
+    let save_btn = Button::with_label("Save");
+    save_btn.add_css_class("suggested-action");
+
+    let state_clone = Rc::clone(state);
+    let dialog_clone = dialog.clone();
+    let parent_clone = parent.clone();
+    let paths: Vec<PathBuf> = songs.iter().map(|song| song.file_path.clone()).collect();
+    save_btn.connect_clicked(move |_| {
+        for field in &fields {
+            field.entry.remove_css_class("error");
+        }
+        let edit = match build_edit(&fields, cover.borrow().clone()) {
+            Ok(edit) => edit,
+            Err(invalid) => {
+                if let Some(field) = fields.iter().find(|f| f.field == invalid) {
+                    field.entry.add_css_class("error");
+                }
+                return;
+            }
+        };
+
+        dialog_clone.close();
+
+        let (errors, updated, store) = {
+            let mut app_state = state_clone.borrow_mut();
+            let errors = app_state.library.edit_tags(&paths, &edit);
+            let updated: Vec<Song> = app_state
+                .library
+                .songs
+                .iter()
+                .filter(|song| paths.contains(&song.file_path))
+                .cloned()
+                .collect();
+            (errors, updated, app_state.song_store.clone())
+        };
+        replace_songs(&store, &updated);
+        println!("Saved tags for {} songs", paths.len() - errors.len());
+        on_saved();
+
+        if !errors.is_empty() {
+            let detail: Vec<String> = errors
+                .iter()
+                .map(|(path, e)| format!("{}: {}", path.display(), e))
+                .collect();
+            let alert = gtk4::AlertDialog::builder()
+                .modal(true)
+                .message(format!("Could not write tags to {} files", errors.len()))
+                .detail(detail.join("\n"))
+                .build();
+            alert.show(Some(&parent_clone));
+        }
+    });
+
+    let cancel_btn = Button::with_label("Cancel");
+    let dialog_clone = dialog.clone();
+    cancel_btn.connect_clicked(move |_| {
+        dialog_clone.close();
+    });
+
+    content_area.append(&grid);
+    content_area.append(&Label::new(Some("Cover Art:")));
+    content_area.append(&cover_label);
+    content_area.append(&cover_buttons);
+
+    let button_box = Box::new(Orientation::Horizontal, 10);
+    button_box.set_halign(gtk4::Align::End);
+    button_box.append(&cancel_btn);
+    button_box.append(&save_btn);
+    content_area.append(&button_box);
+
+    dialog.set_child(Some(&content_area));
+    dialog.present();
+}
@@ -0,0 +1,302 @@
+use gtk4::gdk::RGBA;
+use gtk4::gdk_pixbuf::{InterpType, Pixbuf};
+use gtk4::prelude::*;
+use gtk4::CssProvider;
+use libadwaita as adw;
+use std::cell::{Cell, RefCell};
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use crate::AppState;
+use crate::config::data_dir;
+use crate::library::{tags, Song};
+
+// Temas do usuário ficam acima do embutido, e a cor de destaque acima de todos
+const THEME_PRIORITY: u32 = gtk4::STYLE_PROVIDER_PRIORITY_APPLICATION + 1;
+const ACCENT_PRIORITY: u32 = gtk4::STYLE_PROVIDER_PRIORITY_APPLICATION + 2;
+
+// $XDG_DATA_HOME/librefy/themes
+pub fn themes_dir() -> PathBuf {
+    data_dir().join("themes")
+}
+
+pub fn theme_path(name: &str) -> PathBuf {
+    themes_dir().join(format!("{}.css", name))
+}
+
+// Nomes dos arquivos .css da pasta de temas, sem a extensão
+pub fn available_themes() -> Vec<String> {
+    let Ok(entries) = std::fs::read_dir(themes_dir()) else {
+        return Vec::new();
+    };
+    let mut names: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("css")))
+        .filter_map(|path| path.file_stem().and_then(|stem| stem.to_str()).map(str::to_string))
+        .collect();
+    names.sort_by_key(|name| name.to_lowercase());
+    names
+}
+
+fn report_errors(provider: &CssProvider, source: &'static str) {
+    provider.connect_parsing_error(move |_, section, error| {
+        eprintln!("{} {}: {}", source, section.to_str(), error);
+    });
+}
+
+pub struct ThemeManager {
+    palette: CssProvider,
+    theme: CssProvider,
+    accent: CssProvider,
+    theme_path: RefCell<Option<PathBuf>>,
+    // Mantém o monitor vivo enquanto o tema estiver em uso
+    monitor: RefCell<Option<gio::FileMonitor>>,
+    accent_color: Cell<Option<RGBA>>,
+    // Cor da última capa lida, para não decodificar a imagem de novo
+    cover_color: RefCell<Option<(PathBuf, Option<RGBA>)>>,
+}
+
+impl ThemeManager {
+    pub fn new() -> Rc<Self> {
+        let theme = CssProvider::new();
+        report_errors(&theme, "Theme error in");
+        Rc::new(Self {
+            palette: CssProvider::new(),
+            theme,
+            accent: CssProvider::new(),
+            theme_path: RefCell::new(None),
+            monitor: RefCell::new(None),
+            accent_color: Cell::new(None),
+            cover_color: RefCell::new(None),
+        })
+    }
+
+    pub fn install(self: &Rc<Self>) {
+        let display = gtk4::gdk::Display::default().expect("Could not connect to a display.");
+        let provider = CssProvider::new();
+        provider.load_from_string(include_str!("../../data/themes/librefy.css"));
+        let add = |provider: &CssProvider, priority: u32| {
+            gtk4::style_context_add_provider_for_display(&display, provider, priority);
+        };
+        add(&provider, gtk4::STYLE_PROVIDER_PRIORITY_APPLICATION);
+        add(&self.palette, gtk4::STYLE_PROVIDER_PRIORITY_APPLICATION);
+        add(&self.theme, THEME_PRIORITY);
+        add(&self.accent, ACCENT_PRIORITY);
+
+        // As cores nomeadas do verde folha, trocadas quando o libadwaita muda entre claro e escuro
+        self.load_palette();
+        let this = Rc::clone(self);
+        adw::StyleManager::default().connect_dark_notify(move |_| {
+            this.load_palette();
+            this.load_accent();
+        });
+    }
+
+    fn load_palette(&self) {
+        if adw::StyleManager::default().is_dark() {
+            self.palette.load_from_string(include_str!("../../data/themes/palette-dark.css"));
+        } else {
+            self.palette.load_from_string(include_str!("../../data/themes/palette-light.css"));
+        }
+    }
+
+    // None volta para o tema embutido
+    pub fn set_theme(self: &Rc<Self>, name: Option<&str>) {
+        self.monitor.replace(None);
+        let path = name.map(theme_path);
+        self.theme_path.replace(path.clone());
+        self.load_theme();
+
+        // Recarrega ao salvar o arquivo, para quem está criando o tema
+        let Some(path) = path else {
+            return;
+        };
+        match gio::File::for_path(&path).monitor_file(gio::FileMonitorFlags::NONE, gio::Cancellable::NONE) {
+            Ok(monitor) => {
+                let this = Rc::downgrade(self);
+                monitor.connect_changed(move |_, _, _, event| {
+                    if matches!(
+                        event,
+                        gio::FileMonitorEvent::ChangesDoneHint | gio::FileMonitorEvent::Created | gio::FileMonitorEvent::Deleted
+                    ) {
+                        if let Some(this) = this.upgrade() {
+                            this.load_theme();
+                        }
+                    }
+                });
+                self.monitor.replace(Some(monitor));
+            }
+            Err(e) => eprintln!("Failed to watch theme {}: {}", path.display(), e),
+        }
+    }
+
+    fn load_theme(&self) {
+        let path = self.theme_path.borrow();
+        let css = match path.as_deref() {
+            Some(path) => std::fs::read_to_string(path).unwrap_or_else(|e| {
+                eprintln!("Failed to load theme {}: {}", path.display(), e);
+                String::new()
+            }),
+            None => String::new(),
+        };
+        self.theme.load_from_string(&css);
+        if let Some(path) = path.as_deref() {
+            println!("Loaded theme {}", path.display());
+        }
+    }
+
+    // None deixa a cor do tema
+    pub fn set_accent(&self, color: Option<RGBA>) {
+        self.accent_color.set(color);
+        self.load_accent();
+    }
+
+    fn load_accent(&self) {
+        let css = self
+            .accent_color
+            .get()
+            .map(|color| accent_css(color, adw::StyleManager::default().is_dark()))
+            .unwrap_or_default();
+        self.accent.load_from_string(&css);
+    }
+}
+
+// Cor de destaque: a da capa, se ativado, senão a escolhida nas preferências
+// A capa é lida fora da thread da interface; até ela chegar fica a cor anterior
+pub fn refresh_accent(state: &Rc<RefCell<AppState>>) {
+    let (theme, song, chosen) = {
+        let app_state = state.borrow();
+        let settings = &app_state.settings;
+        let song = app_state.current_song.clone().filter(|_| settings.accent_from_cover);
+        let chosen = settings.accent_color.as_deref().and_then(|color| RGBA::parse(color).ok());
+        (Rc::clone(&app_state.theme), song, chosen)
+    };
+    let Some(song) = song else {
+        theme.set_accent(chosen);
+        return;
+    };
+    let cached = theme
+        .cover_color
+        .borrow()
+        .as_ref()
+        .filter(|(path, _)| *path == song.file_path)
+        .map(|(_, color)| *color);
+    if let Some(cover) = cached {
+        theme.set_accent(cover.or(chosen));
+        return;
+    }
+
+    let path = song.file_path.clone();
+    let handle = gio::spawn_blocking(move || cover_color(&song));
+    let state = Rc::clone(state);
+    glib::MainContext::default().spawn_local(async move {
+        let cover = handle.await.ok().flatten();
+        // Descarta capas de músicas que já passaram
+        let current = state.borrow().current_song.as_ref().is_some_and(|song| song.file_path == path);
+        if current {
+            theme.cover_color.replace(Some((path, cover)));
+            refresh_accent(&state);
+        }
+    });
+}
+
+fn rgb_to_hsl(color: RGBA) -> (f32, f32, f32) {
+    let (r, g, b) = (color.red(), color.green(), color.blue());
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let lightness = (max + min) / 2.0;
+    let delta = max - min;
+    if delta == 0.0 {
+        return (0.0, 0.0, lightness);
+    }
+    let saturation = delta / (1.0 - (2.0 * lightness - 1.0).abs());
+    let hue = if max == r {
+        60.0 * ((g - b) / delta).rem_euclid(6.0)
+    } else if max == g {
+        60.0 * ((b - r) / delta + 2.0)
+    } else {
+        60.0 * ((r - g) / delta + 4.0)
+    };
+    (hue, saturation, lightness)
+}
+
+fn hsl_to_hex(hue: f32, saturation: f32, lightness: f32) -> String {
+    let chroma = (1.0 - (2.0 * lightness - 1.0).abs()) * saturation;
+    let x = chroma * (1.0 - ((hue / 60.0).rem_euclid(2.0) - 1.0).abs());
+    let m = lightness - chroma / 2.0;
+    let (r, g, b) = match hue as u32 {
+        0..=59 => (chroma, x, 0.0),
+        60..=119 => (x, chroma, 0.0),
+        120..=179 => (0.0, chroma, x),
+        180..=239 => (0.0, x, chroma),
+        240..=299 => (x, 0.0, chroma),
+        _ => (chroma, 0.0, x),
+    };
+    let channel = |value: f32| ((value + m).clamp(0.0, 1.0) * 255.0).round() as u8;
+    format!("#{:02X}{:02X}{:02X}", channel(r), channel(g), channel(b))
+}
+
+// Deriva as variações da cor com contraste suficiente para texto branco e para o fundo
+fn accent_css(color: RGBA, dark: bool) -> String {
+    let (hue, saturation, lightness) = rgb_to_hsl(color);
+    let (background, text, hover, end) = if dark {
+        let background = lightness.clamp(0.38, 0.5);
+        (background, 0.68, background + 0.08, background + 0.12)
+    } else {
+        let background = lightness.clamp(0.28, 0.42);
+        (background, 0.28, background + 0.08, background + 0.1)
+    };
+    let shade = |lightness: f32| hsl_to_hex(hue, saturation, lightness);
+    format!(
+        "@define-color accent_bg_color {};\n\
+         @define-color accent_color {};\n\
+         @define-color accent_fg_color #FFFFFF;\n\
+         @define-color librefy_title_color {};\n\
+         @define-color librefy_accent_hover_color {};\n\
+         @define-color librefy_accent_end_color {};\n",
+        shade(background),
+        shade(text),
+        shade(background),
+        shade(hover),
+        shade(end),
+    )
+}
+
+fn cover_pixbuf(song: &Song) -> Option<Pixbuf> {
+    if let Some(path) = song.get_cover_path().filter(|path| path.exists()) {
+        return Pixbuf::from_file(path).ok();
+    }
+    let data = tags::read_cover(&song.file_path)?;
+    let stream = gio::MemoryInputStream::from_bytes(&glib::Bytes::from_owned(data));
+    Pixbuf::from_stream(&stream, gio::Cancellable::NONE).ok()
+}
+
+// Média das cores da capa, dando mais peso às mais vivas; capas em tons de cinza não têm cor
+fn cover_color(song: &Song) -> Option<RGBA> {
+    let pixbuf = cover_pixbuf(song)?.scale_simple(32, 32, InterpType::Bilinear)?;
+    let bytes = pixbuf.read_pixel_bytes();
+    let channels = pixbuf.n_channels() as usize;
+    let stride = pixbuf.rowstride() as usize;
+
+    let (mut red, mut green, mut blue, mut total) = (0.0, 0.0, 0.0, 0.0);
+    for y in 0..pixbuf.height() as usize {
+        for x in 0..pixbuf.width() as usize {
+            let offset = y * stride + x * channels;
+            let Some(pixel) = bytes.get(offset..offset + 3) else {
+                continue;
+            };
+            let [r, g, b] = [pixel[0], pixel[1], pixel[2]].map(|value| f32::from(value) / 255.0);
+            let max = r.max(g).max(b);
+            let weight = (max - r.min(g).min(b)) * max;
+            red += r * weight;
+            green += g * weight;
+            blue += b * weight;
+            total += weight;
+        }
+    }
+    if total < 1.0 {
+        return None;
+    }
+    Some(RGBA::new(red / total, green / total, blue / total, 1.0))
+}